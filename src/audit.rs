@@ -0,0 +1,97 @@
+//! The audit trail kept with `--log <FILE>`, or `IRM_LOG` when that isn't
+//! given: one line per removal, restore from the trash, purge from it and
+//! failure, with a UTC timestamp and the absolute path, tab-separated. The
+//! file is appended to and flushed after every line, so a crash loses
+//! nothing already done.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{self, Path},
+    time::SystemTime,
+};
+
+use crate::format;
+
+/// Where the log file is taken from when `--log` isn't given
+pub const ENV_VAR: &str = "IRM_LOG";
+
+/// Something worth a line in the log
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    /// Deleted for good
+    Removed,
+    /// Moved to the trash
+    Trashed,
+    /// Left in place by `--dry-run`
+    WouldRemove,
+    /// Put back from the trash
+    Restored,
+    /// Deleted from the trash for good
+    Purged,
+    /// A removal that failed, and why
+    RemoveFailed(&'a str),
+    /// A restore from the trash that failed, and why
+    RestoreFailed(&'a str),
+    /// Deleting from the trash that failed, and why
+    PurgeFailed(&'a str),
+}
+
+impl Event<'_> {
+    fn name(self) -> &'static str {
+        match self {
+            Event::Removed => "removed",
+            Event::Trashed => "trashed",
+            Event::WouldRemove => "would-remove",
+            Event::Restored => "restored",
+            Event::Purged => "purged",
+            Event::RemoveFailed(_) => "remove-failed",
+            Event::RestoreFailed(_) => "restore-failed",
+            Event::PurgeFailed(_) => "purge-failed",
+        }
+    }
+}
+
+/// The open log, or nothing when logging wasn't asked for
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    out: Option<BufWriter<File>>,
+}
+
+impl AuditLog {
+    pub fn open(file: &str) -> io::Result<Self> {
+        let out = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file)
+            .map_err(|err| {
+                io::Error::new(err.kind(), format!("could not open log {file}: {err}"))
+            })?;
+        Ok(Self {
+            out: Some(BufWriter::new(out)),
+        })
+    }
+
+    /// Append a line for `event` on `path`
+    pub fn record(&mut self, event: Event, path: &str) -> io::Result<()> {
+        let Some(out) = &mut self.out else {
+            return Ok(());
+        };
+        let path = path::absolute(Path::new(path)).unwrap_or_else(|_| path.into());
+        write!(
+            out,
+            "{}\t{}\t{}",
+            format::rfc3339(SystemTime::now()),
+            event.name(),
+            path.display()
+        )?;
+        if let Event::RemoveFailed(reason)
+        | Event::RestoreFailed(reason)
+        | Event::PurgeFailed(reason) = event
+        {
+            write!(out, "\t{reason}")?;
+        }
+        writeln!(out)?;
+        out.flush()
+    }
+}
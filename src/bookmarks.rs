@@ -0,0 +1,76 @@
+//! Directories bookmarked with <m>, kept as one absolute path per line in
+//! `bookmarks` next to the config file (see `config`)
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config;
+
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    paths: Vec<String>,
+    /// Where they're saved, unless there's no home to save them in
+    file: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    /// The saved bookmarks. A missing or unreadable file just means there
+    /// are none yet.
+    pub fn load() -> Self {
+        let file = config::config_dir().map(|dir| dir.join("bookmarks"));
+        let paths = file
+            .as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { paths, file }
+    }
+
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Bookmark `path`, or forget it if it already is one. Returns whether
+    /// it's bookmarked now.
+    pub fn toggle(&mut self, path: &Path) -> io::Result<bool> {
+        let path = path.to_string_lossy().into_owned();
+        let added = if self.paths.contains(&path) {
+            self.paths.retain(|bookmark| *bookmark != path);
+            false
+        } else {
+            self.paths.push(path);
+            true
+        };
+        self.save()?;
+        Ok(added)
+    }
+
+    pub fn remove(&mut self, path: &str) -> io::Result<()> {
+        self.paths.retain(|bookmark| bookmark != path);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(file) = &self.file else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no home directory to keep bookmarks in",
+            ));
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut contents = self.paths.join("\n");
+        contents.push('\n');
+        fs::write(file, contents)
+    }
+}
@@ -0,0 +1,320 @@
+use std::{env, ffi::OsString, path::PathBuf};
+
+use crate::format::Locale;
+
+const USAGE: &str = "Usage: irm [OPTIONS] [PATH]
+
+Arguments:
+  [PATH]  Directory to browse [default: .]
+
+Options:
+      --no-follow-root                 Keep a symlinked PATH as-is instead of resolving it
+      --max-depth <N>                  Open directories at most N levels below PATH
+      --clear-confirm-threshold <N>    Ask before deselecting more than N items [default: 10]
+      --atime                          Show each entry's last access time
+      --du                             Sort by size and show each entry's share of its directory
+      --script <FILE>                  Run commands from FILE (`-` for stdin) instead of the TUI
+      --since <REF>                    Start with the files added since git REF, or untracked, selected
+      --include <GLOB>                 Only show entries matching GLOB, and the directories leading to them
+      --exclude <GLOB>                 Hide entries matching GLOB, even if they match an --include
+      --trash                          Move removed entries to the system trash instead of deleting them
+      --permanent                      Delete removed entries for good, even if the config says to trash them
+      --dry-run                        Go through the motions of removing without touching the disk
+      --no-dry-run                     Remove for real, even if the config says to only pretend
+      --readonly                       Browse without being able to remove, move, restore or edit anything
+      --no-readonly                    Allow removing, even if the config says to only browse
+  -f, --force                          Remove without asking for confirmation first
+      --print0                         End the removed paths printed on exit with NUL instead of newline
+      --safe-list <FILE>               Never select or remove the absolute paths listed in FILE
+      --log <FILE>                     Append every removal, restore and failure to FILE (or $IRM_LOG)
+      --confirm-outside-links          Ask before opening a symlinked directory that leads outside PATH
+      --follow-symlinks                Expand into symlinked directories when expanding everything
+      --watch                          Print a summary of PATH every --interval seconds instead of the TUI
+      --interval <SECS>                Seconds between --watch summaries [default: 2]
+      --repeat-key <KEY>               Key that repeats the last selection or removal [default: ;]
+      --no-color                       Draw without colors (also when NO_COLOR is set)
+      --color                          Draw with colors, even if NO_COLOR or the config says not to
+      --ascii                          Draw with plain ASCII instead of emoji and box drawing
+      --no-ascii                       Draw with emoji and box drawing, even if the config says ASCII
+      --stats                          Show how many entries are loaded and selected, and their size, in the title
+      --no-stats                       Leave the totals out of the title, even if the config shows them
+      --locale <NAME>                  Number and date format: iso, en-us, en-gb, de, fr [default: iso]
+  -h, --help                           Print this help
+";
+
+/// Options the application was started with
+#[derive(Debug)]
+pub struct Args {
+    pub path: PathBuf,
+    pub follow_root_symlink: bool,
+    pub max_depth: Option<usize>,
+    pub clear_confirm_threshold: usize,
+    pub show_accessed: bool,
+    pub du: bool,
+    pub script: Option<String>,
+    pub watch: bool,
+    pub interval: u64,
+    pub repeat_key: char,
+    pub confirm_outside_links: bool,
+    pub follow_symlinks: bool,
+    pub safe_list: Option<String>,
+    pub log: Option<String>,
+    pub trash: bool,
+    pub permanent: bool,
+    /// This and the other `Option`s below are `None` unless given either
+    /// way, leaving it to the config
+    pub dry_run: Option<bool>,
+    pub readonly: Option<bool>,
+    pub force: bool,
+    pub print0: bool,
+    pub no_color: Option<bool>,
+    pub ascii: Option<bool>,
+    pub stats: Option<bool>,
+    pub since: Option<String>,
+    /// Globs from `--include`, in the order given; see `path_filter`
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub locale: Locale,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("."),
+            follow_root_symlink: true,
+            max_depth: None,
+            clear_confirm_threshold: 10,
+            show_accessed: false,
+            du: false,
+            script: None,
+            watch: false,
+            interval: 2,
+            repeat_key: ';',
+            confirm_outside_links: false,
+            follow_symlinks: false,
+            safe_list: None,
+            log: None,
+            trash: false,
+            permanent: false,
+            dry_run: None,
+            readonly: None,
+            force: false,
+            print0: false,
+            no_color: None,
+            ascii: None,
+            stats: None,
+            since: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            locale: Locale::default(),
+        }
+    }
+}
+
+/// Parse the process arguments, exiting with the usage text on `--help` or
+/// on invalid input
+pub fn parse() -> Args {
+    match parse_from(env::args_os().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{USAGE}");
+            std::process::exit(0);
+        }
+        Err(msg) => {
+            eprint!("irm: {msg}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Returns `Ok(None)` when help was requested. Only PATH may be something
+/// other than UTF-8, as file names can be.
+fn parse_from(mut raw: impl Iterator<Item = OsString>) -> Result<Option<Args>, String> {
+    let mut args = Args::default();
+    let mut path = None;
+
+    while let Some(raw_arg) = raw.next() {
+        let arg = raw_arg.to_string_lossy().into_owned();
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--no-follow-root" => args.follow_root_symlink = false,
+            "--atime" => args.show_accessed = true,
+            "--du" => args.du = true,
+            "--trash" => args.trash = true,
+            "--permanent" => args.permanent = true,
+            "--dry-run" => args.dry_run = Some(true),
+            "--no-dry-run" => args.dry_run = Some(false),
+            "--readonly" => args.readonly = Some(true),
+            "--no-readonly" => args.readonly = Some(false),
+            "-f" | "--force" => args.force = true,
+            "--print0" => args.print0 = true,
+            "--no-color" => args.no_color = Some(true),
+            "--color" => args.no_color = Some(false),
+            "--ascii" => args.ascii = Some(true),
+            "--no-ascii" => args.ascii = Some(false),
+            "--stats" => args.stats = Some(true),
+            "--no-stats" => args.stats = Some(false),
+            "--confirm-outside-links" => args.confirm_outside_links = true,
+            "--follow-symlinks" => args.follow_symlinks = true,
+            "--since" => args.since = Some(parse_value(&arg, raw.next())?),
+            "--include" => args.include.push(parse_value(&arg, raw.next())?),
+            "--exclude" => args.exclude.push(parse_value(&arg, raw.next())?),
+            "--safe-list" => args.safe_list = Some(parse_value(&arg, raw.next())?),
+            "--log" => args.log = Some(parse_value(&arg, raw.next())?),
+            "--script" => args.script = Some(parse_value(&arg, raw.next())?),
+            "--watch" => args.watch = true,
+            "--interval" => {
+                args.interval = parse_value(&arg, raw.next())?;
+                if args.interval == 0 {
+                    return Err("'--interval' must be at least 1".to_string());
+                }
+            }
+            "--max-depth" => {
+                let max_depth = parse_value(&arg, raw.next())?;
+                if max_depth == 0 {
+                    return Err("'--max-depth' must be at least 1".to_string());
+                }
+                args.max_depth = Some(max_depth);
+            }
+            "--repeat-key" => args.repeat_key = parse_value(&arg, raw.next())?,
+            "--locale" => {
+                let name: String = parse_value(&arg, raw.next())?;
+                args.locale = Locale::from_name(&name).ok_or_else(|| {
+                    format!("unknown locale '{name}', expected one of {}", Locale::NAMES)
+                })?;
+            }
+            "--clear-confirm-threshold" => {
+                args.clear_confirm_threshold = parse_value(&arg, raw.next())?
+            }
+            flag if flag.starts_with('-') && flag != "-" => {
+                return Err(format!("unknown option '{flag}'"))
+            }
+            _ if path.is_some() => return Err(format!("unexpected argument '{arg}'")),
+            _ => path = Some(PathBuf::from(raw_arg)),
+        }
+    }
+
+    if let Some(path) = path {
+        args.path = path;
+    }
+    Ok(Some(args))
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<OsString>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("'{flag}' requires a value"))?;
+    let value = value
+        .into_string()
+        .map_err(|value| format!("invalid value '{}' for '{flag}'", value.to_string_lossy()))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{value}' for '{flag}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_strs(raw: &[&str]) -> Result<Option<Args>, String> {
+        parse_from(raw.iter().map(OsString::from))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_path_that_isnt_utf8_is_kept_as_it_is() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let path = OsString::from_vec(b"dir\xff".to_vec());
+        let args = parse_from([OsString::from("--du"), path.clone()].into_iter())
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.path.as_os_str(), path);
+        assert!(args.du);
+
+        let value = OsString::from_vec(b"\xff".to_vec());
+        let err = parse_from([OsString::from("--log"), value].into_iter()).unwrap_err();
+        assert_eq!(err, "invalid value '\u{FFFD}' for '--log'");
+    }
+
+    #[test]
+    fn switches_can_be_turned_off_as_well_as_on() {
+        let args = parse_strs(&["--dry-run", "--no-readonly", "--color", "--no-stats"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(args.dry_run, Some(true));
+        assert_eq!(args.readonly, Some(false));
+        assert_eq!(args.no_color, Some(false));
+        assert_eq!(args.stats, Some(false));
+        assert_eq!(args.ascii, None);
+
+        // the last one given counts
+        let args = parse_strs(&["--ascii", "--no-ascii"]).unwrap().unwrap();
+        assert_eq!(args.ascii, Some(false));
+    }
+
+    #[test]
+    fn the_path_defaults_to_the_current_directory() {
+        let args = parse_strs(&[]).unwrap().unwrap();
+        assert_eq!(args.path, PathBuf::from("."));
+        assert_eq!(
+            parse_strs(&["a", "b"]).unwrap_err(),
+            "unexpected argument 'b'"
+        );
+    }
+
+    #[test]
+    fn options_take_their_values() {
+        let args = parse_strs(&[
+            "--max-depth",
+            "3",
+            "--include",
+            "*.tmp",
+            "--include",
+            "*.log",
+            "--exclude",
+            "keep/**",
+            "--repeat-key",
+            ".",
+            "--interval",
+            "5",
+            "-f",
+            "src",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.max_depth, Some(3));
+        assert_eq!(args.include, ["*.tmp", "*.log"]);
+        assert_eq!(args.exclude, ["keep/**"]);
+        assert_eq!(args.repeat_key, '.');
+        assert_eq!(args.interval, 5);
+        assert!(args.force);
+        assert_eq!(args.path, PathBuf::from("src"));
+
+        // a lone `-` is no option
+        assert_eq!(
+            parse_strs(&["-"]).unwrap().unwrap().path,
+            PathBuf::from("-")
+        );
+        assert!(parse_strs(&["--du", "--help"]).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_arguments_say_what_is_wrong() {
+        let err = |raw: &[&str]| parse_strs(raw).unwrap_err();
+        assert_eq!(err(&["--max-depth"]), "'--max-depth' requires a value");
+        assert_eq!(
+            err(&["--max-depth", "0"]),
+            "'--max-depth' must be at least 1"
+        );
+        assert_eq!(err(&["--interval", "0"]), "'--interval' must be at least 1");
+        assert_eq!(
+            err(&["--max-depth", "deep"]),
+            "invalid value 'deep' for '--max-depth'"
+        );
+        assert_eq!(
+            err(&["--repeat-key", "ab"]),
+            "invalid value 'ab' for '--repeat-key'"
+        );
+        assert_eq!(err(&["--frobnicate"]), "unknown option '--frobnicate'");
+        assert!(err(&["--locale", "tlh"]).starts_with("unknown locale 'tlh'"));
+    }
+}
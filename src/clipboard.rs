@@ -0,0 +1,29 @@
+//! The system clipboard, opened on first use. Where there is none (no
+//! display server, a bare console), copying fails with a message instead.
+
+use std::fmt;
+
+#[derive(Default)]
+pub struct Clipboard {
+    /// Kept open for as long as the app runs, since on X11 the copied text
+    /// only stays available while its owner is around
+    inner: Option<arboard::Clipboard>,
+}
+
+impl fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clipboard")
+            .field("open", &self.inner.is_some())
+            .finish()
+    }
+}
+
+impl Clipboard {
+    pub fn copy(&mut self, text: &str) -> Result<(), arboard::Error> {
+        let clipboard = match &mut self.inner {
+            Some(clipboard) => clipboard,
+            None => self.inner.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_text(text)
+    }
+}
@@ -0,0 +1,451 @@
+//! The optional config file, `~/.config/irm/config.toml` (or
+//! `$XDG_CONFIG_HOME/irm/config.toml`), holding the `[keys]` of `keymap`,
+//! the `[theme]` of `theme`, the `[protect]` patterns of `protect` and
+//! `[sort]`, `[display]`, `[select]`, `[confirm]`, `[restore]` and
+//! `[defaults]` tables:
+//!
+//! ```toml
+//! [sort]
+//! directories_first = false
+//!
+//! [select]
+//! never = ["key", "pem"]
+//!
+//! [display]
+//! ascii = true
+//! stats = true
+//!
+//! [confirm]
+//! type_above_entries = 1000
+//! type_above_size = "10G"
+//!
+//! [restore]
+//! max_items = 100
+//! max_size = "20G"
+//!
+//! [defaults]
+//! delete_mode = "trash"
+//! dry_run = false
+//! readonly = false
+//! max_depth = 4
+//! no_color = false
+//! log = "/var/log/irm.log"
+//! ```
+//!
+//! What's given on the command line wins over the environment (`NO_COLOR`,
+//! `IRM_LOG`), which wins over the file; see `Config::load`.
+
+use std::{env, fs, io, path::PathBuf};
+
+use crate::{audit, cli, format, keymap::Keymap, protect::Protected, theme::Theme, DeleteMode};
+
+#[derive(Debug)]
+pub struct Config {
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub protected: Protected,
+    /// List directories before files, whatever the sort mode
+    pub directories_first: bool,
+    /// Draw with plain ASCII, like `--ascii`
+    pub ascii: bool,
+    /// Show the totals in the title, like `--stats`
+    pub stats: bool,
+    /// Extensions, lowercased and without the dot, of files that selecting
+    /// many entries at once leaves out. Unlike `protected`, they can still
+    /// be selected one by one and removed.
+    pub never_select: Vec<String>,
+    /// Removals of more entries than this have to be confirmed by typing
+    pub type_above_entries: usize,
+    /// Likewise for removals of more bytes than this
+    pub type_above_size: u64,
+    /// How many of the entries trashed this session `u` can put back, the
+    /// oldest being forgotten first
+    pub restore_max_items: usize,
+    /// Likewise for how many bytes they may add up to
+    pub restore_max_size: u64,
+    /// What removing does unless `--trash` or `--permanent` says otherwise
+    pub delete_mode: DeleteMode,
+    pub dry_run: bool,
+    /// Refuse every removal, like `--readonly`
+    pub readonly: bool,
+    pub max_depth: Option<usize>,
+    pub no_color: bool,
+    /// Where to append the audit log, see `audit`
+    pub log: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            protected: Protected::default(),
+            directories_first: true,
+            ascii: false,
+            stats: false,
+            never_select: Vec::new(),
+            type_above_entries: 1000,
+            type_above_size: 10 << 30,
+            restore_max_items: 100,
+            restore_max_size: 20 << 30,
+            delete_mode: DeleteMode::Permanent,
+            dry_run: false,
+            readonly: false,
+            max_depth: None,
+            no_color: false,
+            log: None,
+        }
+    }
+}
+
+impl Config {
+    /// The settings in effect: the config file's (or the defaults when
+    /// there is none), overridden by the environment and then by `args`
+    pub fn load(args: &cli::Args) -> Result<Self, String> {
+        let mut config = Self::from_file()?;
+        config.apply(args);
+        Ok(config)
+    }
+
+    fn from_file() -> Result<Self, String> {
+        let Some(file) = config_file() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&file) {
+            Ok(contents) => {
+                Self::parse(&contents).map_err(|msg| format!("{}: {msg}", file.display()))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("could not read {}: {err}", file.display())),
+        }
+    }
+
+    fn apply(&mut self, args: &cli::Args) {
+        if args.trash {
+            self.delete_mode = DeleteMode::Trash;
+        } else if args.permanent {
+            self.delete_mode = DeleteMode::Permanent;
+        }
+        self.dry_run = args.dry_run.unwrap_or(self.dry_run);
+        self.readonly = args.readonly.unwrap_or(self.readonly);
+        self.max_depth = args.max_depth.or(self.max_depth);
+        self.ascii = args.ascii.unwrap_or(self.ascii);
+        self.stats = args.stats.unwrap_or(self.stats);
+        let from_env = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        self.no_color = args.no_color.unwrap_or(self.no_color || from_env);
+        let from_env = env::var(audit::ENV_VAR)
+            .ok()
+            .filter(|file| !file.is_empty());
+        if let Some(log) = args.log.clone().or(from_env) {
+            self.log = Some(log);
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let config: toml::Table =
+            contents
+                .parse()
+                .map_err(|err: toml::de::Error| match err.span() {
+                    Some(span) => {
+                        let line = contents[..span.start].matches('\n').count() + 1;
+                        format!("line {line}: {}", err.message())
+                    }
+                    None => err.message().to_string(),
+                })?;
+        let mut directories_first = true;
+        if let Some(sort) = config.get("sort") {
+            let sort = sort.as_table().ok_or("`sort` must be a table")?;
+            for (name, value) in sort {
+                match name.as_str() {
+                    "directories_first" => {
+                        directories_first = value
+                            .as_bool()
+                            .ok_or("directories_first must be true or false")?;
+                    }
+                    _ => return Err(format!("unknown setting '{name}' in [sort]")),
+                }
+            }
+        }
+        let mut ascii = false;
+        let mut stats = false;
+        if let Some(display) = config.get("display") {
+            let display = display.as_table().ok_or("`display` must be a table")?;
+            for (name, value) in display {
+                match name.as_str() {
+                    "ascii" => {
+                        ascii = value.as_bool().ok_or("ascii must be true or false")?;
+                    }
+                    "stats" => {
+                        stats = value.as_bool().ok_or("stats must be true or false")?;
+                    }
+                    _ => return Err(format!("unknown setting '{name}' in [display]")),
+                }
+            }
+        }
+        let mut parsed = Self {
+            keymap: Keymap::from_config(&config)?,
+            theme: Theme::from_config(&config)?,
+            protected: Protected::from_config(&config)?,
+            directories_first,
+            ascii,
+            stats,
+            ..Self::default()
+        };
+        if let Some(select) = config.get("select") {
+            parsed.parse_select(select)?;
+        }
+        if let Some(confirm) = config.get("confirm") {
+            parsed.parse_confirm(confirm)?;
+        }
+        if let Some(restore) = config.get("restore") {
+            parsed.parse_restore(restore)?;
+        }
+        if let Some(defaults) = config.get("defaults") {
+            parsed.parse_defaults(defaults)?;
+        }
+        Ok(parsed)
+    }
+
+    fn parse_select(&mut self, select: &toml::Value) -> Result<(), String> {
+        let select = select.as_table().ok_or("`select` must be a table")?;
+        for (name, value) in select {
+            match name.as_str() {
+                "never" => {
+                    let extensions = value
+                        .as_array()
+                        .ok_or("never must be a list of extensions")?;
+                    for extension in extensions {
+                        let extension = extension
+                            .as_str()
+                            .map(|extension| extension.trim_start_matches('.'))
+                            .filter(|extension| !extension.is_empty())
+                            .ok_or("never must be a list of extensions such as \"pem\"")?;
+                        self.never_select.push(extension.to_lowercase());
+                    }
+                }
+                _ => return Err(format!("unknown setting '{name}' in [select]")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_confirm(&mut self, confirm: &toml::Value) -> Result<(), String> {
+        let confirm = confirm.as_table().ok_or("`confirm` must be a table")?;
+        for (name, value) in confirm {
+            match name.as_str() {
+                "type_above_entries" => {
+                    let entries = value
+                        .as_integer()
+                        .filter(|entries| *entries >= 0)
+                        .ok_or("type_above_entries must be a whole number")?;
+                    self.type_above_entries = entries as usize;
+                }
+                "type_above_size" => {
+                    self.type_above_size = value
+                        .as_str()
+                        .and_then(format::parse_size)
+                        .ok_or("type_above_size must be a size such as \"10G\"")?;
+                }
+                _ => return Err(format!("unknown setting '{name}' in [confirm]")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_restore(&mut self, restore: &toml::Value) -> Result<(), String> {
+        let restore = restore.as_table().ok_or("`restore` must be a table")?;
+        for (name, value) in restore {
+            match name.as_str() {
+                "max_items" => {
+                    let items = value
+                        .as_integer()
+                        .filter(|items| *items >= 1)
+                        .ok_or("max_items must be a whole number of at least 1")?;
+                    self.restore_max_items = items as usize;
+                }
+                "max_size" => {
+                    self.restore_max_size = value
+                        .as_str()
+                        .and_then(format::parse_size)
+                        .ok_or("max_size must be a size such as \"20G\"")?;
+                }
+                _ => return Err(format!("unknown setting '{name}' in [restore]")),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_defaults(&mut self, defaults: &toml::Value) -> Result<(), String> {
+        let defaults = defaults.as_table().ok_or("`defaults` must be a table")?;
+        for (name, value) in defaults {
+            match name.as_str() {
+                "delete_mode" => {
+                    self.delete_mode = match value.as_str() {
+                        Some("trash") => DeleteMode::Trash,
+                        Some("permanent") => DeleteMode::Permanent,
+                        _ => return Err("delete_mode must be \"trash\" or \"permanent\"".into()),
+                    };
+                }
+                "dry_run" => {
+                    self.dry_run = value.as_bool().ok_or("dry_run must be true or false")?;
+                }
+                "readonly" => {
+                    self.readonly = value.as_bool().ok_or("readonly must be true or false")?;
+                }
+                "max_depth" => {
+                    let max_depth = value
+                        .as_integer()
+                        .filter(|depth| *depth >= 1)
+                        .ok_or("max_depth must be a whole number of at least 1")?;
+                    self.max_depth = Some(max_depth as usize);
+                }
+                "no_color" => {
+                    self.no_color = value.as_bool().ok_or("no_color must be true or false")?;
+                }
+                "log" => {
+                    let log = value.as_str().ok_or("log must be a path")?;
+                    self.log = Some(log.to_string());
+                }
+                _ => return Err(format!("unknown setting '{name}' in [defaults]")),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn config_file() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Where irm keeps its files, `~/.config/irm` unless `XDG_CONFIG_HOME`
+/// says otherwise
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("irm"))
+}
+
+/// Where irm keeps what it remembers between sessions, `~/.cache/irm`
+/// unless `XDG_CACHE_HOME` says otherwise
+pub fn cache_dir() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(dir.join("irm"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVERYTHING_ON: &str = "
+        [display]
+        ascii = true
+        stats = true
+
+        [defaults]
+        dry_run = true
+        readonly = true
+        no_color = true
+        max_depth = 3
+    ";
+
+    #[test]
+    fn the_command_line_wins_over_the_file_either_way() {
+        let mut config = Config::parse(EVERYTHING_ON).unwrap();
+        config.apply(&cli::Args::default());
+        assert!(config.ascii && config.stats && config.dry_run && config.readonly);
+        assert!(config.no_color);
+        assert_eq!(config.max_depth, Some(3));
+
+        let mut config = Config::parse(EVERYTHING_ON).unwrap();
+        config.apply(&cli::Args {
+            ascii: Some(false),
+            stats: Some(false),
+            dry_run: Some(false),
+            readonly: Some(false),
+            no_color: Some(false),
+            max_depth: Some(5),
+            ..cli::Args::default()
+        });
+        assert!(!config.ascii && !config.stats && !config.dry_run && !config.readonly);
+        assert!(!config.no_color);
+        assert_eq!(config.max_depth, Some(5));
+
+        let mut config = Config::parse("[defaults]\ndelete_mode = \"trash\"\n").unwrap();
+        config.apply(&cli::Args {
+            permanent: true,
+            dry_run: Some(true),
+            ..cli::Args::default()
+        });
+        assert_eq!(config.delete_mode, DeleteMode::Permanent);
+        assert!(config.dry_run);
+    }
+
+    #[test]
+    fn never_select_takes_extensions_with_or_without_the_dot() {
+        let config = Config::parse("[select]\nnever = [\"pem\", \".KEY\"]\n").unwrap();
+        assert_eq!(config.never_select, ["pem", "key"]);
+
+        assert!(Config::parse("[select]\nnever = \"pem\"\n").is_err());
+        assert!(Config::parse("[select]\nnever = [\".\"]\n").is_err());
+        assert!(Config::parse("[select]\nalways = []\n").is_err());
+    }
+
+    #[test]
+    fn settings_are_read_table_by_table() {
+        let config = Config::parse(
+            "[sort]\ndirectories_first = false\n\n\
+             [confirm]\ntype_above_entries = 50\ntype_above_size = \"1G\"\n\n\
+             [restore]\nmax_items = 5\nmax_size = \"1M\"\n\n\
+             [defaults]\ndelete_mode = \"trash\"\nlog = \"/var/log/irm.log\"\n",
+        )
+        .unwrap();
+        assert!(!config.directories_first);
+        assert_eq!(config.type_above_entries, 50);
+        assert_eq!(config.type_above_size, 1 << 30);
+        assert_eq!(config.restore_max_items, 5);
+        assert_eq!(config.restore_max_size, 1 << 20);
+        assert_eq!(config.delete_mode, DeleteMode::Trash);
+        assert_eq!(config.log.as_deref(), Some("/var/log/irm.log"));
+
+        // an empty file changes nothing
+        let empty = Config::parse("").unwrap();
+        assert!(empty.directories_first && !empty.dry_run);
+        assert_eq!(empty.max_depth, None);
+    }
+
+    #[test]
+    fn mistakes_are_errors_that_say_where() {
+        let err = |contents: &str| Config::parse(contents).unwrap_err();
+        assert!(err("[defaults]\ndry_run = \n").starts_with("line 2: "));
+        assert_eq!(
+            err("[defaults]\ndryrun = true\n"),
+            "unknown setting 'dryrun' in [defaults]"
+        );
+        assert_eq!(
+            err("[display]\nascii = \"yes\"\n"),
+            "ascii must be true or false"
+        );
+        assert_eq!(
+            err("[defaults]\nmax_depth = 0\n"),
+            "max_depth must be a whole number of at least 1"
+        );
+        assert_eq!(
+            err("[defaults]\ndelete_mode = \"shred\"\n"),
+            "delete_mode must be \"trash\" or \"permanent\""
+        );
+        assert_eq!(
+            err("[confirm]\ntype_above_entries = -1\n"),
+            "type_above_entries must be a whole number"
+        );
+        assert_eq!(err("sort = 1\n"), "`sort` must be a table");
+        assert_eq!(
+            err("[restore]\nmax_items = 0\n"),
+            "max_items must be a whole number of at least 1"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! The details pane toggled with <p>: what the hovered entry is, and for
+//! text files the first lines of it
+
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, Read},
+    path::Path,
+};
+
+use ratatui::{style::Stylize, text::Line};
+
+use crate::format;
+
+/// How much of a file is read for its preview
+const PREVIEW_BYTES: u64 = 4 * 1024;
+
+/// The pane's lines for the entry at `path`. `size` is what the tree has
+/// measured, if it has, and `real_path` whether to resolve where the entry
+/// really is, see `real_path`.
+pub fn lines(path: &Path, size: Option<u64>, real_path: bool) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(path.display().to_string().bold())];
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(err) => {
+            lines.push(Line::from(format!("Could not read it: {err}").red()));
+            return lines;
+        }
+    };
+
+    lines.push(field("Type", kind(path, &meta)));
+    if real_path {
+        lines.push(self::real_path(path));
+    }
+    let size = match size {
+        Some(size) => format::size(size),
+        None if meta.is_dir() => "not measured yet, press <U>".to_string(),
+        None => format::size(meta.len()),
+    };
+    lines.push(field("Size", size));
+    if let Ok(modified) = meta.modified() {
+        lines.push(field("Modified", format::timestamp(modified)));
+    }
+    lines.push(field("Permissions", permissions(&meta)));
+
+    if meta.is_file() {
+        lines.push(Line::default());
+        match preview(path) {
+            Ok(Some(text)) => lines.extend(
+                text.lines()
+                    .map(|line| Line::from(line.replace('\t', "    ")).dim()),
+            ),
+            Ok(None) => lines.push(Line::from("<binary>").dim()),
+            Err(err) => lines.push(Line::from(format!("Could not read it: {err}").red())),
+        }
+    }
+    lines
+}
+
+/// Where `path` really is, with every symlink on the way resolved, or why
+/// that can't be told. Only ever looked at: removing still goes by `path`.
+pub fn real_path(path: &Path) -> Line<'static> {
+    match fs::canonicalize(path) {
+        Ok(real) => field("Real path", real.display().to_string()),
+        Err(err) => Line::from(vec![
+            "Real path: ".blue().bold(),
+            format!("can't be resolved: {err}").red(),
+        ]),
+    }
+}
+
+fn field(name: &str, value: String) -> Line<'static> {
+    Line::from(vec![format!("{name}: ").blue().bold(), value.into()])
+}
+
+fn kind(path: &Path, meta: &Metadata) -> String {
+    if !meta.file_type().is_symlink() {
+        return if meta.is_dir() { "directory" } else { "file" }.to_string();
+    }
+    match fs::read_link(path) {
+        Ok(target) if path.is_dir() => format!("symlinked directory -> {}", target.display()),
+        Ok(target) => format!("symlink -> {}", target.display()),
+        Err(_) => "symlink".to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn permissions(meta: &Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = meta.permissions().mode();
+    let bits = "rwxrwxrwx"
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if mode & (1 << (8 - i)) != 0 { c } else { '-' })
+        .collect::<String>();
+    format!("{bits} ({:o})", mode & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn permissions(meta: &Metadata) -> String {
+    if meta.permissions().readonly() {
+        "read-only".to_string()
+    } else {
+        "writable".to_string()
+    }
+}
+
+/// The start of the file as text, or `None` when it doesn't look like text
+fn preview(path: &Path) -> io::Result<Option<String>> {
+    let mut bytes = Vec::new();
+    File::open(path)?
+        .take(PREVIEW_BYTES)
+        .read_to_end(&mut bytes)?;
+    if bytes.contains(&0) {
+        return Ok(None);
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(Some(text)),
+        // the cut may have landed inside a character
+        Err(err) if err.utf8_error().error_len().is_none() => {
+            let valid = err.utf8_error().valid_up_to();
+            let mut bytes = err.into_bytes();
+            bytes.truncate(valid);
+            Ok(Some(String::from_utf8(bytes).unwrap_or_default()))
+        }
+        Err(_) => Ok(None),
+    }
+}
@@ -0,0 +1,256 @@
+use std::{fs, io, path::Path};
+
+#[cfg(unix)]
+use std::{collections::HashSet, os::unix::fs::MetadataExt};
+
+/// Space taken up by a tree, counted two ways
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Usage {
+    /// Every file's length added up, the way a naive walk does it
+    pub apparent: u64,
+    /// Like `apparent`, but files hardlinked several times inside the tree
+    /// are only counted once
+    pub unique: u64,
+    /// How many entries were extra links to a file already counted
+    pub repeated_links: usize,
+}
+
+/// Measure everything at and below `path` without following symlinks
+pub fn usage(path: &Path) -> Usage {
+    let mut walk = Walk::default();
+    walk.visit(path);
+    walk.usage
+}
+
+#[derive(Default)]
+struct Walk {
+    usage: Usage,
+    /// (device, inode) of every multiply-linked file seen so far
+    #[cfg(unix)]
+    seen: HashSet<(u64, u64)>,
+}
+
+impl Walk {
+    fn visit(&mut self, path: &Path) {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return;
+        };
+
+        if meta.is_dir() {
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    self.visit(&entry.path());
+                }
+            }
+            return;
+        }
+
+        self.usage.apparent += meta.len();
+        if self.is_repeated_link(&meta) {
+            self.usage.repeated_links += 1;
+        } else {
+            self.usage.unique += meta.len();
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_repeated_link(&mut self, meta: &fs::Metadata) -> bool {
+        meta.nlink() > 1 && !self.seen.insert((meta.dev(), meta.ino()))
+    }
+
+    #[cfg(not(unix))]
+    fn is_repeated_link(&mut self, _meta: &fs::Metadata) -> bool {
+        false
+    }
+}
+
+/// Remove the directory at `path` with everything in it, like
+/// `fs::remove_dir_all`, but one entry at a time, calling `progress` with
+/// how many are gone so far after each. Symlinks are removed themselves.
+/// Stops at the first entry that can't be removed, naming it in the error;
+/// whatever went before it is gone for good.
+pub fn remove_tree(path: &Path, progress: &mut impl FnMut(u64)) -> io::Result<()> {
+    let mut removed = 0;
+    remove_within(path, &mut removed, progress)?;
+    fs::remove_dir(path).map_err(|err| naming(path, err))?;
+    progress(removed + 1);
+    Ok(())
+}
+
+fn remove_within(dir: &Path, removed: &mut u64, progress: &mut impl FnMut(u64)) -> io::Result<()> {
+    for entry in fs::read_dir(dir).map_err(|err| naming(dir, err))? {
+        let entry = entry.map_err(|err| naming(dir, err))?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|err| naming(&path, err))?;
+        if file_type.is_symlink() {
+            crate::symlink::remove(&path)
+        } else if file_type.is_dir() {
+            remove_within(&path, removed, progress)?;
+            fs::remove_dir(&path)
+        } else {
+            fs::remove_file(&path)
+        }
+        .map_err(|err| naming(&path, err))?;
+        *removed += 1;
+        progress(*removed);
+    }
+    Ok(())
+}
+
+/// Move `from` to `to`, which mustn't exist yet. Renaming does it where
+/// it can; across filesystems everything is copied over and then removed,
+/// with symlinks copied as links and files keeping their modification
+/// time. A copy that fails part way is cleaned up again, leaving `from` as
+/// it was.
+pub fn move_to(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {}
+        result => return result,
+    }
+    // what's cleaned up after a failed copy has to be the copy's own
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(naming(to, io::ErrorKind::AlreadyExists.into()));
+    }
+    if let Err(err) = copy(from, to) {
+        let _ = match fs::symlink_metadata(to) {
+            Ok(meta) if meta.is_dir() => remove_tree(to, &mut |_| {}),
+            Ok(_) => fs::remove_file(to),
+            Err(_) => Ok(()),
+        };
+        return Err(err);
+    }
+    let meta = fs::symlink_metadata(from).map_err(|err| naming(from, err))?;
+    if meta.is_symlink() {
+        crate::symlink::remove(from).map_err(|err| naming(from, err))
+    } else if meta.is_dir() {
+        remove_tree(from, &mut |_| {})
+    } else {
+        fs::remove_file(from).map_err(|err| naming(from, err))
+    }
+}
+
+fn copy(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from).map_err(|err| naming(from, err))?;
+    if meta.is_symlink() {
+        let target = fs::read_link(from).map_err(|err| naming(from, err))?;
+        return copy_link(from, &target, to).map_err(|err| naming(to, err));
+    }
+    if !meta.is_dir() {
+        fs::copy(from, to).map_err(|err| naming(to, err))?;
+        if let Ok(modified) = meta.modified() {
+            let _ = fs::File::options()
+                .write(true)
+                .open(to)
+                .and_then(|file| file.set_modified(modified));
+        }
+        return Ok(());
+    }
+    fs::create_dir(to).map_err(|err| naming(to, err))?;
+    for entry in fs::read_dir(from).map_err(|err| naming(from, err))? {
+        let entry = entry.map_err(|err| naming(from, err))?;
+        copy(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    // only once it's filled, in case it's read-only
+    fs::set_permissions(to, meta.permissions()).map_err(|err| naming(to, err))
+}
+
+#[cfg(unix)]
+fn copy_link(_from: &Path, target: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, to)
+}
+
+#[cfg(windows)]
+fn copy_link(from: &Path, target: &Path, to: &Path) -> io::Result<()> {
+    // Windows tells links to directories from links to files
+    if fs::metadata(from).is_ok_and(|meta| meta.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, to)
+    } else {
+        std::os::windows::fs::symlink_file(target, to)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn copy_link(_from: &Path, _target: &Path, _to: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symlinks can't be copied on this platform",
+    ))
+}
+
+fn naming(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+}
+
+/// Room on the filesystem holding some path
+#[derive(Debug, Clone, Copy)]
+pub struct Space {
+    /// What an unprivileged user can still write
+    pub free: u64,
+    pub total: u64,
+}
+
+/// The free and total space of the filesystem `path` is on, or `None` when
+/// that can't be asked
+#[cfg(unix)]
+pub fn space(path: &Path) -> Option<Space> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stats` is only read once
+    // statvfs has filled it in
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stats.assume_init()
+    };
+    let block = stats.f_frsize as u64;
+    Some(Space {
+        free: stats.f_bavail as u64 * block,
+        total: stats.f_blocks as u64 * block,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn space(_path: &Path) -> Option<Space> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn removing_a_tree_leaves_what_its_links_point_at() {
+        let dir = TestDir::new();
+        let outside = dir.file("outside/keep", "kept");
+        dir.file("tree/a/file", "");
+        dir.symlink(dir.join("outside"), "tree/a/to_dir");
+        dir.symlink(&outside, "tree/to_file");
+        dir.symlink(dir.join("nowhere"), "tree/dangling");
+
+        let mut counts = Vec::new();
+        remove_tree(&dir.join("tree"), &mut |count| counts.push(count)).unwrap();
+        assert!(!dir.join("tree").exists());
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "kept");
+        // a, its file and link, the other two links, and the tree itself
+        assert_eq!(counts.last(), Some(&6));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_failure_names_the_entry_and_keeps_the_rest() {
+        let dir = TestDir::new();
+        let file = dir.file("tree/locked/file", "");
+        // root removes it anyway, leaving nothing to check
+        if dir.lock("tree/locked") {
+            let err = remove_tree(&dir.join("tree"), &mut |_| {}).unwrap_err();
+            assert!(err.to_string().contains("locked/file"), "{err}");
+            assert!(file.exists());
+        }
+        dir.unlock("tree/locked");
+    }
+}
@@ -0,0 +1,138 @@
+//! Files with the same contents among what's been read, looked for only
+//! when asked with `=`. Files are grouped by size first, and only those
+//! sharing their size with another are read at all: the first block of
+//! each is hashed to split the groups further, and only files still alike
+//! after that are hashed whole.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    hash::{DefaultHasher, Hasher},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// How much is read at a time, and how much of each file is hashed before
+/// deciding whether to read the rest
+const BLOCK: usize = 64 * 1024;
+
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    /// Each set of files with the same size and hash, in path order. Sets
+    /// emptied by `forget` are kept, so the others keep their number.
+    groups: Vec<Vec<PathBuf>>,
+    /// Which of `groups` each path is in
+    by_path: HashMap<PathBuf, usize>,
+}
+
+impl Duplicates {
+    /// Group `files`, given with their sizes, by what's in them. Empty files
+    /// are left out, since removing them frees nothing. Files still unread
+    /// at `deadline` are left out too, and the second value says whether
+    /// everything was read in time.
+    pub fn find(files: Vec<(PathBuf, u64)>, deadline: Instant) -> (Self, bool) {
+        let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for (path, size) in files {
+            if size > 0 {
+                by_size.entry(size).or_default().push(path);
+            }
+        }
+
+        let mut finished = true;
+        let mut groups = Vec::new();
+        for (size, paths) in by_size {
+            if paths.len() < 2 {
+                continue;
+            }
+            for alike in split(paths, Some(BLOCK as u64), deadline, &mut finished) {
+                if size <= BLOCK as u64 {
+                    // the first block was all of it
+                    groups.push(alike);
+                } else {
+                    groups.extend(split(alike, None, deadline, &mut finished));
+                }
+            }
+        }
+        for group in &mut groups {
+            group.sort();
+        }
+        groups.sort();
+
+        let by_path = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(i, group)| group.iter().map(move |path| (path.clone(), i)))
+            .collect();
+        (Self { groups, by_path }, finished)
+    }
+
+    /// How many sets of duplicates there are
+    pub fn len(&self) -> usize {
+        self.groups.iter().filter(|group| !group.is_empty()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_path.is_empty()
+    }
+
+    /// The set of duplicates `path` is in, if any
+    pub fn get(&self, path: &Path) -> Option<usize> {
+        self.by_path.get(path).copied()
+    }
+
+    /// Drop `removed`, and everything in it when it's a directory. A file
+    /// left without its copies isn't a duplicate anymore.
+    pub fn forget(&mut self, removed: &Path) {
+        for group in &mut self.groups {
+            group.retain(|path| !path.starts_with(removed));
+            if group.len() < 2 {
+                group.clear();
+            }
+        }
+        let groups = &self.groups;
+        self.by_path.retain(|path, i| groups[*i].contains(path));
+    }
+
+    /// Every file but the first of each set, so that selecting these keeps
+    /// one copy of everything
+    pub fn extras(&self) -> impl Iterator<Item = &PathBuf> {
+        self.groups.iter().flat_map(|group| group.iter().skip(1))
+    }
+}
+
+/// `paths` grouped by the hash of their first `limit` bytes, or of all of
+/// them, leaving out files that can't be read and any left on their own
+fn split(
+    paths: Vec<PathBuf>,
+    limit: Option<u64>,
+    deadline: Instant,
+    finished: &mut bool,
+) -> Vec<Vec<PathBuf>> {
+    let mut by_hash: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        if Instant::now() >= deadline {
+            *finished = false;
+            break;
+        }
+        if let Ok(hash) = hash(&path, limit) {
+            by_hash.entry(hash).or_default().push(path);
+        }
+    }
+    by_hash
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect()
+}
+
+fn hash(path: &Path, limit: Option<u64>) -> io::Result<u64> {
+    let mut file = File::open(path)?.take(limit.unwrap_or(u64::MAX));
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; BLOCK];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => return Ok(hasher.finish()),
+            n => hasher.write(&buffer[..n]),
+        }
+    }
+}
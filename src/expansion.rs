@@ -0,0 +1,66 @@
+//! Which directories were open when irm last left a root, so they can be
+//! opened again next time. Kept in `expanded.toml` in the cache directory
+//! (see `config`), one list of paths relative to the root per root:
+//!
+//! ```toml
+//! "/home/me/projects" = [".", "target", "target/debug"]
+//! ```
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config;
+
+fn file() -> Option<PathBuf> {
+    Some(config::cache_dir()?.join("expanded.toml"))
+}
+
+fn read_all() -> toml::Table {
+    file()
+        .and_then(|file| fs::read_to_string(file).ok())
+        .and_then(|contents| contents.parse().ok())
+        .unwrap_or_default()
+}
+
+/// The directories open under `root` last time, parents before children.
+/// Nothing saved, or a file that can't be read, means none.
+pub fn load(root: &Path) -> Vec<PathBuf> {
+    let Some(toml::Value::Array(paths)) = read_all().remove(&*root.to_string_lossy()) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = paths
+        .iter()
+        .filter_map(toml::Value::as_str)
+        .map(PathBuf::from)
+        .collect();
+    paths.sort_by_key(|path| path.components().count());
+    paths
+}
+
+/// Replace what's saved for `root` with `paths`, relative to it. Other
+/// roots keep theirs.
+pub fn save(root: &Path, paths: &[PathBuf]) -> io::Result<()> {
+    let Some(file) = file() else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no home directory to keep them in",
+        ));
+    };
+    let mut all = read_all();
+    let key = root.to_string_lossy().into_owned();
+    if paths.is_empty() {
+        all.remove(&key);
+    } else {
+        let paths = paths
+            .iter()
+            .map(|path| toml::Value::from(path.to_string_lossy().into_owned()))
+            .collect();
+        all.insert(key, toml::Value::Array(paths));
+    }
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(file, all.to_string())
+}
@@ -0,0 +1,155 @@
+//! The <Ctrl + P> finder: type a few letters of a path, in order but not
+//! necessarily together, and jump to the best match anywhere in the loaded
+//! tree
+
+/// How many matches the popup lists
+pub const MAX_MATCHES: usize = 10;
+
+/// Scores for a match, see `fuzzy_score`
+const MATCH: i64 = 1;
+const CONSECUTIVE: i64 = 5;
+const WORD_START: i64 = 8;
+/// Taken off for every character skipped between two matched ones
+const GAP: i64 = 1;
+
+#[derive(Debug)]
+pub struct Finder {
+    pub query: String,
+    /// Every path that can be jumped to
+    candidates: Vec<String>,
+    /// The best matches for `query`, best first
+    pub matches: Vec<String>,
+    /// Which of `matches` Enter jumps to
+    pub picked: usize,
+}
+
+impl Finder {
+    pub fn new(candidates: Vec<String>) -> Self {
+        let mut finder = Self {
+            query: String::new(),
+            candidates,
+            matches: Vec::new(),
+            picked: 0,
+        };
+        finder.update();
+        finder
+    }
+
+    /// Rank the candidates for the query as it is now
+    pub fn update(&mut self) {
+        let mut scored: Vec<(i64, &String)> = self
+            .candidates
+            .iter()
+            .filter_map(|path| Some((fuzzy_score(&self.query, path)?, path)))
+            .collect();
+        // the shorter of two equally good paths is likelier what was meant
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(a.len().cmp(&b.len()))
+                .then(a.cmp(b))
+        });
+        self.matches = scored
+            .into_iter()
+            .take(MAX_MATCHES)
+            .map(|(_, path)| path.clone())
+            .collect();
+        self.picked = 0;
+    }
+
+    pub fn pick_previous(&mut self) {
+        self.picked = self.picked.saturating_sub(1);
+    }
+
+    pub fn pick_next(&mut self) {
+        if self.picked + 1 < self.matches.len() {
+            self.picked += 1;
+        }
+    }
+
+    pub fn picked(&self) -> Option<&String> {
+        self.matches.get(self.picked)
+    }
+}
+
+/// How well `candidate` matches `query`, or `None` if the query's
+/// characters don't all appear in it in order. Case is ignored. Characters
+/// matched one after the other and at the start of a word (after `/`, `_`,
+/// `-`, `.` or a space) count for more, and gaps between matches for less.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut last_match: Option<usize> = None;
+    let mut wanted = query.chars().flat_map(char::to_lowercase).peekable();
+
+    for (i, c) in candidate.chars().enumerate() {
+        let Some(&next) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(next.to_lowercase()) {
+            score += MATCH;
+            match last_match {
+                Some(last) if last + 1 == i => score += CONSECUTIVE,
+                Some(last) => score -= GAP * (i - last - 1) as i64,
+                None => {}
+            }
+            if previous.is_none_or(|p| matches!(p, '/' | '_' | '-' | '.' | ' ')) {
+                score += WORD_START;
+            }
+            last_match = Some(i);
+            wanted.next();
+        }
+        previous = Some(c);
+    }
+
+    wanted.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_character_has_to_appear_in_order() {
+        assert!(fuzzy_score("abc", "a/b/c").is_some());
+        assert!(fuzzy_score("ABC", "abc").is_some());
+        assert_eq!(fuzzy_score("cba", "abc"), None);
+        assert_eq!(fuzzy_score("abcd", "abc"), None);
+        assert_eq!(fuzzy_score("", "abc"), Some(0));
+    }
+
+    #[test]
+    fn together_and_at_word_starts_beats_scattered() {
+        let together = fuzzy_score("main", "src/main.rs").unwrap();
+        let scattered = fuzzy_score("main", "src/my_animation.rs").unwrap();
+        assert!(together > scattered, "{together} <= {scattered}");
+
+        let word_start = fuzzy_score("rs", "build/rust-src").unwrap();
+        let inside = fuzzy_score("rs", "build/ours").unwrap();
+        assert!(word_start > inside, "{word_start} <= {inside}");
+
+        let near = fuzzy_score("ab", "a_b").unwrap();
+        let far = fuzzy_score("ab", "axxxxb").unwrap();
+        assert!(near > far, "{near} <= {far}");
+    }
+
+    #[test]
+    fn the_best_match_is_picked_first() {
+        let mut finder = Finder::new(vec![
+            "src/my_animation.rs".to_string(),
+            "src/main.rs".to_string(),
+            "docs".to_string(),
+        ]);
+        finder.query = "main".to_string();
+        finder.update();
+        assert_eq!(finder.matches, ["src/main.rs", "src/my_animation.rs"]);
+        finder.pick_next();
+        finder.pick_next();
+        assert_eq!(
+            finder.picked().map(String::as_str),
+            Some("src/my_animation.rs")
+        );
+        finder.pick_previous();
+        assert_eq!(finder.picked().map(String::as_str), Some("src/main.rs"));
+    }
+}
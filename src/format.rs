@@ -0,0 +1,346 @@
+//! Formatting of sizes, counts and dates for display, following the
+//! locale picked with `--locale`. The default is locale-neutral: ISO dates,
+//! no thousands separators and a `.` decimal point.
+
+use std::{
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Conventions for writing numbers and dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    thousands: Option<char>,
+    decimal: char,
+    date: DateOrder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateOrder {
+    /// `2024-01-15`
+    Iso,
+    /// `01/15/2024`
+    MonthDayYear,
+    /// `15/01/2024`, or with `.` when the locale uses it
+    DayMonthYear(char),
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            thousands: None,
+            decimal: '.',
+            date: DateOrder::Iso,
+        }
+    }
+}
+
+impl Locale {
+    pub const NAMES: &'static str = "iso, en-us, en-gb, de, fr";
+
+    /// Look up one of the supported locales by name, see `NAMES`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let locale = match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "iso" | "c" | "posix" => Self::default(),
+            "en" | "en-us" => Self {
+                thousands: Some(','),
+                decimal: '.',
+                date: DateOrder::MonthDayYear,
+            },
+            "en-gb" => Self {
+                thousands: Some(','),
+                decimal: '.',
+                date: DateOrder::DayMonthYear('/'),
+            },
+            "de" | "de-de" => Self {
+                thousands: Some('.'),
+                decimal: ',',
+                date: DateOrder::DayMonthYear('.'),
+            },
+            "fr" | "fr-fr" => Self {
+                thousands: Some('\u{202f}'),
+                decimal: ',',
+                date: DateOrder::DayMonthYear('/'),
+            },
+            _ => return None,
+        };
+        Some(locale)
+    }
+}
+
+/// Use `locale` for everything formatted from now on. Only the first call
+/// has an effect.
+pub fn set_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+fn locale() -> Locale {
+    *LOCALE.get_or_init(Locale::default)
+}
+
+/// Integer with the locale's thousands separator, e.g. `1,234,567`
+pub fn count(n: u64) -> String {
+    let digits = n.to_string();
+    let Some(separator) = locale().thousands else {
+        return digits;
+    };
+
+    let mut grouped = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// Decimal number with one fractional digit
+fn decimal(value: f64) -> String {
+    let formatted = format!("{value:.1}");
+    let (whole, fraction) = formatted.split_once('.').unwrap();
+    let whole = count(whole.parse().unwrap_or(0));
+    format!("{whole}{}{fraction}", locale().decimal)
+}
+
+/// Human readable byte count using binary units, e.g. `1.2 MiB`
+pub fn size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", count(bytes))
+    } else {
+        format!("{} {}", decimal(size), UNITS[unit])
+    }
+}
+
+/// `part` as a percentage of `whole`, e.g. `45%`, with a fractional digit
+/// below 10% so that a small share doesn't read as nothing. `None` when
+/// there's no whole to compare against.
+pub fn percent(part: u64, whole: u64) -> Option<String> {
+    if whole == 0 {
+        return None;
+    }
+    let percent = part as f64 / whole as f64 * 100.0;
+    Some(if percent < 10.0 {
+        format!("{}%", decimal(percent))
+    } else {
+        format!("{}%", count(percent.round() as u64))
+    })
+}
+
+/// `s` cut down to at most `width` display columns by replacing its middle
+/// with `…`, so both the start of a name and its end, extension and all,
+/// stay readable
+pub fn elide_middle(s: &str, width: usize) -> String {
+    if s.width() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    // the end tells files apart more often, so it gets the odd column
+    let kept = width - 1;
+    let (head_width, tail_width) = (kept / 2, kept - kept / 2);
+    let take = |chars: &mut dyn Iterator<Item = char>, budget: usize| {
+        let mut used = 0;
+        let mut taken = Vec::new();
+        for c in chars {
+            used += c.width().unwrap_or(0);
+            if used > budget {
+                break;
+            }
+            taken.push(c);
+        }
+        taken
+    };
+    let head = take(&mut s.chars(), head_width);
+    let mut tail = take(&mut s.chars().rev(), tail_width);
+    tail.reverse();
+    head.into_iter()
+        .chain(std::iter::once('…'))
+        .chain(tail)
+        .collect()
+}
+
+/// Parse a human readable size such as `100M`, `>1.5GiB` or `512` (bytes)
+/// into a byte count. Units are case-insensitive powers of 1024, and a
+/// leading `>` or `>=` is accepted so thresholds read naturally.
+pub fn parse_size(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let input = input
+        .strip_prefix(">=")
+        .or_else(|| input.strip_prefix('>'))
+        .unwrap_or(input)
+        .trim();
+
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != locale().decimal)
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.replace(locale().decimal, ".").parse().ok()?;
+
+    let exponent = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 1,
+        "m" | "mb" | "mib" => 2,
+        "g" | "gb" | "gib" => 3,
+        "t" | "tb" | "tib" => 4,
+        _ => return None,
+    };
+    Some((number * 1024f64.powi(exponent)) as u64)
+}
+
+/// Units `parse_age` takes and `age` writes, largest first, in seconds
+const AGE_UNITS: &[(&str, u64)] = &[
+    ("y", 365 * 86_400),
+    ("w", 7 * 86_400),
+    ("d", 86_400),
+    ("h", 3_600),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// Parse a duration such as `30d`, `2h` or `1.5w`. Units are `s`, `m`
+/// (minutes), `h`, `d`, `w` and `y` (365 days); a bare number is days.
+pub fn parse_age(input: &str) -> Option<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.' && c != locale().decimal)
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.replace(locale().decimal, ".").parse().ok()?;
+
+    let unit = unit.trim().to_ascii_lowercase();
+    let seconds = if unit.is_empty() {
+        86_400
+    } else {
+        AGE_UNITS.iter().find(|(name, _)| *name == unit)?.1
+    };
+    Duration::try_from_secs_f64(number * seconds as f64).ok()
+}
+
+/// `age` in the largest unit that divides it evenly, e.g. `30d`
+pub fn age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (name, seconds) = AGE_UNITS
+        .iter()
+        .find(|(_, seconds)| secs.is_multiple_of(*seconds) && secs >= *seconds)
+        .unwrap_or(&("s", 1));
+    format!("{}{name}", secs / seconds)
+}
+
+/// Calendar date (UTC) of `time`, e.g. `2024-01-15`
+pub fn date(time: SystemTime) -> String {
+    let secs = match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    match locale().date {
+        DateOrder::Iso => format!("{year:04}-{month:02}-{day:02}"),
+        DateOrder::MonthDayYear => format!("{month:02}/{day:02}/{year:04}"),
+        DateOrder::DayMonthYear(separator) => {
+            format!("{day:02}{separator}{month:02}{separator}{year:04}")
+        }
+    }
+}
+
+/// Date and time of day (UTC) of `time`, e.g. `2024-01-15 09:30:00`
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (hours, minutes, seconds) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    format!("{} {hours:02}:{minutes:02}:{seconds:02}", date(time))
+}
+
+/// `time` in UTC as RFC 3339, e.g. `2024-01-15T09:30:00Z`, whatever the
+/// locale, for output meant to be read back by other tools
+pub fn rfc3339(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let (hours, minutes, seconds) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    format!("{year:04}-{month:02}-{day:02}T{hours:02}:{minutes:02}:{seconds:02}Z")
+}
+
+/// Convert days since 1970-01-01 into a (year, month, day) triple, using
+/// Howard Hinnant's `civil_from_days` algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eliding_keeps_both_ends() {
+        assert_eq!(elide_middle("report.txt", 10), "report.txt");
+        assert_eq!(elide_middle("report.txt", 20), "report.txt");
+        assert_eq!(elide_middle("report.txt", 9), "repo….txt");
+        assert_eq!(elide_middle("report.txt", 5), "re…xt");
+        assert_eq!(elide_middle("report.txt", 2), "…t");
+        assert_eq!(elide_middle("report.txt", 1), "…");
+        assert_eq!(elide_middle("report.txt", 0), "");
+    }
+
+    #[test]
+    fn eliding_counts_display_columns() {
+        // each of these takes up two columns
+        let name = "漢字漢字漢字.txt";
+        assert_eq!(name.width(), 16);
+        let elided = elide_middle(name, 9);
+        assert_eq!(elided, "漢字….txt");
+        assert!(elided.width() <= 9);
+        // a wide character that doesn't fit leaves its column empty
+        assert_eq!(elide_middle(name, 8), "漢….txt");
+    }
+
+    #[test]
+    fn a_small_share_keeps_a_fractional_digit() {
+        assert_eq!(percent(45, 100).as_deref(), Some("45%"));
+        assert_eq!(percent(1, 200).as_deref(), Some("0.5%"));
+        assert_eq!(percent(0, 100).as_deref(), Some("0.0%"));
+        assert_eq!(percent(300, 100).as_deref(), Some("300%"));
+        assert_eq!(percent(1, 0), None);
+    }
+
+    #[test]
+    fn sizes_are_read_in_powers_of_1024() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("1k"), Some(1024));
+        assert_eq!(parse_size("100M"), Some(100 << 20));
+        assert_eq!(parse_size(">1.5GiB"), Some(3 << 29));
+        assert_eq!(parse_size(" >= 2 tb "), Some(2 << 40));
+        assert_eq!(parse_size("3 B"), Some(3));
+
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("M"), None);
+        assert_eq!(parse_size("10 parsecs"), None);
+        assert_eq!(parse_size("-1K"), None);
+    }
+}
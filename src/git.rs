@@ -0,0 +1,190 @@
+//! Asks `git` which files a piece of work produced, for `--since <REF>`,
+//! and what state the files of each repository in the tree are in
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    io,
+    path::{self, Path, PathBuf},
+    process::Command,
+};
+
+/// Files under `dir` that were added after `reference` or aren't tracked at
+/// all (ignored files excluded), as absolute paths
+pub fn added_since(dir: &Path, reference: &str) -> io::Result<Vec<PathBuf>> {
+    if reference.starts_with('-') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a git revision",
+        ));
+    }
+    let toplevel = PathBuf::from(git(dir, ["rev-parse", "--show-toplevel"])?.trim_end());
+
+    let added = git(
+        dir,
+        [
+            "diff",
+            "--name-only",
+            "--diff-filter=A",
+            "-z",
+            reference,
+            "--",
+        ],
+    )?;
+    let untracked = git(
+        dir,
+        [
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--full-name",
+            "-z",
+        ],
+    )?;
+
+    let dir = dir.canonicalize()?;
+    let mut paths: Vec<PathBuf> = added
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|path| !path.is_empty())
+        .map(|path| toplevel.join(path))
+        .filter(|path| path.starts_with(&dir))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// What `git status` says about an entry, weakest first: a directory shows
+/// the strongest mark of anything below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mark {
+    Ignored,
+    Untracked,
+    Added,
+    Modified,
+}
+
+impl Mark {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Mark::Ignored => "!",
+            Mark::Untracked => "?",
+            Mark::Added => "A",
+            Mark::Modified => "M",
+        }
+    }
+}
+
+/// The marks for every repository found in the tree so far, asked for once
+/// per repository and again on refresh. Anything outside a repository, or
+/// with no `git` to ask, simply has no mark.
+#[derive(Debug, Default)]
+pub struct Marks {
+    /// The top level of each repository asked about
+    repos: HashSet<PathBuf>,
+    /// Absolute paths and their mark, directories included
+    marks: HashMap<PathBuf, Mark>,
+    /// Untracked and ignored directories, whose contents git doesn't list
+    /// but share their mark
+    dirs: HashMap<PathBuf, Mark>,
+}
+
+impl Marks {
+    /// Ask about the repository `dir` is in, unless that was done already
+    pub fn load(&mut self, dir: &Path) {
+        let Ok(toplevel) = git(dir, ["rev-parse", "--show-toplevel"]) else {
+            return;
+        };
+        let toplevel = PathBuf::from(toplevel.trim_end());
+        if self.repos.insert(toplevel.clone()) {
+            self.read(&toplevel);
+        }
+    }
+
+    /// Whether `dir` is the top of a repository not asked about yet
+    pub fn is_new_repo(&self, dir: &Path) -> bool {
+        dir.join(".git").exists()
+            && dir
+                .canonicalize()
+                .is_ok_and(|dir| !self.repos.contains(&dir))
+    }
+
+    /// Ask every known repository again
+    pub fn refresh(&mut self) {
+        self.marks.clear();
+        self.dirs.clear();
+        for toplevel in self.repos.clone() {
+            self.read(&toplevel);
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Mark> {
+        if self.repos.is_empty() {
+            return None;
+        }
+        let path = path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(mark) = self.marks.get(&path) {
+            return Some(*mark);
+        }
+        path.ancestors()
+            .skip(1)
+            .find_map(|dir| self.dirs.get(dir))
+            .copied()
+    }
+
+    fn read(&mut self, toplevel: &Path) {
+        let Ok(status) = git(toplevel, ["status", "--porcelain=v1", "-z", "--ignored"]) else {
+            return;
+        };
+        let mut entries = status.split('\0');
+        while let Some(entry) = entries.next() {
+            let (Some(code), Some(file)) = (entry.get(..2), entry.get(3..)) else {
+                continue;
+            };
+            // renames and copies are followed by where they came from
+            if code.contains(['R', 'C']) {
+                entries.next();
+            }
+            let mark = match code {
+                "!!" => Mark::Ignored,
+                "??" => Mark::Untracked,
+                _ if code.starts_with('A') => Mark::Added,
+                _ => Mark::Modified,
+            };
+            let path = toplevel.join(file.trim_end_matches('/'));
+            if file.ends_with('/') {
+                self.dirs.insert(path.clone(), mark);
+            }
+            self.marks.insert(path.clone(), mark);
+            if mark == Mark::Ignored {
+                continue;
+            }
+            for dir in path.ancestors().skip(1) {
+                if !dir.starts_with(toplevel) || dir == toplevel {
+                    break;
+                }
+                let strongest = self.marks.entry(dir.to_path_buf()).or_insert(mark);
+                *strongest = (*strongest).max(mark);
+            }
+        }
+    }
+}
+
+/// Run git in `dir`, returning its output or its complaint as the error
+fn git<const N: usize>(dir: &Path, args: [&str; N]) -> io::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args.map(OsStr::new))
+        .output()
+        .map_err(|err| io::Error::new(err.kind(), format!("could not run git: {err}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(
+            stderr.lines().next().unwrap_or("git failed").to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
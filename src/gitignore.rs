@@ -0,0 +1,18 @@
+//! Reads the `.gitignore` of each directory as it's scanned, so build
+//! artifacts and other ignored entries can be told apart in the tree
+
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// The patterns in `dir/.gitignore`, if it has one that can be read. Lines
+/// that don't parse are skipped rather than discarding the whole file.
+pub fn load(dir: &Path) -> Option<Gitignore> {
+    let file = dir.join(".gitignore");
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    builder.add(file);
+    builder.build().ok()
+}
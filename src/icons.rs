@@ -0,0 +1,192 @@
+//! The emoji shown before each entry's name, and the plain ASCII the tree
+//! can be drawn with instead. Files are told apart by their extension;
+//! anything not listed gets the plain file icon.
+
+use std::path::Path;
+
+use ratatui::symbols::{border, scrollbar};
+
+use crate::{DirType, Node};
+
+/// Extensions, lowercase, and their icon. Only emoji that are drawn two
+/// columns wide without a variation selector, so the rows stay aligned.
+const BY_EXTENSION: &[(&str, &str)] = &[
+    ("rs", "🦀"),
+    ("py", "🐍"),
+    ("js", "📜"),
+    ("ts", "📜"),
+    ("sh", "🐚"),
+    ("bash", "🐚"),
+    ("zsh", "🐚"),
+    ("fish", "🐚"),
+    ("html", "🌐"),
+    ("css", "🌐"),
+    ("md", "📝"),
+    ("txt", "📝"),
+    ("rst", "📝"),
+    ("pdf", "📕"),
+    ("csv", "📊"),
+    ("json", "🔧"),
+    ("toml", "🔧"),
+    ("yaml", "🔧"),
+    ("yml", "🔧"),
+    ("xml", "🔧"),
+    ("ini", "🔧"),
+    ("conf", "🔧"),
+    ("lock", "🔒"),
+    ("png", "🎨"),
+    ("jpg", "🎨"),
+    ("jpeg", "🎨"),
+    ("gif", "🎨"),
+    ("svg", "🎨"),
+    ("webp", "🎨"),
+    ("bmp", "🎨"),
+    ("ico", "🎨"),
+    ("mp3", "🎵"),
+    ("wav", "🎵"),
+    ("flac", "🎵"),
+    ("ogg", "🎵"),
+    ("mp4", "🎬"),
+    ("mkv", "🎬"),
+    ("mov", "🎬"),
+    ("avi", "🎬"),
+    ("webm", "🎬"),
+    ("zip", "📦"),
+    ("tar", "📦"),
+    ("gz", "📦"),
+    ("tgz", "📦"),
+    ("xz", "📦"),
+    ("bz2", "📦"),
+    ("zst", "📦"),
+    ("7z", "📦"),
+    ("rar", "📦"),
+];
+
+const FILE: &str = "📄";
+
+pub fn icon_for(node: &Node) -> &'static str {
+    match node.type_ {
+        DirType::Dir => "📁",
+        DirType::Symlink => "🔗",
+        DirType::File => {
+            let Some(extension) = Path::new(&node.name).extension() else {
+                return FILE;
+            };
+            let extension = extension.to_string_lossy().to_ascii_lowercase();
+            BY_EXTENSION
+                .iter()
+                .find(|(known, _)| *known == extension)
+                .map_or(FILE, |(_, icon)| icon)
+        }
+    }
+}
+
+/// How many columns the `--du` bars take
+pub const BAR_WIDTH: usize = 10;
+
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// The characters the tree and its frame are drawn with. `Ascii`, from
+/// `--ascii` or the config file, is for terminals and fonts that garble
+/// emoji or box drawing. The tree's prefixes are as wide in one style as in
+/// the other, so the levels line up either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    Unicode,
+    Ascii,
+}
+
+impl RenderStyle {
+    /// What leads to an entry from its parent's line
+    pub fn branch(self, is_last: bool) -> &'static str {
+        match (self, is_last) {
+            (Self::Unicode, false) => "├─",
+            (Self::Unicode, true) => "└─",
+            (Self::Ascii, false) => "+-",
+            (Self::Ascii, true) => "\\-",
+        }
+    }
+
+    /// One level of indentation
+    pub fn indent(self) -> &'static str {
+        match self {
+            Self::Unicode => "│ ",
+            Self::Ascii => "| ",
+        }
+    }
+
+    /// The mark on the hovered row
+    pub fn highlight(self) -> &'static str {
+        match self {
+            Self::Unicode => "▶️",
+            Self::Ascii => "> ",
+        }
+    }
+
+    /// The entry's icon, or a marker for its type
+    pub fn icon(self, type_: &DirType, icon: &'static str) -> &'static str {
+        match (self, type_) {
+            (Self::Unicode, _) => icon,
+            (Self::Ascii, DirType::Dir) => "[D]",
+            (Self::Ascii, DirType::File) => "[F]",
+            (Self::Ascii, DirType::Symlink) => "[L]",
+        }
+    }
+
+    /// The mark on entries that probably can't be removed
+    pub fn lock(self) -> &'static str {
+        match self {
+            Self::Unicode => "🔒",
+            Self::Ascii => "(read-only)",
+        }
+    }
+
+    /// A bar `BAR_WIDTH` columns wide, filled in proportion to `share`
+    /// (between 0 and 1)
+    pub fn bar(self, share: f64) -> String {
+        let (full, empty) = match self {
+            Self::Unicode => ('█', '░'),
+            Self::Ascii => ('#', '.'),
+        };
+        let filled = ((share.clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+        let mut bar = full.to_string().repeat(filled);
+        bar.extend(std::iter::repeat_n(empty, BAR_WIDTH - filled));
+        bar
+    }
+
+    /// The mark on entries the config protects from removal
+    pub fn shield(self) -> &'static str {
+        match self {
+            Self::Unicode => "⛔",
+            Self::Ascii => "(protected)",
+        }
+    }
+
+    pub fn border(self) -> border::Set {
+        match self {
+            Self::Unicode => border::THICK,
+            Self::Ascii => ASCII_BORDER,
+        }
+    }
+
+    pub fn scrollbar(self) -> scrollbar::Set {
+        match self {
+            Self::Unicode => scrollbar::VERTICAL,
+            Self::Ascii => scrollbar::Set {
+                track: "|",
+                thumb: "#",
+                begin: "^",
+                end: "v",
+            },
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Keys for the main actions, read from the `[keys]` table of the config
+//! file (see `config`). Each action takes a key, or a list of keys,
+//! replacing its defaults:
+//!
+//! ```toml
+//! [keys]
+//! quit = ["q", "esc"]
+//! remove = "delete"
+//! ```
+//!
+//! Actions that aren't listed keep their default keys. `Ctrl + C` quits
+//! whatever the config says.
+
+use std::fmt;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// An action that can be bound in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Quit,
+    Select,
+    Open,
+    Remove,
+    RemoveAll,
+    Up,
+    Down,
+}
+
+impl KeyAction {
+    const ALL: [Self; 7] = [
+        Self::Quit,
+        Self::Select,
+        Self::Open,
+        Self::Remove,
+        Self::RemoveAll,
+        Self::Up,
+        Self::Down,
+    ];
+
+    /// How the action is named in the config file
+    fn name(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::Select => "select",
+            Self::Open => "open",
+            Self::Remove => "remove",
+            Self::RemoveAll => "remove_all",
+            Self::Up => "up",
+            Self::Down => "down",
+        }
+    }
+
+    fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Self::Quit => &["q"],
+            Self::Select => &["space"],
+            Self::Open => &["enter"],
+            Self::Remove => &["r"],
+            Self::RemoveAll => &["ctrl+r"],
+            Self::Up => &["up", "k"],
+            Self::Down => &["down", "j"],
+        }
+    }
+}
+
+/// A key together with the modifiers that must be held for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Parse a specification such as `q`, `G`, `ctrl+r`, `space` or `f5`
+    fn parse(spec: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec;
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return None,
+                },
+            },
+        };
+        Some(Self { code, modifiers })
+    }
+
+    fn matches(&self, event: &KeyEvent) -> bool {
+        // the case of a letter already tells whether shift was held
+        let ignored = match event.code {
+            KeyCode::Char(_) => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        self.code == event.code && self.modifiers - ignored == event.modifiers - ignored
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl + ")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt + ")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift + ")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            KeyCode::Esc => write!(f, "Esc"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Which keys trigger each bindable action
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeyAction, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = KeyAction::ALL
+            .iter()
+            .map(|&action| {
+                let keys = action.defaults().iter().filter_map(|spec| Key::parse(spec));
+                (action, keys.collect())
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The keymap from the config file's `[keys]` table, with defaults for
+    /// whatever it leaves out
+    pub fn from_config(config: &toml::Table) -> Result<Self, String> {
+        let mut keymap = Self::default();
+        let Some(keys) = config.get("keys") else {
+            return Ok(keymap);
+        };
+        let keys = keys.as_table().ok_or("`keys` must be a table")?;
+
+        for (name, value) in keys {
+            let Some(action) = KeyAction::ALL
+                .into_iter()
+                .find(|action| action.name() == name)
+            else {
+                let names: Vec<_> = KeyAction::ALL.iter().map(|action| action.name()).collect();
+                return Err(format!(
+                    "unknown action '{name}' in [keys], expected one of: {}",
+                    names.join(", ")
+                ));
+            };
+            let specs = match value {
+                toml::Value::String(spec) => vec![spec.as_str()],
+                toml::Value::Array(specs) => specs
+                    .iter()
+                    .map(|spec| {
+                        spec.as_str()
+                            .ok_or(format!("keys for {name} must be strings"))
+                    })
+                    .collect::<Result<_, _>>()?,
+                _ => {
+                    return Err(format!(
+                        "keys for {name} must be a string or a list of strings"
+                    ))
+                }
+            };
+            let keys = specs
+                .into_iter()
+                .map(|spec| {
+                    Key::parse(spec).ok_or(format!("'{spec}' is not a key name (in {name})"))
+                })
+                .collect::<Result<_, _>>()?;
+            keymap.set(action, keys);
+        }
+        Ok(keymap)
+    }
+
+    fn set(&mut self, action: KeyAction, keys: Vec<Key>) {
+        for (bound, bound_keys) in &mut self.bindings {
+            if *bound == action {
+                *bound_keys = keys;
+                return;
+            }
+        }
+    }
+
+    /// The action bound to a key press, if any
+    pub fn action(&self, event: &KeyEvent) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key.matches(event)))
+            .map(|(action, _)| *action)
+    }
+
+    /// The keys for an action as listed in the help, like `q, Ctrl + C`
+    pub fn describe(&self, action: KeyAction) -> String {
+        self.keys(action)
+            .iter()
+            .map(Key::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The first key for an action, for the hints along the bottom border
+    pub fn first(&self, action: KeyAction) -> String {
+        self.keys(action)
+            .first()
+            .map_or_else(|| "unbound".to_string(), Key::to_string)
+    }
+
+    fn keys(&self, action: KeyAction) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn keymap(config: &str) -> Result<Keymap, String> {
+        Keymap::from_config(&config.parse().unwrap())
+    }
+
+    #[test]
+    fn key_names_are_parsed_with_their_modifiers() {
+        let key = |spec| Key::parse(spec).unwrap();
+        assert_eq!(key("q").code, KeyCode::Char('q'));
+        assert_eq!(key("space").code, KeyCode::Char(' '));
+        assert_eq!(key("F5").code, KeyCode::F(5));
+        assert_eq!(key("+").code, KeyCode::Char('+'));
+        let ctrl_r = key("Ctrl+r");
+        assert_eq!(ctrl_r.code, KeyCode::Char('r'));
+        assert_eq!(ctrl_r.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(ctrl_r.to_string(), "Ctrl + R");
+        assert_eq!(key("ctrl+alt+delete").to_string(), "Ctrl + Alt + Delete");
+
+        assert!(Key::parse("f13").is_none());
+        assert!(Key::parse("hyper+q").is_none());
+        assert!(Key::parse("nothing").is_none());
+    }
+
+    #[test]
+    fn shift_only_matters_through_the_letter() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Some(KeyAction::Quit)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            Some(KeyAction::RemoveAll)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('R'), KeyModifiers::SHIFT)),
+            None
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('r'), KeyModifiers::ALT)),
+            None
+        );
+    }
+
+    #[test]
+    fn configured_keys_replace_the_defaults() {
+        let configured = keymap("[keys]\nquit = [\"x\", \"esc\"]\nremove = \"delete\"\n").unwrap();
+        assert_eq!(configured.describe(KeyAction::Quit), "x, Esc");
+        assert_eq!(configured.first(KeyAction::Remove), "Delete");
+        assert_eq!(
+            configured.action(&press(KeyCode::Char('q'), KeyModifiers::NONE)),
+            None
+        );
+        // the rest keep theirs
+        assert_eq!(configured.describe(KeyAction::Up), "Up, k");
+
+        let unbound = keymap("[keys]\nselect = []\n").unwrap();
+        assert_eq!(unbound.first(KeyAction::Select), "unbound");
+    }
+
+    #[test]
+    fn bad_bindings_are_errors() {
+        let err = keymap("[keys]\njump = \"j\"\n").unwrap_err();
+        assert!(err.starts_with("unknown action 'jump' in [keys]"), "{err}");
+        assert_eq!(
+            keymap("[keys]\nquit = \"hyper+q\"\n").unwrap_err(),
+            "'hyper+q' is not a key name (in quit)"
+        );
+        assert!(keymap("[keys]\nquit = 1\n").is_err());
+        assert!(keymap("[keys]\nquit = [1]\n").is_err());
+        assert!(keymap("keys = 1\n").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Handing entries over to other programs: whatever the platform opens a
+//! file with by default, its file manager, and the user's `$EDITOR`.
+
+use std::{
+    env, io,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Open `path` the way the desktop would on a double click, a directory in
+/// the file manager. The opener is left running without waiting for it.
+pub fn open(path: &Path) -> io::Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "cmd"
+    } else {
+        "xdg-open"
+    };
+    let mut command = Command::new(opener);
+    if cfg!(windows) {
+        // the empty argument is the window title `start` would otherwise
+        // take a quoted path for
+        command.args(["/C", "start", ""]);
+    }
+    command.arg(path);
+    start(command, opener)
+}
+
+/// Show `path` in the file manager, selected where the platform can do
+/// that: Finder and Explorer can, elsewhere the directory it's in is
+/// opened instead. Left running like `open`.
+pub fn reveal(path: &Path) -> io::Result<()> {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        return start(command, "open");
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+
+        let mut command = Command::new("explorer");
+        // Explorer wants the path quoted after the comma, not the whole
+        // argument
+        command.raw_arg(format!("/select,\"{}\"", path.display()));
+        return start(command, "explorer");
+    }
+    #[allow(unreachable_code)]
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+    open(parent.unwrap_or(path))
+}
+
+/// Spawn `command` without waiting for it, with its output discarded so it
+/// can't draw over the tree. Only failing to start it is reported.
+fn start(mut command: Command, program: &str) -> io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| missing(program, err))?;
+    // reaped whenever it exits, so it isn't left a zombie
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// The editor `$EDITOR` names, split into the program and any arguments
+/// given with it, such as `code --wait`. `None` when it isn't set.
+pub fn editor() -> Option<Vec<String>> {
+    let editor = env::var("EDITOR").ok()?;
+    let words: Vec<_> = editor.split_whitespace().map(str::to_string).collect();
+    (!words.is_empty()).then_some(words)
+}
+
+/// Run `editor` on `path` and wait for it to exit. The terminal has to be
+/// handed over first.
+pub fn edit(editor: &[String], path: &Path) -> io::Result<()> {
+    let status = Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(path)
+        .status()
+        .map_err(|err| missing(&editor[0], err))?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {status}",
+            editor[0]
+        )));
+    }
+    Ok(())
+}
+
+/// Say which program wasn't found, rather than leaving it to look like the
+/// path was missing
+fn missing(program: &str, err: io::Error) -> io::Error {
+    if err.kind() == io::ErrorKind::NotFound {
+        io::Error::new(err.kind(), format!("{program} isn't installed"))
+    } else {
+        err
+    }
+}
@@ -1,66 +1,784 @@
 use std::{
-    cell::RefCell,
-    fs, io,
+    cell::{Cell, OnceCell, RefCell},
+    cmp::Ordering,
+    collections::{HashSet, VecDeque},
+    env,
+    ffi::OsString,
+    fmt, fs,
+    io::{self, Write},
+    iter, mem,
+    path::{Component, Path, PathBuf},
+    process::Command,
     rc::{Rc, Weak},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant, SystemTime},
     vec,
 };
 
+use globset::GlobBuilder;
+use ignore::gitignore::Gitignore;
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
-    layout::Alignment,
-    style::{Color, Style, Stylize},
-    symbols::border,
-    text::Line,
+    crossterm::event::{
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    text::{Line, Span},
     widgets::{
         block::{Position, Title},
-        Block, List, ListDirection, ListItem, ListState,
+        Block, Borders, Clear, List, ListDirection, ListItem, ListState, Padding, Paragraph,
+        Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap,
     },
     Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
+mod audit;
+mod bookmarks;
+mod cli;
+mod clipboard;
+mod config;
+mod details;
+mod disk;
+mod duplicates;
+mod expansion;
+mod finder;
+mod format;
+mod git;
+mod gitignore;
+mod icons;
+mod keymap;
+mod launch;
+mod own_files;
+mod path_filter;
+mod progress;
+mod protect;
+mod restore;
+mod safe_list;
+mod scanner;
+mod script;
+mod sizer;
+mod svg;
+mod symlink;
+#[cfg(test)]
+mod test_dir;
+mod theme;
 mod tui;
+mod watch;
+
+use audit::AuditLog;
+use bookmarks::Bookmarks;
+use config::Config;
+use duplicates::Duplicates;
+use finder::Finder;
+use icons::RenderStyle;
+use keymap::{KeyAction, Keymap};
+use own_files::OwnFiles;
+use path_filter::PathFilter;
+use protect::Protected;
+use safe_list::SafeList;
+use theme::Theme;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum DirType {
     File,
     Dir,
     Symlink,
 }
 
+impl DirType {
+    /// What's at `path`. A symlink to a directory counts as a directory.
+    fn of(path: &Path) -> Self {
+        if path.is_dir() {
+            DirType::Dir
+        } else if path.is_symlink() {
+            DirType::Symlink
+        } else {
+            DirType::File
+        }
+    }
+}
+
 type NodeRef = Rc<RefCell<Node>>;
+/// Selected paths, for cheap lookups while listing the tree
+type SelectedPaths<'a> = HashSet<&'a str>;
+
+/// How many selected paths a removal prompt lists before summarizing the rest
+const MAX_LISTED_PATHS: usize = 8;
+
+/// How many levels below the hovered node a subtree toggle will expand
+const MAX_SUBTREE_DEPTH: usize = 32;
+
+/// How long each turn of an expand-all may spend reading directories
+/// before the interface gets to draw and take keys again
+const EXPAND_BUDGET: Duration = Duration::from_millis(30);
+
+/// Selecting more descendants than this at once asks for confirmation
+const LARGE_SUBTREE: usize = 1_000;
+
+/// How many entries of a directory are read at a time, so that opening a
+/// huge one doesn't lock up the interface
+const SCAN_CHUNK: usize = 500;
+
+/// Rows kept in view above and below the hovered one while scrolling
+const SCROLL_MARGIN: usize = 3;
+
+/// How many directories the flat view reads at once while it fills in
+const FLAT_SCANS: usize = 8;
+
+/// How many directories on screen are measured at once in the background
+const SIZE_JOBS: usize = 8;
+
+/// Shown in place of the entries of a directory that haven't been read yet
+const LOADING_MORE: &str = "… (loading more)";
+
+/// Frames of the indicator shown while directories are read in the
+/// background, and how long each one stays up
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a message stays under the list, unless a key press clears it
+/// sooner
+const STATUS_DURATION: Duration = Duration::from_secs(3);
+
+/// How long the question before a removal may spend counting what would
+/// go, before it settles for a lower bound
+const MEASURE_BUDGET: Duration = Duration::from_millis(500);
+
+/// How long `=` may spend reading files to compare them, before it shows
+/// the duplicates found so far
+const DUPLICATE_BUDGET: Duration = Duration::from_secs(5);
+
+/// The colors duplicate sets are told apart by, in turn
+const DUPLICATE_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightRed,
+];
+
+/// Fewest columns a name is elided to, however little room its row has
+const MIN_NAME_WIDTH: usize = 8;
+
+/// Longest gap between two left clicks on a row that still opens it
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Every key and what it does, as listed by the help overlay. The repeat key
+/// is configurable, so it's added to the list separately.
+const KEYBINDINGS: &[(HelpKeys, &str)] = &[
+    (Mapped(&[KeyAction::Up, KeyAction::Down]), "Move"),
+    (Fixed("g / G, Home / End"), "Jump to the top / bottom"),
+    (Fixed("PageUp / PageDown"), "Move a screenful up / down"),
+    (
+        Mapped(&[KeyAction::Open]),
+        "Open or close the hovered directory",
+    ),
+    (
+        Fixed("*"),
+        "Open or close everything below the hovered directory",
+    ),
+    (
+        Fixed("E / C"),
+        "Open everything below the hovered directory / close it all",
+    ),
+    (
+        Fixed("h / l, Left / Right"),
+        "Close the directory or go to its parent / open it",
+    ),
+    (Fixed(":"), "Go to a row by its number"),
+    (Mapped(&[KeyAction::Select]), "Select or deselect"),
+    (
+        Fixed("Click / Right-click"),
+        "Hover / select a row; double-click to open or close",
+    ),
+    (
+        Fixed("V"),
+        "Start selecting a range, then move to extend it",
+    ),
+    (
+        Fixed("a"),
+        "Select everything shown next to the hovered entry",
+    ),
+    (Fixed("i"), "Invert the selection among the entries shown"),
+    (Fixed("A"), "Select everything inside the hovered directory"),
+    (Fixed("D + 0-9"), "Select everything deeper than that level"),
+    (
+        Fixed("K + f/d/l"),
+        "Select every file, directory or link shown",
+    ),
+    (Fixed("+"), "Select loaded entries matching a glob"),
+    (
+        Fixed("I"),
+        "Toggle selecting directories with their contents",
+    ),
+    (Fixed("Esc"), "Deselect all"),
+    (
+        Fixed("/"),
+        "Only show entries whose name contains some text",
+    ),
+    (Fixed("Ctrl + D"), "Browse another directory"),
+    (Fixed("Ctrl + N"), "Browse another directory in a new tab"),
+    (
+        Fixed("Tab / Shift + Tab"),
+        "Switch to the next or previous tab",
+    ),
+    (Fixed("Ctrl + W"), "Close the tab"),
+    (Fixed(">"), "Only show entries of at least some size"),
+    (Fixed("<"), "Only show files older or newer than some age"),
+    (Fixed("."), "Show or hide dotfiles"),
+    (Fixed("z"), "List every file below the root, or the tree"),
+    (Fixed("n / N"), "Go to the next / previous selected entry"),
+    (
+        Fixed("Ctrl + G"),
+        "Show everything, dim ignored files, or only ignored files",
+    ),
+    (Fixed("T"), "Show when entries were last accessed"),
+    (
+        Fixed("s / S"),
+        "Sort by name, size or modification time / reverse",
+    ),
+    (
+        Fixed("t"),
+        "Show what changed in the last hour, newest first",
+    ),
+    (Fixed("U"), "Measure the hovered entry"),
+    (
+        Fixed("F5, Ctrl + L"),
+        "Read the hovered directory from disk again",
+    ),
+    (Fixed("L"), "Show where the hovered symlink leads"),
+    (Fixed("B"), "Follow the largest entry"),
+    (Fixed("c"), "Mark a directory, then compare it with another"),
+    (Fixed("="), "Mark files with the same contents, or clear"),
+    (Fixed("-"), "Select all but one of each set of duplicates"),
+    (Fixed("M"), "Move the selection to another directory"),
+    (Fixed("o"), "Open the hovered entry with its default app"),
+    (Fixed("O"), "Show the hovered entry in the file manager"),
+    (Fixed("e, Ctrl + O"), "Edit the hovered file in $EDITOR"),
+    (Fixed("!"), "Open a shell in the hovered directory"),
+    (Fixed("Ctrl + Z"), "Suspend to the shell, fg to come back"),
+    (
+        Fixed("Ctrl + P"),
+        "Find a loaded entry by a few letters of its path",
+    ),
+    (Fixed("m"), "Bookmark the hovered directory, or forget it"),
+    (Fixed("'"), "Browse a bookmarked directory"),
+    (Fixed("y"), "Copy the hovered entry's path"),
+    (Fixed("p"), "Show details of the hovered entry"),
+    (Fixed("P"), "Show where the hovered entry really is"),
+    (Fixed("X"), "Save the view as an SVG file"),
+    (Fixed("0"), "Remove empty files"),
+    (
+        Fixed("Ctrl + E"),
+        "Select empty directories and ask to remove them",
+    ),
+    (
+        Fixed("x"),
+        "Empty the hovered directory, keeping the directory",
+    ),
+    (Mapped(&[KeyAction::Remove]), "Remove the hovered entry"),
+    (
+        Mapped(&[KeyAction::RemoveAll]),
+        "Remove everything selected",
+    ),
+    (
+        Fixed("Ctrl + T"),
+        "Toggle moving removed entries to the trash",
+    ),
+    (Fixed("u"), "Restore the last entry moved to the trash"),
+    (
+        Fixed("Ctrl + U"),
+        "List what was moved to the trash, to delete for good",
+    ),
+    (Fixed("?"), "Show this help"),
+    (Mapped(&[KeyAction::Quit]), "Quit (so does Ctrl + C)"),
+];
+
+/// The keys in a row of the help table, either fixed or whatever the keymap
+/// binds to the actions
+enum HelpKeys {
+    Fixed(&'static str),
+    Mapped(&'static [KeyAction]),
+}
+use HelpKeys::{Fixed, Mapped};
 
 type Depth = usize;
-type IsLastOfFolder = bool;
-type Name = String;
-type IsSelected = bool;
-type TupleNode = (Name, DirType, Depth, IsLastOfFolder, IsSelected);
+type Accessed = Option<SystemTime>;
+/// Where a symlink points, as written in the link, and whether that's
+/// missing
+type LinkTarget = Option<(String, bool)>;
+
+/// One row of the list, as `to_enriched_array` reports it
+#[derive(Debug)]
+struct Row {
+    name: String,
+    type_: DirType,
+    depth: Depth,
+    is_last: bool,
+    is_selected: bool,
+    accessed: Accessed,
+    /// `None` for directories that haven't been measured yet
+    size: Option<u64>,
+    is_ignored: bool,
+    /// The row stands for entries still to be read, see `LOADING_MORE`
+    is_placeholder: bool,
+    link_target: LinkTarget,
+    /// The emoji shown before the name, see `icons`
+    icon: &'static str,
+    /// How many entries a closed directory holds, `Some(None)` when it
+    /// can't be read
+    item_count: Option<Option<usize>>,
+    /// The permissions suggest removing the entry would fail
+    is_locked: bool,
+    /// What part of its directory's size the entry takes up, once both are
+    /// measured
+    share: Option<f64>,
+    /// A pattern in the config protects the entry from removal
+    is_protected: bool,
+    /// The entry is one of irm's own files, or inside one
+    is_own: bool,
+    /// Why the directory couldn't be read when last tried
+    read_error: Option<io::ErrorKind>,
+    /// What `git status` says about the entry, inside a repository
+    git_mark: Option<git::Mark>,
+    /// Which set of files with the same contents the file is in, once `=`
+    /// has looked for them
+    duplicate_group: Option<usize>,
+    /// For a directory with anything selected in it, how many of the
+    /// entries read below it that aren't directories are selected, out of
+    /// how many
+    selection_count: Option<(usize, usize)>,
+}
+
+impl Row {
+    /// The row standing for the entries of a directory still to be read
+    fn placeholder(type_: DirType, depth: Depth) -> Self {
+        Self {
+            name: LOADING_MORE.to_string(),
+            type_,
+            depth,
+            is_last: true,
+            is_selected: false,
+            accessed: None,
+            size: None,
+            is_ignored: false,
+            is_placeholder: true,
+            link_target: None,
+            icon: "",
+            item_count: None,
+            is_locked: false,
+            share: None,
+            is_protected: false,
+            is_own: false,
+            read_error: None,
+            git_mark: None,
+            duplicate_group: None,
+            selection_count: None,
+        }
+    }
+}
+
+/// How recently `t` shows files as modified
+const RECENT_AGE: Duration = Duration::from_secs(60 * 60);
+
+const ACCESSED_CAVEAT: &str =
+    "Access times are approximate on filesystems mounted with relatime or noatime";
+
+/// Settings that change what `to_enriched_array` reports for each row
+#[derive(Debug, Clone, Copy)]
+struct ViewOptions<'a> {
+    /// A selected directory also marks everything below it as selected
+    implies_children: bool,
+    /// Read each row's last access time
+    show_accessed: bool,
+    /// Hide entries smaller than this many bytes (recursively for dirs)
+    min_size: Option<u64>,
+    /// Only show files modified on the kept side of a cutoff, along with
+    /// the directories leading to them
+    age: Option<AgeFilter>,
+    /// Only show entries whose lowercased name contains this, along with
+    /// the directories leading to them
+    search: Option<&'a str>,
+    /// Show entries whose name starts with a dot
+    show_hidden: bool,
+    ignored: IgnoredView,
+    sort: Sort,
+    /// To mark the entries it protects
+    protected: &'a Protected,
+    own_files: &'a OwnFiles,
+    git: &'a git::Marks,
+    duplicates: &'a Duplicates,
+    /// The `--include` and `--exclude` globs
+    paths: &'a PathFilter,
+    /// List every file below the root on its own row, by its path from
+    /// there, in place of the tree
+    flat: bool,
+}
+
+/// Which files the age filter lets through, by how long ago they were
+/// last modified
+#[derive(Debug, Clone, Copy)]
+struct AgeFilter {
+    /// Keep files at least `age` old, rather than younger than that
+    older: bool,
+    age: Duration,
+}
+
+impl AgeFilter {
+    fn keeps(self, modified: Option<SystemTime>) -> bool {
+        let Some(modified) = modified else {
+            return false;
+        };
+        // modified in the future counts as just now
+        let elapsed = modified.elapsed().unwrap_or_default();
+        if self.older {
+            elapsed >= self.age
+        } else {
+            elapsed < self.age
+        }
+    }
+
+    fn describe(self) -> String {
+        let side = if self.older { "more" } else { "less" };
+        format!("modified {side} than {} ago", format::age(self.age))
+    }
+}
+
+/// How entries matched by a `.gitignore` are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IgnoredView {
+    Everything,
+    /// Show ignored entries dimmed
+    Dim,
+    /// Only show ignored entries, and the directories leading to them
+    OnlyIgnored,
+}
+
+impl IgnoredView {
+    fn next(self) -> Self {
+        match self {
+            Self::Everything => Self::Dim,
+            Self::Dim => Self::OnlyIgnored,
+            Self::OnlyIgnored => Self::Everything,
+        }
+    }
+}
+
+/// What the entries of each directory are ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortMode {
+    Name,
+    /// Largest first
+    Size,
+    /// Most recently modified first
+    Modified,
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Size,
+            Self::Size => Self::Modified,
+            Self::Modified => Self::Name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sort {
+    mode: SortMode,
+    reverse: bool,
+    /// List directories before files, whatever the mode
+    directories_first: bool,
+}
+
+impl Sort {
+    /// A short form of `describe`, for the title
+    fn label(&self) -> String {
+        let mode = match self.mode {
+            SortMode::Name => "name",
+            SortMode::Size => "size",
+            SortMode::Modified => "modified",
+        };
+        if self.reverse {
+            format!("{mode}, reversed")
+        } else {
+            mode.to_string()
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match (self.mode, self.reverse) {
+            (SortMode::Name, false) => "name",
+            (SortMode::Name, true) => "name, reversed",
+            (SortMode::Size, false) => "size, largest first",
+            (SortMode::Size, true) => "size, smallest first",
+            (SortMode::Modified, false) => "modification time, newest first",
+            (SortMode::Modified, true) => "modification time, oldest first",
+        }
+    }
+
+    /// Put `children` in this order. Ties keep their order, which is by
+    /// name as long as they come straight from `Node::children`.
+    fn apply(&self, children: &mut [NodeRef]) {
+        // children are stored in exactly this order
+        if self.mode == SortMode::Name && !self.reverse && self.directories_first {
+            return;
+        }
+        children.sort_by(|a, b| self.compare(&a.borrow(), &b.borrow()));
+    }
+
+    fn compare(&self, a: &Node, b: &Node) -> Ordering {
+        let groups = if self.directories_first {
+            (a.type_ != DirType::Dir).cmp(&(b.type_ != DirType::Dir))
+        } else {
+            Ordering::Equal
+        };
+        let order = match self.mode {
+            SortMode::Name => {
+                (a.name.to_lowercase(), &a.name).cmp(&(b.name.to_lowercase(), &b.name))
+            }
+            SortMode::Size => match (a.known_size(), b.known_size()) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                // still to be measured, so last whichever way round
+                (Some(_), None) => return groups.then(Ordering::Less),
+                (None, Some(_)) => return groups.then(Ordering::Greater),
+                (None, None) => Ordering::Equal,
+            },
+            SortMode::Modified => b.modified().cmp(&a.modified()),
+        };
+        groups.then(if self.reverse { order.reverse() } else { order })
+    }
+}
 
 #[derive(Debug)]
 struct DirTree {
     base_node: NodeRef,
+    /// Paths that `remove_node` refuses to touch
+    safe_list: SafeList,
+    /// The config's patterns for entries `remove_node` refuses to touch
+    protected: Protected,
+    /// irm's own files in this tree, which it refuses to touch too
+    own_files: OwnFiles,
+    delete_mode: DeleteMode,
+    /// Update the tree as if entries were removed, but leave the disk alone
+    dry_run: bool,
+    /// How many levels below the root can be opened, from `--max-depth`
+    max_depth: Option<usize>,
+}
+
+/// What `remove_node` does with what it removes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeleteMode {
+    Permanent,
+    /// Move it to the system trash, from where it can be restored
+    Trash,
 }
 
 impl DirTree {
-    fn new(path: String) -> Self {
+    fn new(
+        path: PathBuf,
+        safe_list: SafeList,
+        protected: Protected,
+        own_files: &OwnFiles,
+        delete_mode: DeleteMode,
+        dry_run: bool,
+        max_depth: Option<usize>,
+    ) -> Self {
         Self {
-            base_node: Node::new(path, DirType::Dir),
+            own_files: own_files.below(&path),
+            base_node: Node::new(path.into_os_string(), DirType::Dir),
+            safe_list,
+            protected,
+            delete_mode,
+            dry_run,
+            max_depth,
+        }
+    }
+
+    /// Remember which directories are open, for `App::restore_expanded` to
+    /// open again next time this root is browsed
+    fn save_expanded(&self) -> io::Result<()> {
+        let base = self.base_node.borrow().path();
+        let mut open = Vec::new();
+        self.base_node.borrow().open_dirs(&mut open);
+        let relative: Vec<PathBuf> = open
+            .iter()
+            .filter_map(|path| path.strip_prefix(&base).ok())
+            .map(|path| {
+                if path.as_os_str().is_empty() {
+                    PathBuf::from(".")
+                } else {
+                    path.to_path_buf()
+                }
+            })
+            .collect();
+        expansion::save(&fs::canonicalize(&base)?, &relative)
+    }
+
+    /// The protect pattern that matches `node`, or a directory it's in
+    fn protection(&self, node: &Node) -> Option<&str> {
+        let path = node.path();
+        let path = std::path::absolute(&path).unwrap_or(path);
+        path.ancestors()
+            .find_map(|ancestor| self.protected.matching(ancestor))
+    }
+
+    /// Why the protect patterns forbid removing `node`, or with `recursive`
+    /// the loaded part of it. `remove_node` also looks through what isn't
+    /// loaded.
+    fn protection_refusal(&self, node: &NodeRef, recursive: bool) -> Option<String> {
+        if let Some(pattern) = self.protection(&node.borrow()) {
+            return Some(format!("it's protected by {pattern}"));
+        }
+        if !recursive || self.protected.is_empty() {
+            return None;
+        }
+        let mut nodes = Vec::new();
+        node.borrow().descendants(&mut nodes);
+        nodes.iter().find_map(|inside| {
+            let inside = inside.borrow();
+            let pattern = self.protected.matching(&inside.path())?;
+            Some(format!(
+                "it contains {}, protected by {pattern}",
+                inside.full_path()
+            ))
+        })
+    }
+
+    /// Whether `--max-depth` allows opening `node`
+    fn can_open(&self, node: &Node) -> bool {
+        self.max_depth.is_none_or(|max| node.depth() < max)
+    }
+
+    /// How many levels below `node` a recursive open may reach, `node`
+    /// itself being level 0
+    fn open_limit(&self, node: &Node) -> Option<usize> {
+        match self.max_depth {
+            Some(max) => max
+                .checked_sub(node.depth() + 1)
+                .map(|limit| limit.min(MAX_SUBTREE_DEPTH)),
+            None => Some(MAX_SUBTREE_DEPTH),
         }
     }
 
-    fn to_array(&self) -> Vec<String> {
+    fn to_array(&self, view: ViewOptions) -> Vec<String> {
+        if view.flat {
+            return self
+                .flat_files(view)
+                .into_iter()
+                .map(|(_, file)| file.borrow().full_path())
+                .collect();
+        }
         let mut array = Vec::new();
-        self.base_node.borrow().to_array(&mut array);
+        self.base_node.borrow().to_array(&mut array, view);
         array
     }
 
-    fn find_node(&self, path: &str) -> Option<NodeRef> {
-        let mut node: NodeRef = self.base_node.clone();
+    /// Every file read so far below the root, whether its directory is open
+    /// or not, with its path from the root. They're ordered by that path,
+    /// or by size or modification time across the whole list.
+    fn flat_files(&self, view: ViewOptions) -> Vec<(String, NodeRef)> {
+        let mut files = Vec::new();
+        self.base_node.borrow().collect_files(&mut files, view);
+        let base = self.base_node.borrow().path();
+        let mut files: Vec<(String, NodeRef)> = files
+            .into_iter()
+            .map(|file| {
+                let path = file.borrow().path();
+                let relative = path.strip_prefix(&base).unwrap_or(&path);
+                (relative.to_string_lossy().into_owned(), file)
+            })
+            .collect();
+        files.sort_by(|(a, _), (b, _)| {
+            let order = (a.to_lowercase(), a).cmp(&(b.to_lowercase(), b));
+            if view.sort.mode == SortMode::Name && view.sort.reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        if view.sort.mode != SortMode::Name {
+            files.sort_by(|(_, a), (_, b)| view.sort.compare(&a.borrow(), &b.borrow()));
+        }
+        files
+    }
 
-        if path == "." {
-            return Some(node.clone());
+    /// Directories the flat view still has to read: ones never listed,
+    /// and ones part way through a background scan
+    fn unlisted_dirs(&self, view: ViewOptions) -> Vec<NodeRef> {
+        let mut dirs = Vec::new();
+        Node::collect_unlisted(self.base_node.clone(), &mut dirs, view);
+        dirs.retain(|dir| self.can_open(&dir.borrow()));
+        dirs
+    }
+
+    /// The directories below the root holding nothing, or only directories
+    /// that hold nothing in turn, children before their parents. Only
+    /// what's been read counts, so a directory still being read never does.
+    fn collect_empty_dirs(&self) -> Vec<NodeRef> {
+        let mut dirs = Vec::new();
+        for child in self.base_node.borrow().children.borrow().iter() {
+            Node::collect_empty(child, &mut dirs);
+        }
+        dirs
+    }
+
+    /// `Node::path` for the entry at `path`, which keeps whatever in its
+    /// name isn't valid UTF-8. A path that isn't loaded is taken as it is.
+    fn disk_path(&self, path: &str) -> PathBuf {
+        match self.find_node(path) {
+            Some(node) => node.borrow().path(),
+            None => PathBuf::from(path),
+        }
+    }
+
+    /// `Node::display_path` for the entry at `path`, which needn't be
+    /// loaded
+    fn display_path(&self, path: &str) -> String {
+        match self.find_node(path) {
+            Some(node) => node.borrow().display_path(),
+            None => Path::new(path)
+                .strip_prefix(self.base_node.borrow().path())
+                .ok()
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .map_or_else(
+                    || path.to_string(),
+                    |relative| relative.to_string_lossy().into_owned(),
+                ),
         }
+    }
+
+    /// Every path read so far that the view would show, open or not
+    fn loaded_paths(&self, view: ViewOptions) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.base_node.borrow().loaded_paths(&mut paths, view);
+        paths
+    }
+
+    /// The names leading from the root to `path`, compared component by
+    /// component so that separators and `.` are handled the platform's way
+    fn names_below_root(&self, path: &str) -> Option<Vec<String>> {
+        let base_path = self.base_node.borrow().path();
+        let relative = Path::new(path).strip_prefix(base_path).ok()?;
+
+        relative
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn find_node(&self, path: &str) -> Option<NodeRef> {
+        let mut node: NodeRef = self.base_node.clone();
 
-        for name in path.replace("./", "").split('/') {
+        for name in self.names_below_root(path)? {
             let cloned = node.clone();
             let _node = cloned.borrow();
             let children = _node.children.borrow();
@@ -75,55 +793,277 @@ impl DirTree {
         Some(node.clone())
     }
 
-    fn remove_node(&self, path: &str) {
-        let found = self.find_node(path).unwrap().clone();
+    /// Like `find_node`, but scans any directories along `path` that haven't
+    /// been read yet
+    fn load_path(&self, path: &str) -> Option<NodeRef> {
+        let mut node: NodeRef = self.base_node.clone();
+
+        for name in self.names_below_root(path)? {
+            let needs_scan = {
+                let node = node.borrow();
+                node.type_ == DirType::Dir && node.children.borrow().is_empty()
+            };
+            if needs_scan {
+                Node::scan_dir(node.clone()).ok()?;
+            }
+            Node::finish_scan(node.clone()).ok()?;
+
+            let child = node
+                .borrow()
+                .children
+                .borrow()
+                .iter()
+                .find(|c| c.borrow().name == name)
+                .cloned()?;
+            node = child;
+        }
+        Some(node)
+    }
+
+    /// Remove the node at `path` from disk and from the tree. Directories are
+    /// only removed with their contents when `recursive` is set, otherwise
+    /// they have to be empty. `progress` hears how many entries a directory
+    /// deleted for good has lost so far.
+    fn remove_node(
+        &self,
+        path: &str,
+        recursive: bool,
+        progress: &mut impl FnMut(u64),
+    ) -> io::Result<()> {
+        let found = self.find_node(path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "it isn't loaded in the tree")
+        })?;
         let node = found.borrow();
         let parent = node.parent.borrow();
+        // the row's path can't name an entry that isn't valid UTF-8
+        let path = &node.path();
 
         if parent.upgrade().is_none() {
-            return;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "refusing to remove the root directory",
+            ));
+        }
+        let refuse = |reason: String| Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+        if let Some(safe) = self.safe_list.protecting(path) {
+            return refuse(format!("{} is on the safe list", safe.display()));
+        }
+        if let Some(own) = self.own_files.owning(path) {
+            return refuse(format!("{} is one of irm's own files", own.display()));
+        }
+        if recursive && node.type_ == DirType::Dir {
+            if let Some(safe) = self.safe_list.inside(path) {
+                return refuse(format!(
+                    "it contains {}, which is on the safe list",
+                    safe.display()
+                ));
+            }
+            if let Some(own) = self.own_files.inside(path) {
+                return refuse(format!(
+                    "it contains {}, one of irm's own files",
+                    own.display()
+                ));
+            }
         }
+        if let Some(pattern) = self.protection(&node) {
+            return refuse(format!("it's protected by {pattern}"));
+        }
+        if node.is_ambiguous() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "its name isn't valid UTF-8 and reads the same as another's next to it",
+            ));
+        }
+        if recursive && node.type_ == DirType::Dir && !node.is_symlink() {
+            if let Some((inside, pattern)) = self.protected.inside(path) {
+                return refuse(format!(
+                    "it contains {}, protected by {pattern}",
+                    inside.display()
+                ));
+            }
+        }
+
+        // neither trashing nor pretending would otherwise notice
+        if (self.dry_run || self.delete_mode == DeleteMode::Trash) && !recursive {
+            let is_full_dir = node.type_ == DirType::Dir
+                && !node.is_symlink()
+                && fs::read_dir(path)?.next().is_some();
+            if is_full_dir {
+                return Err(io::Error::new(
+                    io::ErrorKind::DirectoryNotEmpty,
+                    "directory not empty",
+                ));
+            }
+        }
+
+        if self.dry_run {
+            // only the tree changes
+        } else if self.delete_mode == DeleteMode::Trash {
+            // a directory goes as a single item, contents and all
+            trash::delete(path).map_err(|err| io::Error::other(err.to_string()))?;
+        } else {
+            match node.type_ {
+                // a link to a directory is scanned as a directory, but only the
+                // link itself goes
+                DirType::Dir if node.is_symlink() => symlink::remove(path)?,
+                DirType::Dir if recursive => {
+                    if let Err(err) = disk::remove_tree(path, progress) {
+                        // whatever went before the failure is gone for good
+                        node.prune_missing();
+                        node.invalidate_size();
+                        return Err(err);
+                    }
+                }
+                DirType::Dir => fs::remove_dir(path)?,
+                DirType::File => fs::remove_file(path)?,
+                DirType::Symlink => symlink::remove(path)?,
+            }
+        }
+
+        let parent = parent.upgrade().unwrap();
         parent
-            .upgrade()
-            .unwrap()
             .borrow()
             .children
             .borrow_mut()
-            .retain(|c| c.borrow().name != node.name);
-
-        match node.type_ {
-            DirType::Dir => fs::remove_dir_all(node.full_path()).unwrap(),
-            DirType::File => fs::remove_file(node.full_path()).unwrap(),
-            DirType::Symlink => todo!("implement symlinks"),
+            .retain(|c| c.borrow().os_name != node.os_name);
+        // counted before, and read again should it end up empty
+        parent.borrow_mut().item_count.take();
+        if self.dry_run {
+            node.discount_size();
+        } else {
+            node.invalidate_size();
         }
+        Ok(())
     }
 
-    fn to_enriched_array(&self, selected_nodes: &Vec<NodeRef>) -> Vec<TupleNode> {
-        let mut items = Vec::new();
-        self.base_node
+    /// Drop the entry at `path` from the tree once it's been moved away on
+    /// disk, see `App::move_selection`
+    fn detach(&self, path: &str) {
+        let Some(found) = self.find_node(path) else {
+            return;
+        };
+        let node = found.borrow();
+        let Some(parent) = node.parent.borrow().upgrade() else {
+            return;
+        };
+        parent
             .borrow()
-            .to_enriched_array(&mut items, selected_nodes, 0, false);
+            .children
+            .borrow_mut()
+            .retain(|c| c.borrow().os_name != node.os_name);
+        parent.borrow_mut().item_count.take();
+        node.invalidate_size();
+    }
+
+    /// Every scanned node deeper than `depth`, keeping only the shallowest
+    /// ones so that a selected directory stands in for its whole subtree
+    fn nodes_below_depth(&self, depth: Depth) -> Vec<NodeRef> {
+        let mut nodes = Vec::new();
+        Node::collect_at_depth(self.base_node.clone(), depth + 1, 0, &mut nodes);
+        nodes
+    }
+
+    fn to_enriched_array(&self, selected_paths: &[String], view: ViewOptions) -> Vec<Row> {
+        let selected: SelectedPaths = selected_paths.iter().map(String::as_str).collect();
+        if view.flat {
+            let files = self.flat_files(view);
+            let len = files.len();
+            return files
+                .into_iter()
+                .enumerate()
+                .map(|(i, (relative, file))| {
+                    let file = file.borrow();
+                    let full_path = file.full_path();
+                    let is_selected = if view.implies_children {
+                        Path::new(&full_path)
+                            .ancestors()
+                            .filter_map(Path::to_str)
+                            .any(|path| selected.contains(path))
+                    } else {
+                        selected.contains(full_path.as_str())
+                    };
+                    let mut row = file.row(view, 0, i + 1 == len, is_selected);
+                    row.name = relative;
+                    row
+                })
+                .collect();
+        }
+        let root_selected = selected.contains(self.base_node.borrow().full_path().as_str());
+
+        let mut items = Vec::new();
+        self.base_node.borrow().to_enriched_array(
+            &mut items,
+            &selected,
+            view,
+            0,
+            false,
+            root_selected,
+        );
         items
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 struct Node {
+    /// The name as shown, with anything that isn't valid UTF-8 replaced.
+    /// Rows are told apart by paths made of these.
     name: String,
+    /// The name as it is on disk, which everything touching the disk goes
+    /// by
+    os_name: OsString,
     type_: DirType,
     is_open: bool,
     parent: RefCell<Weak<RefCell<Node>>>,
     children: RefCell<Vec<NodeRef>>,
+    accessed: OnceCell<Accessed>,
+    /// Read once, since it's shown on every frame
+    link_target: OnceCell<LinkTarget>,
+    /// Read once, since sorting asks for it on every frame
+    modified: OnceCell<Option<SystemTime>>,
+    /// Entries on disk, counted once for as long as the directory hasn't
+    /// been read. `None` when it can't be listed.
+    item_count: OnceCell<Option<usize>>,
+    /// Read once, see `is_locked`
+    locked: OnceCell<bool>,
+    /// Why listing the directory failed the last time it was tried
+    read_error: Cell<Option<io::ErrorKind>>,
+    /// Every entry has been read at least once, even if there were none
+    listed: Cell<bool>,
+    /// Recursive size on disk, computed on first use
+    size: Cell<Option<u64>>,
+    /// The background walk measuring this directory for `size`, until it
+    /// reports back
+    sizing: RefCell<Option<sizer::Job>>,
+    /// Matched by a `.gitignore` somewhere between the root and here
+    ignored: Cell<bool>,
+    /// This directory's own `.gitignore`, read when it's scanned
+    gitignore: RefCell<Option<Gitignore>>,
+    /// The background scan reading this directory, until it has read all
+    /// of it
+    scan: RefCell<Option<scanner::Scan>>,
 }
 
 impl Node {
-    fn new(name: String, type_: DirType) -> NodeRef {
+    fn new(os_name: OsString, type_: DirType) -> NodeRef {
         Rc::new(RefCell::new(Node {
-            name,
+            name: os_name.to_string_lossy().into_owned(),
+            os_name,
             type_,
             is_open: false,
             parent: RefCell::new(Weak::new()),
             children: RefCell::new(Vec::new()),
+            accessed: OnceCell::new(),
+            link_target: OnceCell::new(),
+            modified: OnceCell::new(),
+            item_count: OnceCell::new(),
+            locked: OnceCell::new(),
+            read_error: Cell::new(None),
+            listed: Cell::new(false),
+            size: Cell::new(None),
+            sizing: RefCell::new(None),
+            ignored: Cell::new(false),
+            gitignore: RefCell::new(None),
+            scan: RefCell::new(None),
         }))
     }
 
@@ -133,307 +1073,6706 @@ impl Node {
         child.borrow().parent.replace(Rc::downgrade(&parent));
     }
 
+    /// Read a directory in one go, taking over from a background scan of it
+    /// if there is one
     fn scan_dir(node: NodeRef) -> io::Result<()> {
-        let path = node.borrow().full_path();
+        let path = node.borrow().path();
 
-        let entries = fs::read_dir(path)?;
+        let entries = fs::read_dir(&path);
+        node.borrow()
+            .read_error
+            .set(entries.as_ref().err().map(io::Error::kind));
+        let entries = entries?;
+        node.borrow().gitignore.replace(gitignore::load(&path));
+        node.borrow().scan.take();
 
         for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-            let name = path.file_name().unwrap().to_string_lossy().to_string();
-
-            let type_ = if path.is_dir() {
-                DirType::Dir
-            } else if path.is_symlink() {
-                DirType::Symlink
-            } else {
-                DirType::File
-            };
-
-            let child = Node::new(name, type_);
-
+            let path = entry?.path();
+            let child = Node::entry(&node, &path, DirType::of(&path));
             Node::add_child(node.clone(), child);
         }
+        node.borrow().sort_children();
+        node.borrow().listed.set(true);
 
         Ok(())
     }
 
-    fn full_path(&self) -> String {
-        match self.parent.borrow() {
-            parent if parent.upgrade().is_none() => self.name.clone(),
-            parent => {
-                let parent = parent.upgrade().unwrap();
-                let parent_path = parent.borrow().full_path();
-                format!("{}/{}", parent_path, self.name)
-            }
+    /// Add what a background scan has read so far. The entries are sorted in
+    /// among the ones already there, so a directory ends up in the same
+    /// order however many chunks it took.
+    fn graft(node: NodeRef, chunk: scanner::Chunk) {
+        if chunk.gitignore.is_some() {
+            node.borrow().gitignore.replace(chunk.gitignore);
         }
-    }
+        for (path, type_) in chunk.entries {
+            let child = Node::entry(&node, &path, type_);
+            Node::add_child(node.clone(), child);
+        }
+        node.borrow().sort_children();
 
-    fn to_array(&self, array: &mut Vec<String>) {
-        let full_path = self.full_path();
-        array.push(full_path);
-        for child in self.children.borrow().iter() {
-            child.borrow().to_array(array);
+        let node = node.borrow();
+        if chunk.done {
+            node.scan.take();
+            node.listed.set(true);
+        } else if let Some(scan) = &*node.scan.borrow() {
+            scan.received();
         }
     }
 
-    fn is_parent_selected(&self, selected_nodes: &[NodeRef]) -> bool {
-        let parent = self.parent.borrow();
-        match parent.upgrade() {
-            None => false,
-            Some(parent) => parent.borrow().is_selected(selected_nodes),
+    /// Read whatever a background scan hasn't yet
+    fn finish_scan(node: NodeRef) -> io::Result<()> {
+        if node.borrow().has_more() {
+            Node::rescan(node)?;
         }
+        Ok(())
     }
 
-    fn is_selected(&self, selected_nodes: &[NodeRef]) -> bool {
-        selected_nodes.iter().any(|node| {
-            node.borrow().full_path() == self.full_path() || self.is_parent_selected(selected_nodes)
-        })
+    /// Whether some entries are still being read in the background
+    fn has_more(&self) -> bool {
+        self.scan.borrow().is_some()
     }
 
-    fn to_enriched_array(
-        &self,
-        items: &mut Vec<TupleNode>,
-        selected_nodes: &Vec<NodeRef>,
-        depth: usize,
-        is_last: bool,
-    ) {
-        let tuple = (
-            self.name.clone(),
-            self.type_.clone(),
-            depth,
-            is_last,
-            self.is_selected(selected_nodes),
-        );
-        items.push(tuple);
-
-        let children = self.children.clone();
-        let len = children.borrow().len();
+    /// Bring a scanned directory's children in line with what's on disk,
+    /// keeping the nodes that are still there along with everything loaded
+    /// below them
+    fn rescan(node: NodeRef) -> io::Result<()> {
+        let path = node.borrow().path();
+        let mut found = Vec::new();
+        let entries = fs::read_dir(&path);
+        node.borrow()
+            .read_error
+            .set(entries.as_ref().err().map(io::Error::kind));
+        for entry in entries? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap().to_os_string();
+            let type_ = DirType::of(&path);
+            found.push((name, path, type_));
+        }
+        node.borrow().gitignore.replace(gitignore::load(&path));
 
-        for (i, child) in children.borrow().iter().enumerate() {
-            let is_last = i == len - 1;
-            if self.is_open {
-                child
-                    .borrow()
-                    .to_enriched_array(items, selected_nodes, depth + 1, is_last);
+        // an entry that changed type is a different entry
+        node.borrow().children.borrow_mut().retain(|child| {
+            let child = child.borrow();
+            found
+                .iter()
+                .any(|(name, _, type_)| *name == child.os_name && *type_ == child.type_)
+        });
+        for (name, path, type_) in found {
+            let known = node
+                .borrow()
+                .children
+                .borrow()
+                .iter()
+                .any(|child| child.borrow().os_name == name);
+            if !known {
+                let child = Node::entry(&node, &path, type_);
+                Node::add_child(node.clone(), child);
             }
         }
-    }
+        node.borrow().scan.take();
+        node.borrow().listed.set(true);
+        node.borrow().sort_children();
+        node.borrow().forget_size();
+        node.borrow().invalidate_size();
 
-    fn toggle_open(node: NodeRef) {
-        let mut mut_node = node.borrow_mut();
-        mut_node.is_open = !mut_node.is_open;
+        Ok(())
     }
-}
 
-#[derive(Debug)]
-pub struct App {
-    selected: Vec<NodeRef>,
-    hovered: ListState,
-    dir_tree: DirTree,
-    exit: bool,
-}
+    /// Rescan `node` and every directory loaded below it, so the tree
+    /// matches the disk again while keeping what's open and selected
+    fn refresh(node: NodeRef) -> io::Result<()> {
+        Node::rescan(node.clone())?;
 
-impl App {
-    pub fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
-        while !self.exit {
-            terminal.draw(|frame| self.draw(frame))?;
-            self.handle_events()?;
+        let children = node.borrow().children.borrow().clone();
+        for child in children {
+            // files may have grown or shrunk too, and links been pointed
+            // elsewhere
+            child.borrow().forget_size();
+            child.borrow_mut().link_target.take();
+            child.borrow_mut().modified.take();
+            child.borrow_mut().item_count.take();
+            child.borrow_mut().locked.take();
+            let loaded = !child.borrow().children.borrow().is_empty();
+            if loaded {
+                Node::refresh(child)?;
+            }
         }
         Ok(())
     }
 
-    fn handle_events(&mut self) -> io::Result<()> {
-        match event::read()? {
-            // it's important to check that the event is a key press event as
-            // crossterm also emits key release and repeat events on Windows.
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_key_event(key_event)
-            }
-            _ => {}
-        };
-        Ok(())
+    /// A node for `path`, found inside the directory `dir`
+    fn entry(dir: &NodeRef, path: &Path, type_: DirType) -> NodeRef {
+        let name = path.file_name().unwrap().to_os_string();
+        let is_dir = type_ == DirType::Dir;
+
+        let child = Node::new(name, type_);
+        child
+            .borrow()
+            .ignored
+            .set(Node::is_ignored(dir, path, is_dir));
+        child
     }
 
-    fn handle_key_event(&mut self, key: KeyEvent) {
-        match key.code {
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.handle_exit()
-            }
-            KeyCode::Char('q') => self.handle_exit(),
-            KeyCode::Char(' ') => self.handle_select_dir(),
-            KeyCode::Up => self.handle_hover_up(),
-            KeyCode::Down => self.handle_hover_down(),
-            KeyCode::Enter => self.handle_toggle_dir(),
-            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.handle_clear_all()
-            }
-            KeyCode::Char('r') => self.handle_clear_hovered(),
-            _ => {}
+    /// Directories first, then by name, so the order doesn't depend on what
+    /// the OS hands back
+    fn sort_children(&self) {
+        self.children.borrow_mut().sort_by_cached_key(|child| {
+            let child = child.borrow();
+            (
+                child.type_ != DirType::Dir,
+                child.name.to_lowercase(),
+                child.name.clone(),
+            )
+        });
+    }
+
+    /// Whether `path`, a new child of `dir`, is ignored. The closest
+    /// `.gitignore` with an opinion wins, so nested files and `!` patterns
+    /// can re-include what an outer one ignores, but nothing inside an
+    /// ignored directory can be, just like in git.
+    fn is_ignored(dir: &NodeRef, path: &Path, is_dir: bool) -> bool {
+        if dir.borrow().ignored.get() {
+            return true;
+        }
+        let mut current = Some(dir.clone());
+        while let Some(node) = current {
+            let node = node.borrow();
+            if let Some(gitignore) = &*node.gitignore.borrow() {
+                let matched = gitignore.matched(path, is_dir);
+                if !matched.is_none() {
+                    return matched.is_ignore();
+                }
+            }
+            current = node.parent.borrow().upgrade();
         }
+        false
     }
 
-    fn default() -> Self {
-        let current_dir = ".".to_string();
-        let dir_tree = DirTree::new(current_dir);
-        let hovered = ListState::default().with_selected(Some(0));
+    /// Whether this node, or anything loaded below it, is ignored
+    fn has_ignored(&self) -> bool {
+        self.ignored.get()
+            || self
+                .children
+                .borrow()
+                .iter()
+                .any(|child| child.borrow().has_ignored())
+    }
 
-        Self {
-            hovered,
-            selected: Vec::new(),
-            dir_tree,
-            exit: false,
+    fn path(&self) -> PathBuf {
+        match self.parent.borrow().upgrade() {
+            Some(parent) => parent.borrow().path().join(&self.os_name),
+            None => PathBuf::from(&self.os_name),
         }
     }
 
-    fn handle_exit(&mut self) {
-        self.exit = true;
+    /// `path()` as the string the tree identifies rows by. What touches the
+    /// disk goes by `path()` itself, which this may have mangled.
+    fn full_path(&self) -> String {
+        self.path().to_string_lossy().into_owned()
     }
 
-    fn handle_select_dir(&mut self) {
-        let arr = self.dir_tree.to_array();
-        let idx = self.hovered.selected().unwrap();
-        let node_path = arr[idx].clone();
-        let node = self.dir_tree.find_node(&node_path).unwrap();
-
-        if self
-            .selected
+    /// The path from the root down to this entry, for showing it without a
+    /// long absolute prefix. The root itself is shown as it was given.
+    fn display_path(&self) -> String {
+        let Some(mut parent) = self.parent.borrow().upgrade() else {
+            return self.full_path();
+        };
+        let mut path = PathBuf::from(&self.name);
+        loop {
+            let up = parent.borrow().parent.borrow().upgrade();
+            let Some(up) = up else {
+                break;
+            };
+            path = Path::new(&parent.borrow().name).join(path);
+            parent = up;
+        }
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Whether another entry next to this one has the same shown name, which
+    /// only happens when they differ in what isn't valid UTF-8. Their rows
+    /// can't be told apart by path, so neither is safe to act on.
+    fn is_ambiguous(&self) -> bool {
+        let Some(parent) = self.parent.borrow().upgrade() else {
+            return false;
+        };
+        let parent = parent.borrow();
+        let children = parent.children.borrow();
+        children.iter().any(|child| {
+            let child = child.borrow();
+            child.name == self.name && child.os_name != self.os_name
+        })
+    }
+
+    /// How many levels below the root this is
+    fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut parent = self.parent.borrow().upgrade();
+        while let Some(dir) = parent {
+            depth += 1;
+            parent = dir.borrow().parent.borrow().upgrade();
+        }
+        depth
+    }
+
+    fn loaded_paths(&self, paths: &mut Vec<String>, view: ViewOptions) {
+        paths.push(self.full_path());
+        for child in self.visible_children(view) {
+            child.borrow().loaded_paths(paths, view);
+        }
+    }
+
+    /// How many entries have been read below this one
+    fn loaded_count(&self) -> usize {
+        let children = self.children.borrow();
+        children.len()
+            + children
+                .iter()
+                .map(|child| child.borrow().loaded_count())
+                .sum::<usize>()
+    }
+
+    /// The paths of every open directory from this one down
+    fn open_dirs(&self, paths: &mut Vec<PathBuf>) {
+        if self.type_ == DirType::Dir && self.is_open {
+            paths.push(self.path());
+        }
+        for child in self.children.borrow().iter() {
+            child.borrow().open_dirs(paths);
+        }
+    }
+
+    fn to_array(&self, array: &mut Vec<String>, view: ViewOptions) {
+        let full_path = self.full_path();
+        array.push(full_path);
+        if !self.is_open {
+            return;
+        }
+        for child in self.visible_children(view) {
+            child.borrow().to_array(array, view);
+        }
+        // the placeholder row stands for the directory it belongs to
+        if self.has_more() {
+            array.push(self.full_path());
+        }
+    }
+
+    /// The children `view` lets through, in display order
+    fn visible_children(&self, view: ViewOptions) -> Vec<NodeRef> {
+        let mut children: Vec<NodeRef> = self
+            .children
+            .borrow()
             .iter()
-            .any(|x| x.borrow().full_path() == node_path)
-        {
-            self.selected
-                .retain(|x| x.borrow().full_path() != node_path);
-        } else {
-            self.selected.push(node);
+            .filter(|child| child.borrow().is_visible(view))
+            .cloned()
+            .collect();
+        view.sort.apply(&mut children);
+        children
+    }
+
+    fn is_visible(&self, view: ViewOptions) -> bool {
+        // until `measure_visible` has a directory's size it's shown
+        let big_enough = match (view.min_size, self.known_size()) {
+            (None, _) | (Some(_), None) => true,
+            (Some(min_size), Some(size)) => size >= min_size,
+        };
+        (view.show_hidden || !self.is_hidden())
+            && (view.ignored != IgnoredView::OnlyIgnored || self.has_ignored())
+            && big_enough
+            && view
+                .search
+                .is_none_or(|query| self.matches(query, view.show_hidden))
+            && view
+                .age
+                .is_none_or(|filter| self.matches_age(filter, view.show_hidden))
+            && (view.paths.is_empty()
+                || self.matches_paths(
+                    view.paths,
+                    Path::new(&self.display_path()),
+                    view.show_hidden,
+                ))
+    }
+
+    /// A dotfile. Only ever asked of children, so a root named `.` is shown
+    fn is_hidden(&self) -> bool {
+        self.name.starts_with('.')
+    }
+
+    /// Whether this node's name, or that of anything loaded below it that
+    /// would be shown, contains `query`
+    fn matches(&self, query: &str, show_hidden: bool) -> bool {
+        self.name.to_lowercase().contains(query)
+            || self.children.borrow().iter().any(|child| {
+                let child = child.borrow();
+                (show_hidden || !child.is_hidden()) && child.matches(query, show_hidden)
+            })
+    }
+
+    /// Whether this file, or any file loaded below this directory that would
+    /// be shown, passes `filter`. A directory with nothing read goes by its
+    /// own modification time, so it can still be opened.
+    fn matches_age(&self, filter: AgeFilter, show_hidden: bool) -> bool {
+        if self.type_ != DirType::Dir || self.children.borrow().is_empty() {
+            return filter.keeps(self.modified());
         }
+        self.children.borrow().iter().any(|child| {
+            let child = child.borrow();
+            (show_hidden || !child.is_hidden()) && child.matches_age(filter, show_hidden)
+        })
     }
 
-    fn handle_toggle_dir(&mut self) {
-        let arr = self.dir_tree.to_array();
-        let idx = self.hovered.selected().unwrap();
-        let node_path = arr[idx].clone();
-        let node = self.dir_tree.find_node(&node_path).unwrap();
+    /// Whether this entry, at `relative` below the root, or anything loaded
+    /// below it that would be shown, gets past `filter`. A directory that
+    /// hasn't been read all the way may still hold something that does.
+    fn matches_paths(&self, filter: &PathFilter, relative: &Path, show_hidden: bool) -> bool {
+        if filter.excludes(relative) {
+            return false;
+        }
+        if filter.includes(relative) {
+            return true;
+        }
+        if self.type_ != DirType::Dir {
+            return false;
+        }
+        if !self.listed.get() || self.has_more() {
+            return true;
+        }
+        self.children.borrow().iter().any(|child| {
+            let child = child.borrow();
+            (show_hidden || !child.is_hidden())
+                && child.matches_paths(filter, &relative.join(&child.name), show_hidden)
+        })
+    }
 
-        Node::toggle_open(node.clone());
+    /// Size on disk, including everything below a directory. Symlinks count
+    /// as the link itself.
+    fn size(&self) -> u64 {
+        if let Some(size) = self.size.get() {
+            return size;
+        }
+        let size = DeletionSummary::measure(&self.path()).bytes;
+        self.size.set(Some(size));
+        // measured already, so whatever a walk still comes up with is stale
+        self.sizing.take();
+        size
+    }
 
-        let borrowed = node.borrow();
-        if borrowed.children.borrow().is_empty() && borrowed.type_ == DirType::Dir {
-            Node::scan_dir(node.clone()).unwrap();
+    /// `size` where that's quick: a directory's only once it's been
+    /// measured, as walking one would hold up drawing
+    fn known_size(&self) -> Option<u64> {
+        match self.size.get() {
+            None if self.type_ == DirType::Dir => None,
+            Some(size) => Some(size),
+            None => Some(self.size()),
         }
     }
 
-    fn handle_hover_down(&mut self) {
-        let i = match self.hovered.selected() {
-            None => 0,
-            Some(i) => {
-                if i >= self.dir_tree.to_array().len() - 1 {
-                    0
+    /// Forget the cached size, along with any walk still measuring it
+    fn forget_size(&self) {
+        self.size.set(None);
+        self.sizing.take();
+    }
+
+    /// Drop the children that no longer exist on disk, all the way down,
+    /// forgetting the sizes of what's left
+    fn prune_missing(&self) {
+        self.forget_size();
+        self.children
+            .borrow_mut()
+            .retain(|child| fs::symlink_metadata(child.borrow().path()).is_ok());
+        for child in self.children.borrow().iter() {
+            child.borrow().prune_missing();
+        }
+    }
+
+    /// Take this node's size off its ancestors' cached sizes, for when it's
+    /// only gone from the tree and measuring again would still count it
+    fn discount_size(&self) {
+        let size = self.size();
+        let mut parent = self.parent.borrow().upgrade();
+        while let Some(node) = parent {
+            if let Some(cached) = node.borrow().size.get() {
+                node.borrow().size.set(Some(cached.saturating_sub(size)));
+            }
+            parent = node.borrow().parent.borrow().upgrade();
+        }
+    }
+
+    /// Forget the cached size of this node's ancestors, e.g. after it has
+    /// been removed from disk
+    fn invalidate_size(&self) {
+        let mut parent = self.parent.borrow().upgrade();
+        while let Some(node) = parent {
+            node.borrow().forget_size();
+            parent = node.borrow().parent.borrow().upgrade();
+        }
+    }
+
+    /// Push this node and its visible descendants. `is_selected` says whether
+    /// this node shows as selected, either directly or, when
+    /// `implies_children` is set, through a selected ancestor, so the
+    /// children can be judged without walking back up the tree. Returns the
+    /// node's `selection_counts`, gathered along the way so that every
+    /// loaded entry is only looked at once.
+    fn to_enriched_array(
+        &self,
+        items: &mut Vec<Row>,
+        selected: &SelectedPaths,
+        view: ViewOptions,
+        depth: usize,
+        is_last: bool,
+        is_selected: bool,
+    ) -> (usize, usize) {
+        let row = self.row(view, depth, is_last, is_selected);
+        let size = row.size;
+        let own = items.len();
+        items.push(row);
+
+        if self.type_ != DirType::Dir {
+            return (usize::from(is_selected), 1);
+        }
+        if !self.is_open {
+            // no count is shown when nothing's selected
+            if selected.is_empty() {
+                return (0, 0);
+            }
+            let counts = self.selection_counts(selected, view.implies_children, is_selected);
+            if counts.0 > 0 {
+                items[own].selection_count = Some(counts);
+            }
+            return counts;
+        }
+
+        let mut counts = (0, 0);
+        let children = self.visible_children(view);
+        let len = children.len();
+
+        for (i, child) in children.iter().enumerate() {
+            let is_last = i == len - 1 && !self.has_more();
+            let child_selected = (view.implies_children && is_selected)
+                || selected.contains(child.borrow().full_path().as_str());
+            let row = items.len();
+            let (picked, total) = child.borrow().to_enriched_array(
+                items,
+                selected,
+                view,
+                depth + 1,
+                is_last,
+                child_selected,
+            );
+            counts.0 += picked;
+            counts.1 += total;
+            if let (Some(child_size), Some(size)) = (items[row].size, size) {
+                // an empty directory's entries are all empty too
+                let share = if size == 0 {
+                    0.0
                 } else {
-                    i + 1
+                    child_size as f64 / size as f64
+                };
+                items[row].share = Some(share);
+            }
+        }
+        if !selected.is_empty() {
+            for child in self.children.borrow().iter() {
+                let child = child.borrow();
+                if child.is_visible(view) {
+                    continue;
                 }
+                let child_selected = (view.implies_children && is_selected)
+                    || selected.contains(child.full_path().as_str());
+                let (picked, total) =
+                    child.selection_counts(selected, view.implies_children, child_selected);
+                counts.0 += picked;
+                counts.1 += total;
             }
-        };
+        }
+        if counts.0 > 0 {
+            items[own].selection_count = Some(counts);
+        }
+        if self.has_more() {
+            items.push(Row::placeholder(self.type_, depth + 1));
+        }
+        counts
+    }
 
-        self.hovered.select(Some(i));
+    /// See `Row::selection_count`: how many entries that aren't directories are
+    /// selected from this one down, out of how many, for the entries
+    /// `to_enriched_array` doesn't push
+    fn selection_counts(
+        &self,
+        selected: &SelectedPaths,
+        implies: bool,
+        is_selected: bool,
+    ) -> (usize, usize) {
+        if self.type_ != DirType::Dir {
+            return (usize::from(is_selected), 1);
+        }
+        let mut counts = (0, 0);
+        for child in self.children.borrow().iter() {
+            let child = child.borrow();
+            let child_selected =
+                (implies && is_selected) || selected.contains(child.full_path().as_str());
+            let (picked, total) = child.selection_counts(selected, implies, child_selected);
+            counts.0 += picked;
+            counts.1 += total;
+        }
+        counts
     }
 
-    fn handle_hover_up(&mut self) {
-        let i = match self.hovered.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.dir_tree.to_array().len() - 1
-                } else {
-                    i - 1
+    /// The row for this entry alone
+    fn row(&self, view: ViewOptions, depth: usize, is_last: bool, is_selected: bool) -> Row {
+        Row {
+            name: self.name.clone(),
+            type_: self.type_,
+            depth,
+            is_last,
+            is_selected,
+            accessed: if view.show_accessed {
+                self.accessed()
+            } else {
+                None
+            },
+            // directories are measured in the background, see
+            // `App::measure_visible`
+            size: if self.type_ == DirType::Dir {
+                self.size.get()
+            } else {
+                Some(self.size())
+            },
+            is_ignored: self.ignored.get(),
+            is_placeholder: false,
+            link_target: self.link_target(),
+            icon: icons::icon_for(self),
+            item_count: if self.type_ == DirType::Dir && !self.is_open {
+                Some(self.item_count())
+            } else {
+                None
+            },
+            is_locked: self.is_locked(),
+            share: None,
+            is_protected: view.protected.matching(&self.path()).is_some(),
+            is_own: view.own_files.owning(&self.path()).is_some(),
+            read_error: self.read_error.get(),
+            git_mark: view.git.get(&self.path()),
+            duplicate_group: if self.type_ == DirType::File && !view.duplicates.is_empty() {
+                view.duplicates.get(&self.path())
+            } else {
+                None
+            },
+            selection_count: None,
+        }
+    }
+
+    /// The files among what's been read from this directory down, see
+    /// `DirTree::flat_files`
+    fn collect_files(&self, files: &mut Vec<NodeRef>, view: ViewOptions) {
+        for child in self.visible_children(view) {
+            if child.borrow().type_ == DirType::Dir {
+                child.borrow().collect_files(files, view);
+            } else {
+                files.push(child);
+            }
+        }
+    }
+
+    /// See `DirTree::unlisted_dirs`. Symlinked directories are left for
+    /// the user to open, so a link cycle can't be followed forever.
+    fn collect_unlisted(node: NodeRef, dirs: &mut Vec<NodeRef>, view: ViewOptions) {
+        let unlisted = {
+            let node = node.borrow();
+            if node.type_ != DirType::Dir || node.read_error.get().is_some() {
+                return;
+            }
+            node.has_more() || (!node.listed.get() && !node.is_symlink())
+        };
+        if unlisted {
+            dirs.push(node.clone());
+        }
+        for child in node.borrow().visible_children(view) {
+            Node::collect_unlisted(child, dirs, view);
+        }
+    }
+
+    /// See `DirTree::collect_empty_dirs`. Says whether `node` itself is
+    /// empty that way, so its parent can tell whether it is too.
+    fn collect_empty(node: &NodeRef, dirs: &mut Vec<NodeRef>) -> bool {
+        let empty = {
+            let node = node.borrow();
+            if node.type_ != DirType::Dir
+                || node.has_more()
+                || node.read_error.get().is_some()
+                || node.is_symlink()
+            {
+                return false;
+            }
+            if node.listed.get() {
+                // every child is looked at, for the empty ones among them
+                let mut empty = true;
+                for child in node.children.borrow().iter() {
+                    empty &= Node::collect_empty(child, dirs);
                 }
+                empty
+            } else {
+                node.item_count() == Some(0)
             }
-            None => 0,
         };
-        self.hovered.select(Some(i));
+        if empty {
+            dirs.push(node.clone());
+        }
+        empty
     }
 
-    fn handle_clear_hovered(&mut self) {
-        let arr = self.dir_tree.to_array();
-        let idx = self.hovered.selected().unwrap();
-        let node_path = arr[idx].clone();
+    /// Last access time, read once and cached. Note that mounts using
+    /// `relatime` or `noatime` only update this occasionally, if ever.
+    fn accessed(&self) -> Accessed {
+        *self.accessed.get_or_init(|| {
+            fs::symlink_metadata(self.path())
+                .and_then(|meta| meta.accessed())
+                .ok()
+        })
+    }
 
-        self.dir_tree.remove_node(&node_path);
+    /// Last modification time of the entry itself, read once and cached
+    fn modified(&self) -> Option<SystemTime> {
+        *self.modified.get_or_init(|| {
+            fs::symlink_metadata(self.path())
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
     }
 
-    fn handle_clear_all(&mut self) {
-        self.selected.iter().for_each(|node| {
-            self.dir_tree.remove_node(&node.borrow().full_path());
-        });
+    /// How many entries the directory holds, without going any deeper.
+    /// Once it has been read that's what the tree has of it.
+    fn item_count(&self) -> Option<usize> {
+        let children = self.children.borrow();
+        if !children.is_empty() && !self.has_more() {
+            return Some(children.len());
+        }
+        *self
+            .item_count
+            .get_or_init(|| fs::read_dir(self.path()).ok().map(Iterator::count))
     }
-}
 
-impl App {
-    fn draw(&mut self, f: &mut Frame) {
-        let title = Title::from(" Interactive file remover ".bold());
+    /// Whether the entry or the directory it's in is read-only, so removing
+    /// it will probably fail. Only a guess from the permission bits: it
+    /// doesn't stop anyone from trying.
+    fn is_locked(&self) -> bool {
+        *self.locked.get_or_init(|| {
+            let path = self.path();
+            let read_only = |path: &Path| {
+                fs::symlink_metadata(path).is_ok_and(|meta| meta.permissions().readonly())
+            };
+            read_only(&path) || path.parent().is_some_and(read_only)
+        })
+    }
 
-        let instructions = Title::from(Line::from(vec![
-            " Move: ".into(),
-            "<Up/Down>".blue().bold(),
-            " Open dir: ".into(),
-            "<Enter>".blue().bold(),
-            " Select: ".into(),
-            "<Space>".blue().bold(),
-            " Remove all: ".into(),
-            "<Ctrl + R> ".red().bold(),
-            "Remove: ".into(),
-            "<R> ".red().bold(),
-            " Quit: ".into(),
-            "<Q> ".blue().bold(),
-        ]));
-        let block = Block::bordered()
-            .title(title.alignment(Alignment::Center))
-            .title(
-                instructions
-                    .alignment(Alignment::Center)
-                    .position(Position::Bottom),
-            )
-            .border_set(border::THICK);
+    /// Where the entry points if it's a symlink, without following it.
+    /// Symlinked directories count too.
+    fn link_target(&self) -> LinkTarget {
+        self.link_target
+            .get_or_init(|| {
+                if self.type_ == DirType::File {
+                    return None;
+                }
+                let path = self.path();
+                let target = fs::read_link(&path).ok()?;
+                let missing = self.type_ == DirType::Symlink && fs::metadata(&path).is_err();
+                Some((target.to_string_lossy().to_string(), missing))
+            })
+            .clone()
+    }
 
-        let enriched = self.dir_tree.to_enriched_array(&self.selected);
-        let items = enriched
-            .iter()
-            .map(|(name, type_, depth, is_last, is_selected)| {
-                let type_prefix = match type_ {
-                    DirType::Dir => "📁",
-                    DirType::File => "📄",
-                    DirType::Symlink => "🔗",
-                };
+    fn collect_at_depth(node: NodeRef, target: Depth, depth: Depth, nodes: &mut Vec<NodeRef>) {
+        if depth == target {
+            nodes.push(node);
+            return;
+        }
+        for child in node.borrow().children.borrow().iter() {
+            Node::collect_at_depth(child.clone(), target, depth + 1, nodes);
+        }
+    }
+
+    /// Expand `node`, reading its entries the first time
+    fn open(node: NodeRef) -> io::Result<()> {
+        if node.borrow().children.borrow().is_empty() {
+            Node::scan_dir(node.clone())?;
+        }
+        Node::finish_scan(node.clone())?;
+        node.borrow_mut().is_open = true;
+        Ok(())
+    }
+
+    /// Read every directory below `node` that hasn't been scanned yet,
+    /// down to `limit` levels and without following symlinks
+    fn scan_all(node: NodeRef, depth: usize, limit: usize) {
+        if node.borrow().type_ != DirType::Dir || depth > limit {
+            return;
+        }
+        if node.borrow().is_symlink() {
+            return;
+        }
+        let needs_scan = node.borrow().children.borrow().is_empty();
+        if needs_scan && Node::scan_dir(node.clone()).is_err() {
+            return;
+        }
+        if Node::finish_scan(node.clone()).is_err() {
+            return;
+        }
 
-                let list_prefix = if *is_last { "└─" } else { "├─" };
-                let depth_prefix = "│ ".repeat(*depth);
+        let children = node.borrow().children.borrow().clone();
+        for child in children {
+            Node::scan_all(child, depth + 1, limit);
+        }
+    }
 
-                let formatted = format!("{depth_prefix}{list_prefix} {type_prefix} {name}");
+    /// Every scanned node below this one, parents before their children
+    fn descendants(&self, nodes: &mut Vec<NodeRef>) {
+        for child in self.children.borrow().iter() {
+            nodes.push(child.clone());
+            child.borrow().descendants(nodes);
+        }
+    }
 
-                let li = ListItem::new(formatted);
+    fn toggle_open(node: NodeRef) {
+        let mut mut_node = node.borrow_mut();
+        mut_node.is_open = !mut_node.is_open;
+    }
 
-                if *is_selected {
-                    li.style(Style::default().fg(Color::Red))
-                } else {
-                    li
-                }
-            });
+    fn is_symlink(&self) -> bool {
+        fs::symlink_metadata(self.path())
+            .map(|meta| meta.file_type().is_symlink())
+            .unwrap_or(false)
+    }
 
-        let list = List::new(items)
-            .block(Block::bordered().title("Directories"))
-            .style(Style::default().fg(Color::White))
-            .highlight_symbol("▶️")
-            .repeat_highlight_symbol(true)
-            .block(block)
-            .direction(ListDirection::TopToBottom);
+    /// Opens or closes `node` and every directory below it, down to `limit`
+    /// levels, scanning lazily when opening. Symlinked directories are only
+    /// descended into given the real paths `visited` so far, and never twice
+    /// into the same one, so a link cycle can't expand forever.
+    fn set_open_recursive(
+        node: NodeRef,
+        open: bool,
+        depth: usize,
+        limit: usize,
+        mut visited: Option<&mut HashSet<PathBuf>>,
+    ) {
+        if node.borrow().type_ != DirType::Dir || depth > limit {
+            return;
+        }
+        if open {
+            let descend = match visited.as_deref_mut() {
+                Some(visited) => first_visit(&node.borrow().path(), visited),
+                None => !node.borrow().is_symlink(),
+            };
+            if !descend {
+                return;
+            }
+        }
+        let needs_scan = open && node.borrow().children.borrow().is_empty();
+        if needs_scan && Node::scan_dir(node.clone()).is_err() {
+            return;
+        }
 
-        f.render_stateful_widget(list, f.size(), &mut self.hovered);
+        node.borrow_mut().is_open = open;
+
+        let children = node.borrow().children.borrow().clone();
+        for child in children {
+            Node::set_open_recursive(child, open, depth + 1, limit, visited.as_deref_mut());
+        }
     }
 }
 
-fn main() -> io::Result<()> {
-    let mut terminal = tui::init()?;
-    let mut app = App::default();
-    let app_result = app.run(&mut terminal);
-    tui::restore()?;
-    app_result
+#[derive(Debug)]
+pub struct App {
+    /// Full paths of the selected entries, looked up in the tree when
+    /// needed so that a selection outlives the nodes it was made on
+    selected: Vec<String>,
+    hovered: ListState,
+    dir_tree: DirTree,
+    /// The path the user launched with, when it was a symlink we resolved
+    root_link: Option<String>,
+    /// Resolve a symlinked root, including one browsed to later
+    follow_root_symlink: bool,
+    /// Set after <D>, the next digit picks the depth to select below
+    awaiting_depth: bool,
+    /// Set after <K>, the next of <f>, <d> or <l> picks the type of entry
+    /// to select
+    awaiting_type: bool,
+    /// Path of the row where a range selection started with <V>. While it's
+    /// set, moving selects every row between it and the hovered one,
+    /// wherever sorting or sizes have moved it to since.
+    range_anchor: Option<String>,
+    /// What was selected before the range started, kept alongside it
+    range_base: Vec<String>,
+    /// Deselecting more than this many items asks for confirmation first
+    clear_confirm_threshold: usize,
+    /// A question shown over the list, answered with <Y> or any other key
+    confirm: Option<Confirm>,
+    /// Remove without asking first, from `--force`
+    force: bool,
+    /// Refuse to remove, move, restore or edit anything, from `--readonly`
+    readonly: bool,
+    /// Above how many entries, or bytes, a removal has to be confirmed by
+    /// typing rather than with a single key
+    type_above_entries: usize,
+    type_above_size: u64,
+    /// Room left on the root's filesystem, as of the last removal
+    disk_space: Option<disk::Space>,
+    /// Directory to open a shell in once the terminal has been handed back
+    shell_request: Option<String>,
+    /// File to edit, as its row's path, once the terminal has been handed
+    /// back, with the editor to run
+    edit_request: Option<(String, Vec<String>)>,
+    /// Stop for the shell's job control with <Ctrl + Z> once this frame is
+    /// done
+    stop_requested: bool,
+    /// Message shown under the list until the next key press or until
+    /// `STATUS_DURATION` has passed since it was set
+    status: Option<(String, Instant)>,
+    summary: DeletionSummary,
+    /// Every path removed this session (or that would have been, on a dry
+    /// run), printed to stdout on exit for other tools to read
+    removed: Vec<String>,
+    /// Where removals and restores are recorded, if anywhere
+    audit: AuditLog,
+    git_marks: git::Marks,
+    /// Files with the same contents, as of the last `=`
+    duplicates: Duplicates,
+    path_filter: PathFilter,
+    /// irm's files wherever they are, for the trees of new tabs to look
+    /// for below their roots
+    own_files: OwnFiles,
+    /// Selecting a directory selects everything below it too. When off only
+    /// the directory entry itself is selected, and removing it requires it
+    /// to be empty.
+    select_implies_children: bool,
+    /// Extensions of files the bulk selections skip, `[select] never` in
+    /// the config
+    never_select: Vec<String>,
+    show_accessed: bool,
+    min_size: Option<u64>,
+    age: Option<AgeFilter>,
+    show_hidden: bool,
+    ignored_view: IgnoredView,
+    sort: Sort,
+    /// The sort and age filter to go back to when `t` leaves the recently
+    /// modified view, while it's shown
+    recent_view: Option<(Sort, Option<AgeFilter>)>,
+    /// Lowercased name filter, see `ViewOptions::search`
+    search: Option<String>,
+    /// Text input in progress, shown in place of the status line
+    prompt: Option<Prompt>,
+    /// The most recent repeatable action, re-run with `repeat_key`
+    last_action: Option<Action>,
+    repeat_key: char,
+    /// Keys for the actions that can be rebound in the config file
+    keymap: Keymap,
+    theme: Theme,
+    /// Draw without any colors, from `--no-color` or `NO_COLOR`
+    no_color: bool,
+    /// Show bars for how much of its directory each entry takes up
+    du: bool,
+    /// Show how much is loaded and selected in the title
+    stats: bool,
+    /// List every file below the root in place of the tree, see
+    /// `ViewOptions::flat`
+    flat: bool,
+    render_style: RenderStyle,
+    /// Ask before opening a symlinked directory that leads outside the root
+    confirm_outside_links: bool,
+    /// Let expanding everything below a directory go into symlinked ones,
+    /// from `--follow-symlinks`
+    follow_symlinks: bool,
+    /// Save the next frame as an SVG file
+    export_requested: bool,
+    /// Show a gauge while deleting a large directory, which only `run`
+    /// turns on, since scripts have no screen
+    shows_progress: bool,
+    /// The screen was drawn on behind the tree's back, by the gauge
+    redraw: bool,
+    /// Directory marked as the first side of a size comparison
+    compare_mark: Option<NodeRef>,
+    /// Comparison popup, closed by the next key press
+    comparison: Option<Comparison>,
+    /// What was moved to the trash this session, most recent last, for
+    /// putting back with <U>
+    trashed: Vec<TrashedItem>,
+    /// How many entries `trashed` keeps, and how many bytes they may add up
+    /// to, from `[restore]` in the config
+    restore_max_items: usize,
+    restore_max_size: u64,
+    /// Entries dropped from `trashed` since the status last said so
+    unrestorable: Vec<String>,
+    clipboard: clipboard::Clipboard,
+    /// Paths that couldn't be put on the clipboard, printed on exit instead
+    uncopied: Vec<String>,
+    /// Keybinding overlay, closed by the next key press
+    show_help: bool,
+    bookmarks: Bookmarks,
+    /// The bookmark picked in the list opened with <'>, while it's open
+    bookmark_list: Option<usize>,
+    /// What this session moved to the trash, listed with <Ctrl + U> for
+    /// deleting from it for good
+    trash_list: Option<TrashList>,
+    /// The fuzzy finder opened with <Ctrl + P>
+    finder: Option<Finder>,
+    /// Details pane beside the list, toggled with <p>
+    show_details: bool,
+    /// Show where the hovered entry really is, symlinks resolved, in the
+    /// details pane or else the status line. Toggled with <P>.
+    show_real_path: bool,
+    /// What the details pane shows, for the path and size it was made for
+    details: Option<(String, Option<u64>, Vec<Line<'static>>)>,
+    /// Rows that fit in the list as last drawn, for paging
+    list_height: usize,
+    /// Where the list's rows were last drawn, for mapping clicks to rows
+    list_area: Rect,
+    /// When and on which row the last left click landed, to tell a
+    /// double-click apart
+    last_click: Option<(Instant, usize)>,
+    /// Where background scans send what they read, see `scanner`
+    scan_sender: Sender<scanner::Chunk>,
+    scan_receiver: Receiver<scanner::Chunk>,
+    /// Chunks asked for that haven't arrived yet
+    scanning: usize,
+    /// Where background measurements send their results, see `sizer`
+    size_sender: Sender<sizer::Measured>,
+    size_receiver: Receiver<sizer::Measured>,
+    /// Measurements started that haven't reported back yet
+    measuring: usize,
+    /// Directories an expand-all with <E> has yet to open, with their depth
+    /// below where it started
+    expanding: VecDeque<(NodeRef, usize)>,
+    /// How many directories the expand-all in progress has opened
+    expanded: usize,
+    /// Real paths of the directories the expand-all has opened, so that
+    /// with `follow_symlinks` none is opened twice
+    expand_visited: HashSet<PathBuf>,
+    /// Entries visited by consecutive jumps to the largest item, outermost
+    /// first
+    largest_trail: Vec<NodeRef>,
+    /// The tabs other than the one shown, in order, see `Pane`
+    tabs: Vec<Pane>,
+    /// Where the shown tab sits among `tabs`
+    active: usize,
+    exit: bool,
+}
+
+/// What a tab has of its own: the tree it browses and where the user is in
+/// it. The shown tab's lives in `App` itself and is swapped with one of
+/// `App::tabs` when switching, so everything else only ever deals with the
+/// one tree.
+#[derive(Debug)]
+struct Pane {
+    dir_tree: DirTree,
+    root_link: Option<String>,
+    selected: Vec<String>,
+    hovered: ListState,
+    range_anchor: Option<String>,
+    range_base: Vec<String>,
+    compare_mark: Option<NodeRef>,
+    largest_trail: Vec<NodeRef>,
+    details: Option<(String, Option<u64>, Vec<Line<'static>>)>,
+    last_click: Option<(Instant, usize)>,
+    scan_sender: Sender<scanner::Chunk>,
+    scan_receiver: Receiver<scanner::Chunk>,
+    scanning: usize,
+    size_sender: Sender<sizer::Measured>,
+    size_receiver: Receiver<sizer::Measured>,
+    measuring: usize,
+    expanding: VecDeque<(NodeRef, usize)>,
+    expanded: usize,
+    expand_visited: HashSet<PathBuf>,
+}
+
+impl Pane {
+    fn new(dir_tree: DirTree, root_link: Option<String>) -> Self {
+        let (scan_sender, scan_receiver) = mpsc::channel();
+        let (size_sender, size_receiver) = mpsc::channel();
+        Self {
+            dir_tree,
+            root_link,
+            selected: Vec::new(),
+            hovered: ListState::default().with_selected(Some(0)),
+            range_anchor: None,
+            range_base: Vec::new(),
+            compare_mark: None,
+            largest_trail: Vec::new(),
+            details: None,
+            last_click: None,
+            scan_sender,
+            scan_receiver,
+            scanning: 0,
+            size_sender,
+            size_receiver,
+            measuring: 0,
+            expanding: VecDeque::new(),
+            expanded: 0,
+            expand_visited: HashSet::new(),
+        }
+    }
+}
+
+impl App {
+    pub fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
+        self.shows_progress = true;
+        while !self.exit {
+            self.receive_scans();
+            self.receive_sizes();
+            self.load_near_hover();
+            self.load_flat();
+            self.expand_step();
+            self.measure_visible();
+            if self
+                .status
+                .as_ref()
+                .is_some_and(|(_, since)| since.elapsed() >= STATUS_DURATION)
+            {
+                self.status = None;
+            }
+            if self.redraw {
+                self.redraw = false;
+                terminal.clear()?;
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            // keep the spinner turning, the scans coming in and the message
+            // timing out while waiting for a key, and go on expanding right
+            // away unless one came
+            let wait = if self.expanding.is_empty() {
+                SPINNER_INTERVAL
+            } else {
+                Duration::ZERO
+            };
+            let busy = self.scanning > 0
+                || self.measuring > 0
+                || !self.expanding.is_empty()
+                || self.status.is_some();
+            if !busy || event::poll(wait)? {
+                self.handle_events()?;
+            }
+
+            if let Some(dir) = self.shell_request.take() {
+                let result =
+                    tui::suspend(terminal, || spawn_shell(&self.dir_tree.disk_path(&dir)))?;
+                if let Err(err) = result {
+                    self.set_status(format!("Could not start a shell in {dir}: {err}"));
+                }
+            }
+            if let Some((path, editor)) = self.edit_request.take() {
+                let result = tui::suspend(terminal, || {
+                    launch::edit(&editor, &self.dir_tree.disk_path(&path))
+                })?;
+                self.handle_edited(&path, result);
+            }
+            if self.stop_requested {
+                self.stop_requested = false;
+                tui::stop(terminal)?;
+            }
+            if self.export_requested {
+                self.export_requested = false;
+                let frame = terminal.draw(|frame| self.draw(frame))?;
+                let svg = svg::render(frame.buffer);
+                self.set_status(export_view(&svg));
+            }
+        }
+        Ok(())
+    }
+
+    fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), Instant::now()));
+    }
+
+    /// Whether `--readonly` rules out changing the disk, saying so if it does
+    fn refuses_changes(&mut self) -> bool {
+        if self.readonly {
+            self.set_status("Read-only mode, nothing can be changed");
+        }
+        self.readonly
+    }
+
+    fn handle_events(&mut self) -> io::Result<()> {
+        match event::read()? {
+            // it's important to check that the event is a key press event as
+            // crossterm also emits key release and repeat events on Windows.
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                self.handle_key_event(key_event)
+            }
+            Event::Mouse(mouse_event) => self.handle_mouse_event(mouse_event),
+            _ => {}
+        };
+        Ok(())
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        let overlay = self.prompt.is_some()
+            || self.awaiting_depth
+            || self.awaiting_type
+            || self.comparison.is_some()
+            || self.show_help
+            || self.bookmark_list.is_some()
+            || self.trash_list.is_some()
+            || self.finder.is_some()
+            || self.confirm.is_some();
+        if overlay {
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.handle_hover_up(),
+            MouseEventKind::ScrollDown => self.handle_hover_down(),
+            MouseEventKind::Down(button) => {
+                let Some(row) = self.row_at(mouse.column, mouse.row) else {
+                    return;
+                };
+                self.status = None;
+                self.hovered.select(Some(row));
+                match button {
+                    MouseButton::Left => {
+                        let double = self.last_click.take().is_some_and(|(at, last)| {
+                            last == row && at.elapsed() <= DOUBLE_CLICK_INTERVAL
+                        });
+                        if double {
+                            self.range_anchor = None;
+                            self.handle_toggle_dir();
+                            return;
+                        }
+                        self.last_click = Some((Instant::now(), row));
+                    }
+                    MouseButton::Right => {
+                        self.range_anchor = None;
+                        self.perform(Action::ToggleSelect);
+                        return;
+                    }
+                    MouseButton::Middle => return,
+                }
+            }
+            _ => return,
+        }
+        self.extend_range();
+    }
+
+    /// The list row drawn at the given screen cell, if any
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        let inside = column >= area.left()
+            && column < area.right()
+            && row >= area.top()
+            && row < area.bottom();
+        if !inside {
+            return None;
+        }
+        let index = self.hovered.offset() + usize::from(row - area.top());
+        (index < self.dir_tree.to_array(self.view()).len()).then_some(index)
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) {
+        self.status = None;
+
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key);
+            return;
+        }
+        if self.finder.is_some() {
+            self.handle_finder_key(key);
+            return;
+        }
+        if self.awaiting_depth {
+            self.awaiting_depth = false;
+            if let KeyCode::Char(c @ '0'..='9') = key.code {
+                self.perform(Action::SelectBelowDepth(c as usize - '0' as usize));
+            }
+            return;
+        }
+        if self.awaiting_type {
+            self.awaiting_type = false;
+            let type_ = match key.code {
+                KeyCode::Char('f') => DirType::File,
+                KeyCode::Char('d') => DirType::Dir,
+                KeyCode::Char('l') => DirType::Symlink,
+                _ => return,
+            };
+            self.perform(Action::SelectType(type_));
+            return;
+        }
+        if self.comparison.take().is_some() {
+            return;
+        }
+        if self.show_help {
+            self.show_help = false;
+            return;
+        }
+        if self.bookmark_list.is_some() {
+            self.handle_bookmark_list_key(key);
+            return;
+        }
+        if self.trash_list.is_some() {
+            self.handle_trash_list_key(key);
+            return;
+        }
+        if !self.expanding.is_empty() && key.code == KeyCode::Esc {
+            self.expanding.clear();
+            self.set_status(format!(
+                "Stopped expanding after {} directories",
+                format::count(self.expanded as u64)
+            ));
+            return;
+        }
+        if let Some(confirm) = self.confirm.take() {
+            match confirm {
+                Confirm::Typed(removal, typed) => {
+                    self.handle_typed_confirm_key(key, removal, typed)
+                }
+                Confirm::Move(paths, dest, _) if key.code == KeyCode::Char('s') => {
+                    self.move_selection(paths, &dest, false)
+                }
+                Confirm::Move(..) if key.code == KeyCode::Char('o') => {
+                    self.handle_confirmed(confirm)
+                }
+                Confirm::Move(..) => {}
+                _ if matches!(key.code, KeyCode::Char('y') | KeyCode::Enter) => {
+                    self.handle_confirmed(confirm)
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        // Ctrl + C quits whatever the keymap says
+        let mapped =
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                Some(KeyAction::Quit)
+            } else {
+                self.keymap.action(&key)
+            };
+        let moves = matches!(mapped, Some(KeyAction::Up | KeyAction::Down))
+            || matches!(
+                key.code,
+                KeyCode::Home
+                    | KeyCode::End
+                    | KeyCode::PageUp
+                    | KeyCode::PageDown
+                    | KeyCode::Char('g' | 'G')
+            );
+        if self.range_anchor.is_some() && !moves {
+            self.range_anchor = None;
+            if key.code == KeyCode::Esc || key.code == KeyCode::Char('V') {
+                return;
+            }
+        }
+
+        match mapped {
+            Some(KeyAction::Quit) => self.handle_exit(),
+            Some(KeyAction::Select) => self.perform(Action::ToggleSelect),
+            Some(KeyAction::Open) => self.handle_toggle_dir(),
+            Some(KeyAction::Remove) => self.perform(Action::RemoveHovered),
+            Some(KeyAction::RemoveAll) => self.perform(Action::RemoveSelected),
+            Some(KeyAction::Up) => self.handle_hover_up(),
+            Some(KeyAction::Down) => self.handle_hover_down(),
+            None => self.handle_unmapped_key(key),
+        }
+        if moves {
+            self.extend_range();
+        }
+    }
+
+    /// Keys that can't be rebound in the config file
+    fn handle_unmapped_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_toggle_delete_mode()
+            }
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_remove_empty_dirs()
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_edit()
+            }
+            KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_refresh()
+            }
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.stop_requested = true
+            }
+            KeyCode::F(5) => self.handle_refresh(),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.finder = Some(Finder::new(self.dir_tree.loaded_paths(self.view())))
+            }
+            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_cycle_ignored_view()
+            }
+            KeyCode::Char(c) if c == self.repeat_key => self.handle_repeat(),
+            KeyCode::Home | KeyCode::Char('g') => self.hovered.select(Some(0)),
+            KeyCode::End | KeyCode::Char('G') => self.handle_hover_last(),
+            KeyCode::PageDown => self.handle_page_down(),
+            KeyCode::PageUp => self.handle_page_up(),
+            KeyCode::Char('*') => self.handle_toggle_subtree(),
+            KeyCode::Char('E') => self.handle_expand_all(),
+            KeyCode::Char('C') => self.handle_collapse_all(),
+            KeyCode::Left | KeyCode::Char('h') => self.handle_go_to_parent(),
+            KeyCode::Right | KeyCode::Char('l') => self.handle_expand(),
+            KeyCode::Char('D') => self.awaiting_depth = true,
+            KeyCode::Char('K') => self.awaiting_type = true,
+            KeyCode::Char('A') => self.perform(Action::SelectDescendants),
+            KeyCode::Char('V') => self.handle_start_range(),
+            KeyCode::Char('a') => self.handle_select_siblings(),
+            KeyCode::Char('i') => self.handle_invert_selection(),
+            KeyCode::Esc => self.handle_deselect_all(),
+            KeyCode::Char('!') => self.handle_open_shell(),
+            KeyCode::Char('o') => self.handle_open(),
+            KeyCode::Char('O') => self.handle_reveal(),
+            KeyCode::Char('e') => self.handle_edit(),
+            KeyCode::Char('m') => self.handle_toggle_bookmark(),
+            KeyCode::Char('\'') => self.handle_show_bookmarks(),
+            KeyCode::Char('I') => {
+                self.select_implies_children = !self.select_implies_children;
+            }
+            KeyCode::Char('T') => self.handle_toggle_accessed(),
+            KeyCode::Char('s') => self.handle_cycle_sort(),
+            KeyCode::Char('S') => self.handle_reverse_sort(),
+            KeyCode::Char('t') => self.handle_toggle_recent(),
+            KeyCode::Char('=') => self.handle_find_duplicates(),
+            KeyCode::Char('-') => self.handle_select_duplicates(),
+            KeyCode::Char('.') => self.handle_toggle_hidden(),
+            KeyCode::Char('z') => self.handle_toggle_flat(),
+            KeyCode::Char('L') => self.handle_show_link_chain(),
+            KeyCode::Char('U') => self.handle_show_usage(),
+            KeyCode::Char('B') => self.handle_jump_to_largest(),
+            KeyCode::Char('c') => self.handle_mark_compare(),
+            KeyCode::Char('X') => self.export_requested = true,
+            KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Char('0') => self.perform(Action::RemoveEmptyFiles),
+            KeyCode::Char('x') => self.perform(Action::EmptyHovered),
+            KeyCode::Char('>') => self.prompt = Some(Prompt::new(PromptKind::MinSize)),
+            KeyCode::Char('<') => self.prompt = Some(Prompt::new(PromptKind::Age)),
+            KeyCode::Char('+') => self.prompt = Some(Prompt::new(PromptKind::Glob)),
+            KeyCode::Char(':') => self.prompt = Some(Prompt::new(PromptKind::Row)),
+            KeyCode::Char('M') => self.handle_start_move(),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let mut prompt = Prompt::new(PromptKind::Root);
+                prompt.input = self.dir_tree.base_node.borrow().full_path();
+                self.prompt = Some(prompt);
+            }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let mut prompt = Prompt::new(PromptKind::Tab);
+                prompt.input = self.dir_tree.base_node.borrow().full_path();
+                self.prompt = Some(prompt);
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_close_tab()
+            }
+            KeyCode::Tab => self.handle_switch_tab(1),
+            KeyCode::BackTab => self.handle_switch_tab(-1),
+            KeyCode::Char('/') => {
+                let mut prompt = Prompt::new(PromptKind::Search);
+                prompt.input = self.search.clone().unwrap_or_default();
+                self.prompt = Some(prompt);
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.handle_show_trash()
+            }
+            KeyCode::Char('u') => self.handle_undo_trash(),
+            KeyCode::Char('n') => self.handle_jump_to_selected(true),
+            KeyCode::Char('N') => self.handle_jump_to_selected(false),
+            KeyCode::Char('y') => self.handle_copy_path(),
+            KeyCode::Char('p') => self.show_details = !self.show_details,
+            KeyCode::Char('P') => {
+                self.show_real_path = !self.show_real_path;
+                // made again with or without it
+                self.details = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Run a repeatable action and remember it for `handle_repeat`
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::ToggleSelect => self.handle_select_dir(),
+            Action::SelectBelowDepth(depth) => self.handle_select_below_depth(depth),
+            Action::SelectType(type_) => self.handle_select_type(type_),
+            Action::SelectDescendants => self.handle_select_descendants(),
+            Action::RemoveHovered => self.handle_clear_hovered(),
+            Action::RemoveSelected => self.handle_clear_all(),
+            Action::RemoveEmptyFiles => self.handle_remove_empty_files(),
+            Action::EmptyHovered => self.handle_empty_hovered(),
+        }
+        self.last_action = Some(action);
+    }
+
+    fn handle_repeat(&mut self) {
+        match self.last_action {
+            Some(action) => self.perform(action),
+            None => self.set_status("Nothing to repeat yet".to_string()),
+        }
+    }
+
+    fn new(
+        root: PathBuf,
+        root_link: Option<String>,
+        safe_list: SafeList,
+        audit: AuditLog,
+        config: Config,
+        path_filter: PathFilter,
+        args: &cli::Args,
+    ) -> Self {
+        let own_files = OwnFiles::new(config.log.as_deref());
+        let dir_tree = DirTree::new(
+            root,
+            safe_list,
+            config.protected,
+            &own_files,
+            config.delete_mode,
+            config.dry_run,
+            config.max_depth,
+        );
+        let hovered = ListState::default().with_selected(Some(0));
+        let (scan_sender, scan_receiver) = mpsc::channel();
+        let (size_sender, size_receiver) = mpsc::channel();
+        let disk_space = disk::space(&dir_tree.base_node.borrow().path());
+
+        Self {
+            hovered,
+            selected: Vec::new(),
+            dir_tree,
+            root_link,
+            follow_root_symlink: args.follow_root_symlink,
+            awaiting_depth: false,
+            awaiting_type: false,
+            range_anchor: None,
+            range_base: Vec::new(),
+            clear_confirm_threshold: args.clear_confirm_threshold,
+            confirm: None,
+            force: args.force,
+            readonly: config.readonly,
+            never_select: config.never_select,
+            type_above_entries: config.type_above_entries,
+            type_above_size: config.type_above_size,
+            disk_space,
+            shell_request: None,
+            edit_request: None,
+            stop_requested: false,
+            status: args
+                .show_accessed
+                .then(|| (ACCESSED_CAVEAT.to_string(), Instant::now())),
+            summary: DeletionSummary::default(),
+            removed: Vec::new(),
+            audit,
+            git_marks: git::Marks::default(),
+            duplicates: Duplicates::default(),
+            path_filter,
+            own_files,
+            select_implies_children: true,
+            show_accessed: args.show_accessed,
+            min_size: None,
+            age: None,
+            search: None,
+            prompt: None,
+            last_action: None,
+            repeat_key: args.repeat_key,
+            keymap: config.keymap,
+            theme: config.theme,
+            render_style: if config.ascii {
+                RenderStyle::Ascii
+            } else {
+                RenderStyle::Unicode
+            },
+            no_color: config.no_color,
+            confirm_outside_links: args.confirm_outside_links,
+            follow_symlinks: args.follow_symlinks,
+            export_requested: false,
+            shows_progress: false,
+            redraw: false,
+            compare_mark: None,
+            comparison: None,
+            trashed: Vec::new(),
+            restore_max_items: config.restore_max_items,
+            restore_max_size: config.restore_max_size,
+            unrestorable: Vec::new(),
+            clipboard: clipboard::Clipboard::default(),
+            uncopied: Vec::new(),
+            show_help: false,
+            show_details: false,
+            show_real_path: false,
+            bookmarks: Bookmarks::load(),
+            bookmark_list: None,
+            trash_list: None,
+            finder: None,
+            details: None,
+            show_hidden: false,
+            ignored_view: IgnoredView::Everything,
+            du: args.du,
+            stats: config.stats,
+            flat: false,
+            sort: Sort {
+                mode: if args.du {
+                    SortMode::Size
+                } else {
+                    SortMode::Name
+                },
+                reverse: false,
+                directories_first: config.directories_first,
+            },
+            recent_view: None,
+            list_height: 0,
+            list_area: Rect::default(),
+            last_click: None,
+            scan_sender,
+            scan_receiver,
+            scanning: 0,
+            size_sender,
+            size_receiver,
+            measuring: 0,
+            expanding: VecDeque::new(),
+            expanded: 0,
+            expand_visited: HashSet::new(),
+            largest_trail: Vec::new(),
+            tabs: Vec::new(),
+            active: 0,
+            exit: false,
+        }
+    }
+
+    fn view(&self) -> ViewOptions<'_> {
+        ViewOptions {
+            implies_children: self.select_implies_children,
+            show_accessed: self.show_accessed,
+            min_size: self.min_size,
+            age: self.age,
+            search: self.search.as_deref(),
+            show_hidden: self.show_hidden,
+            ignored: self.ignored_view,
+            sort: self.sort,
+            protected: &self.dir_tree.protected,
+            own_files: &self.dir_tree.own_files,
+            git: &self.git_marks,
+            duplicates: &self.duplicates,
+            paths: &self.path_filter,
+            flat: self.flat,
+        }
+    }
+
+    /// Read more of any directory whose placeholder row is within a
+    /// screenful of the hovered row
+    fn load_near_hover(&mut self) {
+        let reach = self.hovered.selected().unwrap_or(0) + self.list_height.max(1);
+        let arr = self.dir_tree.to_array(self.view());
+        let rows = self.dir_tree.to_enriched_array(&self.selected, self.view());
+        let placeholders = arr
+            .iter()
+            .zip(rows)
+            .take(reach + 1)
+            .filter(|(_, row)| row.is_placeholder)
+            .filter_map(|(path, _)| self.dir_tree.find_node(path));
+        for dir in placeholders {
+            if let Some(scan) = &*dir.borrow().scan.borrow() {
+                if scan.request_more() {
+                    self.scanning += 1;
+                }
+            }
+        }
+    }
+
+    /// Keep reading whatever the flat view has yet to list, a few
+    /// directories at a time in the background
+    fn load_flat(&mut self) {
+        if !self.flat || self.scanning >= FLAT_SCANS {
+            return;
+        }
+        for dir in self.dir_tree.unlisted_dirs(self.view()) {
+            if self.scanning >= FLAT_SCANS {
+                break;
+            }
+            if !dir.borrow().has_more() {
+                self.start_scan(&dir);
+            } else if let Some(scan) = &*dir.borrow().scan.borrow() {
+                if scan.request_more() {
+                    self.scanning += 1;
+                }
+            }
+        }
+    }
+
+    /// Start measuring the directories on screen that haven't been yet, a
+    /// few at a time, see `receive_sizes`
+    fn measure_visible(&mut self) {
+        let arr = self.dir_tree.to_array(self.view());
+        let (skip, take) = if self.sort.mode == SortMode::Size {
+            // where a row goes depends on every size, not only those shown
+            (0, arr.len())
+        } else {
+            (self.hovered.offset(), self.list_height.max(1))
+        };
+        for path in arr.iter().skip(skip).take(take) {
+            if self.measuring >= SIZE_JOBS {
+                break;
+            }
+            if let Some(node) = self.dir_tree.find_node(path) {
+                self.start_measuring(&node.borrow());
+            }
+        }
+    }
+
+    /// Measure `node` in the background if it's a directory with no size
+    /// yet, see `receive_sizes`. Returns whether its size is still to come.
+    fn start_measuring(&mut self, node: &Node) -> bool {
+        if node.type_ != DirType::Dir || node.size.get().is_some() {
+            return false;
+        }
+        if node.sizing.borrow().is_none() && self.measuring < SIZE_JOBS {
+            let job = sizer::start(node.full_path(), node.path(), self.size_sender.clone());
+            node.sizing.replace(Some(job));
+            self.measuring += 1;
+        }
+        true
+    }
+
+    /// Take in the sizes the background walks have come up with since last
+    /// time. One whose directory has gone, or was measured again or
+    /// changed in the meantime, no longer has its job and is dropped.
+    fn receive_sizes(&mut self) {
+        // a size can hide its row, with the minimum size filter on, or
+        // move it when sorting by size
+        let hovered = (self.min_size.is_some() || self.sort.mode == SortMode::Size)
+            .then(|| self.hovered_path())
+            .flatten();
+        let mut received = false;
+        while let Ok(measured) = self.size_receiver.try_recv() {
+            received = true;
+            self.measuring = self.measuring.saturating_sub(1);
+            let Some(node) = self.dir_tree.find_node(&measured.dir) else {
+                continue;
+            };
+            let node = node.borrow();
+            let current = node
+                .sizing
+                .borrow()
+                .as_ref()
+                .is_some_and(|job| job.id() == measured.id);
+            if current {
+                node.sizing.take();
+                node.size.set(Some(measured.bytes));
+            }
+        }
+        if received && hovered.is_some() {
+            self.rehover(hovered);
+        }
+    }
+
+    /// Read `node` in the background, see `receive_scans`
+    fn start_scan(&mut self, node: &NodeRef) {
+        let (dir, path) = (node.borrow().full_path(), node.borrow().path());
+        let scan = scanner::start(dir, path, SCAN_CHUNK, self.scan_sender.clone());
+        node.borrow().scan.replace(Some(scan));
+        self.scanning += 1;
+    }
+
+    /// Graft whatever the background scans have read since last time
+    fn receive_scans(&mut self) {
+        while let Ok(chunk) = self.scan_receiver.try_recv() {
+            self.scanning = self.scanning.saturating_sub(1);
+            // closed and read again in the meantime, or removed
+            let Some(node) = self.dir_tree.find_node(&chunk.dir) else {
+                continue;
+            };
+            if !node.borrow().has_more() {
+                continue;
+            }
+
+            let hovered = self.hovered_path();
+            match &chunk.error {
+                // nothing could be read at all, so there's nothing to show
+                Some(err)
+                    if chunk.entries.is_empty() && node.borrow().children.borrow().is_empty() =>
+                {
+                    self.set_status(format!("Could not open {}: {err}", chunk.dir));
+                    node.borrow().read_error.set(Some(err.kind()));
+                    node.borrow_mut().is_open = false;
+                }
+                Some(err) => {
+                    self.set_status(format!("Failed to read all of {}: {err}", chunk.dir));
+                    node.borrow().read_error.set(Some(err.kind()));
+                }
+                None => node.borrow().read_error.set(None),
+            }
+            let dir = PathBuf::from(&chunk.dir);
+            Node::graft(node, chunk);
+            self.note_repo(&dir);
+            self.rehover(hovered);
+        }
+    }
+
+    /// Ask git about `dir` if it's the top of a repository not seen yet
+    fn note_repo(&mut self, dir: &Path) {
+        if self.git_marks.is_new_repo(dir) {
+            self.git_marks.load(dir);
+        }
+    }
+
+    fn hovered_path(&self) -> Option<String> {
+        let idx = self.hovered.selected()?;
+        self.dir_tree.to_array(self.view()).get(idx).cloned()
+    }
+
+    /// Hover `path` again after the visible rows changed, or the nearest
+    /// valid row if it's no longer shown
+    fn rehover(&mut self, path: Option<String>) {
+        let arr = self.dir_tree.to_array(self.view());
+        let idx = path
+            .and_then(|path| arr.iter().position(|p| *p == path))
+            .unwrap_or_else(|| {
+                self.hovered
+                    .selected()
+                    .unwrap_or(0)
+                    .min(arr.len().saturating_sub(1))
+            });
+        self.hovered.select(Some(idx));
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = self.prompt.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) if matches!(prompt.kind, PromptKind::Row) && !c.is_ascii_digit() => {
+                return
+            }
+            KeyCode::Char(c) => prompt.input.push(c),
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Esc => {
+                if let Some(Prompt {
+                    kind: PromptKind::Search,
+                    ..
+                }) = self.prompt.take()
+                {
+                    self.handle_set_search("");
+                }
+                return;
+            }
+            KeyCode::Enter => {
+                let prompt = self.prompt.take().unwrap();
+                self.handle_submit_prompt(prompt);
+                return;
+            }
+            _ => return,
+        }
+
+        // the search narrows the list as it's typed
+        if let PromptKind::Search = prompt.kind {
+            let input = prompt.input.clone();
+            self.handle_set_search(&input);
+        }
+    }
+
+    fn handle_finder_key(&mut self, key: KeyEvent) {
+        let Some(finder) = self.finder.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Up => finder.pick_previous(),
+            KeyCode::Down => finder.pick_next(),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                finder.pick_previous()
+            }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                finder.pick_next()
+            }
+            KeyCode::Char(c) => {
+                finder.query.push(c);
+                finder.update();
+            }
+            KeyCode::Backspace => {
+                finder.query.pop();
+                finder.update();
+            }
+            KeyCode::Enter => {
+                let picked = finder.picked().cloned();
+                self.finder = None;
+                if let Some(path) = picked {
+                    self.handle_jump_to(&path);
+                }
+            }
+            KeyCode::Esc => self.finder = None,
+            _ => {}
+        }
+    }
+
+    /// Hover `path`, opening the directories it's in
+    fn handle_jump_to(&mut self, path: &str) {
+        let Some(node) = self.dir_tree.find_node(path) else {
+            return;
+        };
+        let mut parent = node.borrow().parent.borrow().upgrade();
+        while let Some(dir) = parent {
+            dir.borrow_mut().is_open = true;
+            parent = dir.borrow().parent.borrow().upgrade();
+        }
+        self.rehover(Some(path.to_string()));
+    }
+
+    fn handle_submit_prompt(&mut self, prompt: Prompt) {
+        match prompt.kind {
+            PromptKind::MinSize => self.handle_set_min_size(prompt.input.trim()),
+            PromptKind::Age => self.handle_set_age(prompt.input.trim()),
+            PromptKind::Search => self.handle_set_search(&prompt.input),
+            PromptKind::Root => self.handle_change_root(&prompt.input),
+            PromptKind::Tab => self.handle_open_tab(&prompt.input),
+            PromptKind::Glob => self.handle_select_glob(prompt.input.trim()),
+            PromptKind::Row => self.handle_go_to_row(&prompt.input),
+            PromptKind::Move => self.handle_move_selected(&prompt.input),
+        }
+    }
+
+    /// Browse the directory at `input` from now on, found relative to the
+    /// current root. The selection and everything that was open belong to
+    /// the old tree, so they go with it.
+    fn handle_change_root(&mut self, input: &str) {
+        let Some(pane) = self.pane_for(input) else {
+            return;
+        };
+        let saved = self.dir_tree.save_expanded();
+        self.show_pane(pane);
+        let browsing = format!("Browsing {}", self.dir_tree.base_node.borrow().full_path());
+        self.set_status(match saved {
+            Ok(()) => browsing,
+            Err(err) => format!("{browsing}, but could not save the open directories: {err}"),
+        });
+    }
+
+    /// Browse the directory at `input` in a new tab after the shown one,
+    /// leaving the shown tree as it is
+    fn handle_open_tab(&mut self, input: &str) {
+        let Some(pane) = self.pane_for(input) else {
+            return;
+        };
+        let previous = self.show_pane(pane);
+        self.tabs.insert(self.active, previous);
+        self.active += 1;
+        let browsing = format!(
+            "Browsing {} in tab {} of {}",
+            self.dir_tree.base_node.borrow().full_path(),
+            self.active + 1,
+            self.tabs.len() + 1
+        );
+        self.set_status(browsing);
+    }
+
+    /// A fresh tab for the directory at `input`, found relative to the
+    /// current root, or `None` once the status says why it can't be opened
+    fn pane_for(&mut self, input: &str) -> Option<Pane> {
+        let input = input.trim();
+        if input.is_empty() {
+            return None;
+        }
+        let path = normalize(&self.dir_tree.base_node.borrow().path().join(input));
+        let (root, root_link) = match resolve_root(&path, self.follow_root_symlink) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                self.set_status(format!("Could not open {input}: {err}"));
+                return None;
+            }
+        };
+        let dir_tree = DirTree::new(
+            root,
+            self.dir_tree.safe_list.clone(),
+            self.dir_tree.protected.clone(),
+            &self.own_files,
+            self.dir_tree.delete_mode,
+            self.dir_tree.dry_run,
+            self.dir_tree.max_depth,
+        );
+        Some(Pane::new(dir_tree, root_link))
+    }
+
+    /// Show `pane`, opening its root, and hand back the one shown until now
+    fn show_pane(&mut self, mut pane: Pane) -> Pane {
+        self.swap_pane(&mut pane);
+        let base = self.dir_tree.base_node.clone();
+        self.git_marks.load(&base.borrow().path());
+        self.toggle_dir(base);
+        self.restore_expanded();
+        pane
+    }
+
+    /// Switch to the tab `offset` places after the shown one, wrapping
+    /// around at either end
+    fn handle_switch_tab(&mut self, offset: isize) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        let count = self.tabs.len() as isize + 1;
+        let to = (self.active as isize + offset).rem_euclid(count) as usize;
+        let mut pane = self.tabs.remove(if to > self.active { to - 1 } else { to });
+        self.swap_pane(&mut pane);
+        self.tabs.insert(
+            if self.active > to {
+                self.active - 1
+            } else {
+                self.active
+            },
+            pane,
+        );
+        self.active = to;
+        // removals in another tab may have taken some of this one's entries
+        self.prune_selection();
+        let hovered = self.hovered_path();
+        self.rehover(hovered);
+    }
+
+    /// Close the shown tab and show the one after it, or before it when it
+    /// was the last. The only tab can't be closed; quit instead.
+    fn handle_close_tab(&mut self) {
+        if self.tabs.is_empty() {
+            self.set_status("This is the only tab".to_string());
+            return;
+        }
+        let saved = self.dir_tree.save_expanded();
+        let closed = self.dir_tree.base_node.borrow().full_path();
+        let to = self.active.min(self.tabs.len() - 1);
+        let mut pane = self.tabs.remove(to);
+        self.swap_pane(&mut pane);
+        self.active = to;
+        self.prune_selection();
+        self.set_status(match saved {
+            Ok(()) => format!("Closed {closed}"),
+            Err(err) => format!("Closed {closed}, but could not save its open directories: {err}"),
+        });
+    }
+
+    /// Trade the shown tab's state for `pane`'s. Removing to the trash or
+    /// for good is a setting of the session, so it stays as it was.
+    fn swap_pane(&mut self, pane: &mut Pane) {
+        let delete_mode = self.dir_tree.delete_mode;
+        mem::swap(&mut self.dir_tree, &mut pane.dir_tree);
+        mem::swap(&mut self.root_link, &mut pane.root_link);
+        mem::swap(&mut self.selected, &mut pane.selected);
+        mem::swap(&mut self.hovered, &mut pane.hovered);
+        mem::swap(&mut self.range_anchor, &mut pane.range_anchor);
+        mem::swap(&mut self.range_base, &mut pane.range_base);
+        mem::swap(&mut self.compare_mark, &mut pane.compare_mark);
+        mem::swap(&mut self.largest_trail, &mut pane.largest_trail);
+        mem::swap(&mut self.details, &mut pane.details);
+        mem::swap(&mut self.last_click, &mut pane.last_click);
+        mem::swap(&mut self.scan_sender, &mut pane.scan_sender);
+        mem::swap(&mut self.scan_receiver, &mut pane.scan_receiver);
+        mem::swap(&mut self.scanning, &mut pane.scanning);
+        mem::swap(&mut self.size_sender, &mut pane.size_sender);
+        mem::swap(&mut self.size_receiver, &mut pane.size_receiver);
+        mem::swap(&mut self.measuring, &mut pane.measuring);
+        mem::swap(&mut self.expanding, &mut pane.expanding);
+        mem::swap(&mut self.expanded, &mut pane.expanded);
+        mem::swap(&mut self.expand_visited, &mut pane.expand_visited);
+        self.dir_tree.delete_mode = delete_mode;
+        self.refresh_disk_space();
+    }
+
+    /// Open the directories that were open when this root was last left,
+    /// dropping any that are gone since
+    fn restore_expanded(&mut self) {
+        let base = self.dir_tree.base_node.borrow().path();
+        let Ok(root) = fs::canonicalize(&base) else {
+            return;
+        };
+        for relative in expansion::load(&root) {
+            let path = base.join(relative);
+            if !path.is_dir() {
+                continue;
+            }
+            let Some(node) = self.dir_tree.load_path(&path.to_string_lossy()) else {
+                continue;
+            };
+            let closed = {
+                let node = node.borrow();
+                node.type_ == DirType::Dir && !node.is_open && self.dir_tree.can_open(&node)
+            };
+            if closed {
+                self.toggle_dir(node);
+            }
+        }
+    }
+
+    fn handle_set_search(&mut self, query: &str) {
+        let hovered = self.hovered_path();
+        self.search = (!query.is_empty()).then(|| query.to_lowercase());
+        self.rehover(hovered);
+    }
+
+    fn handle_set_min_size(&mut self, input: &str) {
+        let min_size = if input.is_empty() {
+            None
+        } else if let Some(bytes) = format::parse_size(input) {
+            Some(bytes)
+        } else {
+            self.set_status(format!("Invalid size '{input}', try e.g. >100M or 1.5G"));
+            return;
+        };
+
+        let hovered = self.hovered_path();
+        self.min_size = min_size;
+        self.rehover(hovered);
+    }
+
+    /// Filter by `>30d` (older than) or `<2h` (newer than); a bare
+    /// duration means older than
+    fn handle_set_age(&mut self, input: &str) {
+        let age = if input.is_empty() {
+            None
+        } else {
+            let (older, duration) = match input.strip_prefix('<') {
+                Some(duration) => (false, duration),
+                None => (true, input.strip_prefix('>').unwrap_or(input)),
+            };
+            match format::parse_age(duration) {
+                Some(age) => Some(AgeFilter { older, age }),
+                None => {
+                    self.set_status(format!("Invalid age '{input}', try e.g. >30d or <2h"));
+                    return;
+                }
+            }
+        };
+
+        let hovered = self.hovered_path();
+        self.age = age;
+        self.rehover(hovered);
+    }
+
+    fn handle_copy_path(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        match self.clipboard.copy(&path) {
+            Ok(()) => self.set_status(format!("Copied {path}")),
+            Err(_) => {
+                self.set_status(format!(
+                    "No clipboard to copy to, {path} is printed on exit instead"
+                ));
+                self.uncopied.push(path);
+            }
+        }
+    }
+
+    fn handle_exit(&mut self) {
+        self.exit = true;
+    }
+
+    fn handle_select_dir(&mut self) {
+        let Some(node_path) = self.hovered_path() else {
+            return;
+        };
+        let Some(node) = self.dir_tree.find_node(&node_path) else {
+            return;
+        };
+
+        if self.is_path_selected(&node_path) {
+            self.deselect_path(&node_path);
+        } else if let Some(dir) = self.selected_ancestor(&node_path) {
+            self.set_status(format!("{node_path} is selected already, with {dir}"));
+        } else {
+            self.select_node(node);
+        }
+    }
+
+    /// Select every visible entry in the hovered entry's directory, or in
+    /// the root when that's what is hovered
+    fn handle_select_siblings(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+        let parent = node.borrow().parent.borrow().upgrade();
+        let dir = parent.unwrap_or(node);
+        let mut skipped = 0;
+        for child in dir.borrow().visible_children(self.view()) {
+            self.select_in_bulk(child, &mut skipped);
+        }
+        self.report_skipped(skipped);
+    }
+
+    /// Whether the bulk selections leave `node` out, for having one of the
+    /// extensions in `[select] never`
+    fn never_selected(&self, node: &Node) -> bool {
+        node.type_ != DirType::Dir
+            && Path::new(&node.name).extension().is_some_and(|extension| {
+                let extension = extension.to_string_lossy().to_lowercase();
+                self.never_select.contains(&extension)
+            })
+    }
+
+    /// `select_node` for the commands that select many entries at once,
+    /// which pass over the files `never_selected` says to and count them in
+    /// `skipped`. A directory holding such a file has its other entries
+    /// selected in its place, as its own selection would take them along.
+    fn select_in_bulk(&mut self, node: NodeRef, skipped: &mut usize) -> bool {
+        if self.never_selected(&node.borrow()) {
+            *skipped += 1;
+            return false;
+        }
+        let is_dir = node.borrow().type_ == DirType::Dir;
+        if is_dir && self.select_implies_children && !self.never_select.is_empty() {
+            let mut inside = Vec::new();
+            node.borrow().descendants(&mut inside);
+            if inside
+                .iter()
+                .any(|inside| self.never_selected(&inside.borrow()))
+            {
+                let children = node.borrow().children.borrow().clone();
+                let mut any = false;
+                for child in children {
+                    any |= self.select_in_bulk(child, skipped);
+                }
+                return any;
+            }
+        }
+        self.select_node(node)
+    }
+
+    /// Say how many files a bulk selection passed over for `[select] never`
+    fn report_skipped(&mut self, skipped: usize) {
+        if skipped > 0 {
+            self.set_status(format!(
+                "Left {} files out of the selection by their extension ([select] never)",
+                format::count(skipped as u64)
+            ));
+        }
+    }
+
+    /// Select every loaded entry the view shows that matches `pattern`. A
+    /// pattern with a `/` is matched against the path below the root, with
+    /// `*` staying within one directory; one without is matched against
+    /// names, like a `.gitignore` line.
+    fn handle_select_glob(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            return;
+        }
+        let matcher = match GlobBuilder::new(pattern.trim_start_matches('/'))
+            .literal_separator(true)
+            .build()
+        {
+            Ok(glob) => glob.compile_matcher(),
+            Err(err) => {
+                self.set_status(format!("Invalid pattern: {err}"));
+                return;
+            }
+        };
+        let by_path = pattern.contains('/');
+
+        let base = self.dir_tree.base_node.borrow().path();
+        let paths = self.dir_tree.loaded_paths(self.view());
+        let (mut matched, mut selected, mut skipped) = (0, 0, 0);
+        // what `select_node` turned down, by why
+        let (mut safe, mut own) = (0, 0);
+        for path in paths.iter().skip(1) {
+            let relative = Path::new(path)
+                .strip_prefix(&base)
+                .unwrap_or(Path::new(path));
+            let candidate = if by_path {
+                relative
+            } else {
+                Path::new(relative.file_name().unwrap_or_default())
+            };
+            if !matcher.is_match(candidate) {
+                continue;
+            }
+            matched += 1;
+            let node = self.dir_tree.find_node(path).unwrap();
+            let skipped_before = skipped;
+            if self.select_in_bulk(node.clone(), &mut skipped) {
+                selected += 1;
+            } else if skipped == skipped_before {
+                let path = node.borrow().path();
+                if self.dir_tree.safe_list.protecting(&path).is_some() {
+                    safe += 1;
+                } else if self.dir_tree.own_files.owning(&path).is_some() {
+                    own += 1;
+                }
+            }
+        }
+
+        let status = match matched {
+            0 => format!("Nothing loaded matches {pattern}"),
+            n if n == selected => format!(
+                "Selected {} entries matching {pattern}",
+                format::count(n as u64)
+            ),
+            n => format!(
+                "Selected {} of the {} entries matching {pattern}",
+                format::count(selected as u64),
+                format::count(n as u64)
+            ),
+        };
+        let mut refused = Vec::new();
+        if safe > 0 {
+            refused.push(format!("{} on the safe list", format::count(safe)));
+        }
+        if own > 0 {
+            refused.push(format!("{} of irm's own files", format::count(own)));
+        }
+        let refused = match refused.as_slice() {
+            [] => String::new(),
+            reasons => format!(", leaving out {}", reasons.join(" and ")),
+        };
+        self.set_status(format!("{status}{refused}{}", skipped_note(skipped)));
+    }
+
+    /// Select every visible entry that isn't selected and deselect the rest.
+    /// The root is left out, and so is whatever the filters hide.
+    fn handle_invert_selection(&mut self) {
+        let arr = self.dir_tree.to_array(self.view());
+        let root = self.dir_tree.base_node.borrow().full_path();
+        let mut skipped = 0;
+        for path in arr.iter().filter(|path| **path != root) {
+            if self.is_path_selected(path) {
+                self.deselect_path(path);
+            } else if let Some(node) = self.dir_tree.find_node(path) {
+                self.select_in_bulk(node, &mut skipped);
+            }
+        }
+        self.report_skipped(skipped);
+    }
+
+    fn handle_start_range(&mut self) {
+        self.range_anchor = self.hovered_path();
+        self.range_base = self.selected.clone();
+        self.extend_range();
+    }
+
+    /// Select the rows from the range anchor to the hovered one, on top of
+    /// what was selected before the range started
+    fn extend_range(&mut self) {
+        let (Some(anchor), Some(hovered)) = (&self.range_anchor, self.hovered.selected()) else {
+            return;
+        };
+        let arr = self.dir_tree.to_array(self.view());
+        // the anchor's row is gone, removed or filtered away
+        let Some(anchor) = arr.iter().position(|path| path == anchor) else {
+            self.range_anchor = None;
+            return;
+        };
+        let hovered = hovered.min(arr.len() - 1);
+        self.selected = self.range_base.clone();
+        for path in &arr[anchor.min(hovered)..=anchor.max(hovered)] {
+            if let Some(node) = self.dir_tree.find_node(path) {
+                self.select_node(node);
+            }
+        }
+    }
+
+    /// The nodes of the selected paths that are in the tree
+    fn selected_nodes(&self) -> Vec<NodeRef> {
+        self.selected
+            .iter()
+            .filter_map(|path| self.dir_tree.find_node(path))
+            .collect()
+    }
+
+    /// Drop the selected paths that are no longer in the tree
+    fn prune_selection(&mut self) {
+        let selected = std::mem::take(&mut self.selected);
+        self.selected = selected
+            .into_iter()
+            .filter(|path| self.dir_tree.find_node(path).is_some())
+            .collect();
+    }
+
+    fn is_path_selected(&self, path: &str) -> bool {
+        self.selected.iter().any(|selected| selected == path)
+    }
+
+    /// Add `node` to the selection, unless it's protected by the safe list
+    /// or is one of irm's own files. While a directory's selection takes its
+    /// contents with it, the selection never holds both a directory and
+    /// something in it: an entry under a selected directory is left as it
+    /// is, and selecting a directory drops whatever was selected in it.
+    /// Returns whether it's selected now, by itself or with a directory.
+    fn select_node(&mut self, node: NodeRef) -> bool {
+        let path = node.borrow().full_path();
+        if let Some(safe) = self.dir_tree.safe_list.protecting(&node.borrow().path()) {
+            self.set_status(format!(
+                "Refusing to select {path}: {} is on the safe list",
+                safe.display()
+            ));
+            return false;
+        }
+        if let Some(own) = self.dir_tree.own_files.owning(&node.borrow().path()) {
+            self.set_status(format!(
+                "Refusing to select {path}: {} is one of irm's own files",
+                own.display()
+            ));
+            return false;
+        }
+        if self.selected_ancestor(&path).is_some() {
+            return true;
+        }
+        if self.select_implies_children {
+            self.selected
+                .retain(|selected| !Path::new(selected).starts_with(&path));
+        }
+        if !self.is_path_selected(&path) {
+            self.selected.push(path);
+        }
+        true
+    }
+
+    /// The selected directory that `path` is inside of, when that selects
+    /// everything in it
+    fn selected_ancestor(&self, path: &str) -> Option<String> {
+        if !self.select_implies_children {
+            return None;
+        }
+        self.selected
+            .iter()
+            .find(|selected| *selected != path && Path::new(path).starts_with(selected))
+            .cloned()
+    }
+
+    fn deselect_path(&mut self, path: &str) {
+        self.selected.retain(|selected| selected != path);
+    }
+
+    /// Put the most recently trashed entry back, and show it in the tree
+    /// again if its directory is loaded
+    fn handle_undo_trash(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        let Some(item) = self.trashed.pop() else {
+            self.set_status("Nothing has been moved to the trash yet".to_string());
+            return;
+        };
+
+        if let Err(err) = restore::restore(Path::new(&item.path)) {
+            self.set_status(format!("Could not restore {}: {err}", item.path));
+            self.log(audit::Event::RestoreFailed(&err.to_string()), &item.path);
+            self.trashed.push(item);
+            return;
+        }
+        self.summary.subtract(&item.removed);
+        self.removed.retain(|path| *path != item.path);
+        self.refresh_disk_space();
+        self.set_status(format!("Restored {}", item.path));
+        self.log(audit::Event::Restored, &item.path);
+
+        let parent = Path::new(&item.path)
+            .parent()
+            .and_then(|parent| self.dir_tree.find_node(&parent.to_string_lossy()));
+        if let Some(parent) = parent {
+            let hovered = self.hovered_path();
+            if let Err(err) = Node::rescan(parent) {
+                self.set_status(format!("Restored {}, but {err}", item.path));
+            }
+            self.rehover(hovered);
+        }
+    }
+
+    fn handle_show_trash(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        self.trash_list = Some(TrashList {
+            row: 0,
+            picked: vec![false; self.trashed.len()],
+            everything: false,
+        });
+    }
+
+    fn handle_trash_list_key(&mut self, key: KeyEvent) {
+        let Some(list) = &mut self.trash_list else {
+            return;
+        };
+        // the session's items, most recent first, then the rest of the trash
+        let last = list.picked.len();
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => list.row = list.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => list.row = (list.row + 1).min(last),
+            KeyCode::Char(' ') if list.row == last => list.everything = !list.everything,
+            KeyCode::Char(' ') => {
+                let item = last - 1 - list.row;
+                list.picked[item] = !list.picked[item];
+            }
+            KeyCode::Char('a') => {
+                let all = list.picked.iter().all(|picked| *picked);
+                list.picked.fill(!all);
+            }
+            KeyCode::Enter => {
+                let paths: Vec<_> = self
+                    .trashed
+                    .iter()
+                    .zip(&list.picked)
+                    .filter(|(_, picked)| **picked)
+                    .map(|(item, _)| item.path.clone())
+                    .collect();
+                if paths.is_empty() && !list.everything {
+                    self.set_status("Nothing is picked, <Space> picks the hovered item");
+                    return;
+                }
+                let purge = Confirm::Purge(paths, list.everything);
+                self.trash_list = None;
+                // never on a single key, whatever --force says
+                self.confirm = Some(Confirm::Typed(Box::new(purge), String::new()));
+            }
+            _ => self.trash_list = None,
+        }
+    }
+
+    /// Delete `paths`, each the most recent item trashed from there, from
+    /// the trash for good, and everything else in it too with `everything`
+    fn handle_purge(&mut self, paths: Vec<String>, everything: bool) {
+        let (mut purged, mut freed, mut failed) = (0, 0, None);
+        for path in &paths {
+            let Some(i) = self.trashed.iter().rposition(|item| item.path == *path) else {
+                continue;
+            };
+            match restore::purge(Path::new(path)) {
+                Ok(()) => {
+                    let item = self.trashed.remove(i);
+                    self.summary.trashed_bytes = self
+                        .summary
+                        .trashed_bytes
+                        .saturating_sub(item.removed.trashed_bytes);
+                    freed += item.removed.trashed_bytes;
+                    purged += 1;
+                    self.log(audit::Event::Purged, path);
+                }
+                Err(err) => {
+                    self.log(audit::Event::PurgeFailed(&err.to_string()), path);
+                    failed = Some(format!("{path}: {err}"));
+                }
+            }
+        }
+        let mut others = 0;
+        if everything && failed.is_none() {
+            match restore::purge_everything() {
+                Ok(count) => {
+                    // whatever was left of this session's went with the rest
+                    for item in mem::take(&mut self.trashed) {
+                        self.summary.trashed_bytes = self
+                            .summary
+                            .trashed_bytes
+                            .saturating_sub(item.removed.trashed_bytes);
+                        freed += item.removed.trashed_bytes;
+                        purged += 1;
+                        self.log(audit::Event::Purged, &item.path);
+                    }
+                    others = count.saturating_sub(purged);
+                }
+                Err(err) => failed = Some(format!("the rest of the trash: {err}")),
+            }
+        }
+        self.refresh_disk_space();
+
+        let items = |n: usize| match n {
+            1 => "1 item".to_string(),
+            n => format!("{} items", format::count(n as u64)),
+        };
+        let mut status = format!(
+            "Deleted {} from the trash for good, freeing {}",
+            items(purged),
+            format::size(freed)
+        );
+        if others > 0 {
+            status += &format!(", and {} more from before", items(others));
+        }
+        if let Some(failure) = failed {
+            status += &format!(", stopped at {failure}");
+        }
+        self.set_status(status);
+    }
+
+    /// Read the hovered directory (or the hovered entry's directory) and
+    /// everything loaded below it from disk again
+    fn handle_refresh(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+        let is_dir = node.borrow().type_ == DirType::Dir;
+        let parent = node.borrow().parent.borrow().upgrade();
+        let dir = match parent {
+            Some(parent) if !is_dir => parent,
+            _ => node,
+        };
+
+        let dir_path = dir.borrow().full_path();
+        self.set_status(match Node::refresh(dir) {
+            Ok(()) => format!("Refreshed {dir_path}"),
+            Err(err) => format!("Could not refresh {dir_path}: {err}"),
+        });
+        self.git_marks.refresh();
+        // whatever is gone from disk is no longer selected
+        self.prune_selection();
+        self.details = None;
+        self.rehover(Some(path));
+    }
+
+    fn handle_toggle_delete_mode(&mut self) {
+        self.dir_tree.delete_mode = match self.dir_tree.delete_mode {
+            DeleteMode::Permanent => DeleteMode::Trash,
+            DeleteMode::Trash => DeleteMode::Permanent,
+        };
+    }
+
+    fn handle_toggle_hidden(&mut self) {
+        let hovered = self.hovered_path();
+        self.show_hidden = !self.show_hidden;
+        self.rehover(hovered);
+    }
+
+    fn handle_toggle_flat(&mut self) {
+        let hovered = self.hovered_path();
+        self.flat = !self.flat;
+        self.rehover(hovered);
+        self.set_status(if self.flat {
+            "Listing every file below the root, <z> for the tree".to_string()
+        } else {
+            "Showing the tree".to_string()
+        });
+    }
+
+    fn handle_cycle_ignored_view(&mut self) {
+        let hovered = self.hovered_path();
+        self.ignored_view = self.ignored_view.next();
+        self.rehover(hovered);
+        if self.ignored_view == IgnoredView::Dim {
+            self.set_status("Dimming entries ignored by .gitignore files".to_string());
+        }
+    }
+
+    fn handle_cycle_sort(&mut self) {
+        let hovered = self.hovered_path();
+        self.sort.mode = self.sort.mode.next();
+        self.sort.reverse = false;
+        self.rehover(hovered);
+        self.set_status(format!("Sorted by {}", self.sort.describe()));
+    }
+
+    fn handle_reverse_sort(&mut self) {
+        let hovered = self.hovered_path();
+        self.sort.reverse = !self.sort.reverse;
+        self.rehover(hovered);
+        self.set_status(format!("Sorted by {}", self.sort.describe()));
+    }
+
+    /// Sort by modification time, newest first, and only show what
+    /// changed within `RECENT_AGE`, or go back to the sort and age filter
+    /// from before
+    fn handle_toggle_recent(&mut self) {
+        let hovered = self.hovered_path();
+        match self.recent_view.take() {
+            Some((sort, age)) => {
+                self.sort = sort;
+                self.age = age;
+                self.set_status(format!("Sorted by {}", self.sort.describe()));
+            }
+            None => {
+                self.recent_view = Some((self.sort, self.age));
+                self.sort.mode = SortMode::Modified;
+                self.sort.reverse = false;
+                let age = AgeFilter {
+                    older: false,
+                    age: RECENT_AGE,
+                };
+                self.age = Some(age);
+                self.set_status(format!("Showing files {}, newest first", age.describe()));
+            }
+        }
+        self.rehover(hovered);
+    }
+
+    fn handle_toggle_accessed(&mut self) {
+        self.show_accessed = !self.show_accessed;
+        if self.show_accessed {
+            self.set_status(ACCESSED_CAVEAT.to_string());
+        }
+    }
+
+    /// Mark the hovered directory as the first side of a comparison, or
+    /// compare it against the one already marked. Pressing it on the marked
+    /// directory again drops the mark.
+    fn handle_mark_compare(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+        if node.borrow().type_ != DirType::Dir {
+            self.set_status(format!("{path} is not a directory"));
+            return;
+        }
+
+        match self.compare_mark.take() {
+            Some(marked) if Rc::ptr_eq(&marked, &node) => {
+                self.set_status(format!("Unmarked {path}"));
+            }
+            Some(marked) => {
+                let marked = marked.borrow().full_path();
+                self.comparison = Some(Comparison::new(&self.dir_tree, marked, path));
+            }
+            None => {
+                self.set_status(format!(
+                    "Marked {path} as A, press c on another directory to compare"
+                ));
+                self.compare_mark = Some(node);
+            }
+        }
+    }
+
+    /// Hover the largest entry next to the hovered one. Pressing it again on
+    /// that entry opens it and moves to the largest entry inside, following
+    /// the disk usage down one level at a time.
+    fn handle_jump_to_largest(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+
+        let is_trail_end = self
+            .largest_trail
+            .last()
+            .is_some_and(|last| Rc::ptr_eq(last, &node));
+        let parent = node.borrow().parent.borrow().upgrade();
+        let dir = match parent {
+            Some(parent) if !is_trail_end => {
+                self.largest_trail.clear();
+                parent
+            }
+            _ => {
+                let is_dir = {
+                    let node = node.borrow();
+                    node.type_ == DirType::Dir && !node.is_symlink()
+                };
+                if !is_dir {
+                    self.set_status(format!(
+                        "Largest: {}, nothing further to follow",
+                        self.describe_largest_trail()
+                    ));
+                    return;
+                }
+                if !self.dir_tree.can_open(&node.borrow()) {
+                    self.set_status(format!(
+                        "Largest: {}, the rest is beyond --max-depth",
+                        self.describe_largest_trail()
+                    ));
+                    return;
+                }
+                if Node::open(node.clone()).is_err() {
+                    self.set_status(format!("Failed to read {path}"));
+                    return;
+                }
+                node
+            }
+        };
+
+        let children = dir.borrow().visible_children(self.view());
+        let mut pending = 0;
+        for child in &children {
+            pending += usize::from(self.start_measuring(&child.borrow()));
+        }
+        if pending > 0 {
+            self.set_status(format!(
+                "Measuring {} directories in {} in the background, press again once they're done",
+                format::count(pending as u64),
+                dir.borrow().full_path()
+            ));
+            return;
+        }
+        let largest = children
+            .into_iter()
+            .max_by_key(|child| child.borrow().size());
+        let Some(largest) = largest else {
+            self.set_status(format!("{} is empty", dir.borrow().full_path()));
+            return;
+        };
+
+        self.rehover(Some(largest.borrow().full_path()));
+        self.largest_trail.push(largest);
+        self.set_status(format!("Largest: {}", self.describe_largest_trail()));
+    }
+
+    /// `./a (1.2 GiB) > b (900.0 MiB) > c (512.0 MiB)`
+    fn describe_largest_trail(&self) -> String {
+        self.largest_trail
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let node = node.borrow();
+                let name = if i == 0 {
+                    node.full_path()
+                } else {
+                    node.name.clone()
+                };
+                format!("{name} ({})", format::size(node.size()))
+            })
+            .collect::<Vec<_>>()
+            .join(" > ")
+    }
+
+    /// Report the hovered entry's size, warning when hardlinks inside it
+    /// make the naive total overstate the space it really takes
+    fn handle_show_usage(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let usage = disk::usage(&self.dir_tree.disk_path(&path));
+
+        self.set_status(if usage.repeated_links > 0 {
+            format!(
+                "{path}: {} apparent, {} unique ({} hardlinks counted once)",
+                format::size(usage.apparent),
+                format::size(usage.unique),
+                format::count(usage.repeated_links as u64)
+            )
+        } else {
+            format!("{path}: {}", format::size(usage.apparent))
+        });
+    }
+
+    fn handle_show_link_chain(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+
+        self.set_status(if node.borrow().is_symlink() {
+            symlink::describe_chain(&self.dir_tree.disk_path(&path))
+        } else {
+            format!("{path} is not a symlink")
+        });
+    }
+
+    /// Bookmark the hovered directory (or the hovered file's directory), or
+    /// forget it if it's bookmarked already
+    fn handle_toggle_bookmark(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+        let parent = node.borrow().parent.borrow().upgrade();
+        let dir = match parent {
+            Some(parent) if node.borrow().type_ != DirType::Dir => parent.borrow().path(),
+            _ => node.borrow().path(),
+        };
+        let real = match fs::canonicalize(&dir) {
+            Ok(real) => real,
+            Err(err) => {
+                self.set_status(format!("Could not bookmark {}: {err}", dir.display()));
+                return;
+            }
+        };
+        let shown = real.display();
+        let status = match self.bookmarks.toggle(&real) {
+            Ok(true) => format!("Bookmarked {shown}, <'> lists the bookmarks"),
+            Ok(false) => format!("Forgot the bookmark for {shown}"),
+            Err(err) => format!("Could not save the bookmarks: {err}"),
+        };
+        self.set_status(status);
+    }
+
+    fn handle_show_bookmarks(&mut self) {
+        if self.bookmarks.paths().is_empty() {
+            self.set_status("No bookmarks yet, <m> bookmarks the hovered directory");
+            return;
+        }
+        self.bookmark_list = Some(0);
+    }
+
+    fn handle_bookmark_list_key(&mut self, key: KeyEvent) {
+        let Some(row) = self.bookmark_list else {
+            return;
+        };
+        let last = self.bookmarks.paths().len().saturating_sub(1);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.bookmark_list = Some(row.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.bookmark_list = Some((row + 1).min(last)),
+            KeyCode::Enter => {
+                self.bookmark_list = None;
+                if let Some(path) = self.bookmarks.paths().get(row).cloned() {
+                    self.handle_open_bookmark(&path);
+                }
+            }
+            _ => self.bookmark_list = None,
+        }
+    }
+
+    /// Browse the bookmarked directory, or drop the bookmark if it's gone
+    fn handle_open_bookmark(&mut self, path: &str) {
+        if !Path::new(path).is_dir() {
+            let status = match self.bookmarks.remove(path) {
+                Ok(()) => format!("{path} no longer exists, forgot its bookmark"),
+                Err(err) => {
+                    format!("{path} no longer exists, and the bookmarks could not be saved: {err}")
+                }
+            };
+            self.set_status(status);
+            return;
+        }
+        self.handle_change_root(path);
+    }
+
+    fn handle_open_shell(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+        let node = node.borrow();
+
+        let dir = match (&node.type_, node.parent.borrow().upgrade()) {
+            (DirType::Dir, _) | (_, None) => node.full_path(),
+            (_, Some(parent)) => parent.borrow().full_path(),
+        };
+        self.shell_request = Some(dir);
+    }
+
+    /// Open the hovered entry with whatever the platform opens it with,
+    /// which for a directory is the file manager
+    fn handle_open(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let shown = self.dir_tree.display_path(&path);
+        match launch::open(&self.dir_tree.disk_path(&path)) {
+            Ok(()) => self.set_status(format!("Opened {shown}")),
+            Err(err) => self.set_status(format!("Could not open {shown}: {err}")),
+        }
+    }
+
+    fn handle_reveal(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let shown = self.dir_tree.display_path(&path);
+        match launch::reveal(&self.dir_tree.disk_path(&path)) {
+            Ok(()) => self.set_status(format!("Showing {shown} in the file manager")),
+            Err(err) => self.set_status(format!("Could not show {shown}: {err}")),
+        }
+    }
+
+    /// Edit the hovered file in `$EDITOR` once the terminal has been handed
+    /// over, see `run`
+    fn handle_edit(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+        let shown = self.dir_tree.display_path(&path);
+        if node.borrow().type_ == DirType::Dir {
+            self.set_status(format!("{shown} is a directory, press o to open it"));
+            return;
+        }
+        match launch::editor() {
+            Some(editor) => self.edit_request = Some((path, editor)),
+            None => self.set_status("Set $EDITOR to edit files"),
+        }
+    }
+
+    /// Note what editing `path` did, which may have changed its size
+    fn handle_edited(&mut self, path: &str, result: io::Result<()>) {
+        if let Some(node) = self.dir_tree.find_node(path) {
+            let mut node = node.borrow_mut();
+            node.forget_size();
+            node.modified.take();
+            node.invalidate_size();
+        }
+        self.details = None;
+        let shown = self.dir_tree.display_path(path);
+        if let Err(err) = result {
+            self.set_status(format!("Could not edit {shown}: {err}"));
+        }
+    }
+
+    fn handle_deselect_all(&mut self) {
+        if self.selected.len() > self.clear_confirm_threshold {
+            self.confirm = Some(Confirm::ClearSelection(self.selected.len()));
+        } else {
+            self.selected.clear();
+        }
+    }
+
+    /// Ask before a removal, or go straight ahead with `--force`. One that
+    /// takes more than `type_above_entries` or `type_above_size` has to be
+    /// confirmed by typing.
+    fn confirm_removal(&mut self, confirm: Confirm) {
+        if self.force {
+            self.handle_confirmed(confirm);
+            return;
+        }
+        let large = confirm.totals().is_some_and(|totals| {
+            totals.files + totals.dirs > self.type_above_entries
+                || totals.bytes > self.type_above_size
+        });
+        self.confirm = Some(if large {
+            Confirm::Typed(Box::new(confirm), String::new())
+        } else {
+            confirm
+        });
+    }
+
+    /// A key while a removal waits to be confirmed by typing: Enter goes
+    /// ahead once the answer is right, Esc cancels
+    fn handle_typed_confirm_key(
+        &mut self,
+        key: KeyEvent,
+        removal: Box<Confirm>,
+        mut typed: String,
+    ) {
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter if removal.accepts(&typed) => {
+                self.handle_confirmed(*removal);
+                return;
+            }
+            KeyCode::Enter => {
+                self.set_status(format!("'{typed}' doesn't confirm the removal"));
+                typed.clear();
+            }
+            KeyCode::Backspace => {
+                typed.pop();
+            }
+            KeyCode::Char(c) => typed.push(c),
+            _ => {}
+        }
+        self.confirm = Some(Confirm::Typed(removal, typed));
+    }
+
+    fn handle_confirmed(&mut self, confirm: Confirm) {
+        match confirm {
+            Confirm::ClearSelection(_) => self.selected.clear(),
+            Confirm::FollowLink(node, _) => self.toggle_dir(node),
+            Confirm::Typed(removal, _) => self.handle_confirmed(*removal),
+            Confirm::Purge(paths, everything) => self.handle_purge(paths, everything),
+            Confirm::Move(paths, dest, _) => self.move_selection(paths, &dest, true),
+            Confirm::RemoveHovered(path, ..) => {
+                let before = (self.summary.bytes, self.summary.failures.len());
+                self.remove_path(&path, true);
+                let status = self.freed_since(before);
+                self.set_status(status);
+            }
+            Confirm::RemoveSelected(paths, ..) => {
+                let before = (self.summary.bytes, self.summary.failures.len());
+                self.remove_paths(paths);
+                let status = self.freed_since(before);
+                self.set_status(status);
+            }
+            Confirm::EmptyHovered(path, ..) => self.empty_dir(&path),
+            Confirm::SelectDescendants(_, nodes) => self.select_descendants(nodes),
+        }
+        self.refresh_disk_space();
+    }
+
+    /// Scan the hovered directory completely and select everything in it
+    /// explicitly, rather than relying on the implied selection of children
+    fn handle_select_descendants(&mut self) {
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let node = self.dir_tree.find_node(&path).unwrap();
+        if node.borrow().type_ != DirType::Dir {
+            self.set_status(format!("{path} is not a directory"));
+            return;
+        }
+
+        if let Some(limit) = self.dir_tree.open_limit(&node.borrow()) {
+            Node::scan_all(node.clone(), 0, limit);
+        }
+        let mut nodes = Vec::new();
+        node.borrow().descendants(&mut nodes);
+
+        if nodes.len() > LARGE_SUBTREE {
+            self.confirm = Some(Confirm::SelectDescendants(path, nodes));
+        } else {
+            self.select_descendants(nodes);
+        }
+    }
+
+    fn select_descendants(&mut self, nodes: Vec<NodeRef>) {
+        let mut skipped = 0;
+        for node in nodes {
+            self.select_in_bulk(node, &mut skipped);
+        }
+        self.report_skipped(skipped);
+    }
+
+    /// Select the files git reports as added after `reference` or untracked,
+    /// opening the directories they're in so they can be reviewed
+    fn handle_select_since(&mut self, reference: &str) -> io::Result<()> {
+        let root = self.dir_tree.base_node.borrow().path();
+        let real_root = fs::canonicalize(&root)?;
+
+        let (mut count, mut skipped) = (0, 0);
+        for path in git::added_since(&root, reference)? {
+            let Ok(relative) = path.strip_prefix(&real_root) else {
+                continue;
+            };
+            let path = root.join(relative).to_string_lossy().into_owned();
+            let Some(node) = self.dir_tree.load_path(&path) else {
+                continue;
+            };
+            // it couldn't be shown, its directory can't be opened
+            let parent = node.borrow().parent.borrow().upgrade();
+            if parent.is_some_and(|dir| !self.dir_tree.can_open(&dir.borrow())) {
+                continue;
+            }
+
+            let mut parent = node.borrow().parent.borrow().upgrade();
+            while let Some(dir) = parent {
+                dir.borrow_mut().is_open = true;
+                parent = dir.borrow().parent.borrow().upgrade();
+            }
+            if self.select_in_bulk(node, &mut skipped) {
+                count += 1;
+            }
+        }
+
+        self.set_status(format!(
+            "Selected {} files added or untracked since {reference}{}",
+            format::count(count),
+            skipped_note(skipped)
+        ));
+        Ok(())
+    }
+
+    fn handle_select_below_depth(&mut self, depth: Depth) {
+        let mut skipped = 0;
+        for node in self.dir_tree.nodes_below_depth(depth) {
+            self.select_in_bulk(node, &mut skipped);
+        }
+        self.report_skipped(skipped);
+    }
+
+    /// Select every entry of `type_` the view shows, leaving out the root
+    /// and whatever the filters hide. A symlinked directory counts as a
+    /// link rather than a directory, since removing it only takes the link.
+    fn handle_select_type(&mut self, type_: DirType) {
+        let arr = self.dir_tree.to_array(self.view());
+        let root = self.dir_tree.base_node.borrow().full_path();
+        let (mut matched, mut skipped) = (0, 0);
+        for path in arr.iter().filter(|path| **path != root) {
+            let Some(node) = self.dir_tree.find_node(path) else {
+                continue;
+            };
+            let is_type = match node.borrow().type_ {
+                DirType::Dir if node.borrow().is_symlink() => type_ == DirType::Symlink,
+                other => other == type_,
+            };
+            if is_type {
+                matched += 1;
+                self.select_in_bulk(node, &mut skipped);
+            }
+        }
+        let kind = match type_ {
+            DirType::File => "files",
+            DirType::Dir => "directories",
+            DirType::Symlink => "links",
+        };
+        self.set_status(match matched {
+            0 => format!("No {kind} shown"),
+            n => format!(
+                "Selected {} {kind}{}",
+                format::count(n - skipped as u64),
+                skipped_note(skipped)
+            ),
+        });
+    }
+
+    fn handle_toggle_dir(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+
+        if self.confirm_outside_links && !node.borrow().is_open {
+            if let Some(target) = self.outside_link_target(&node) {
+                self.confirm = Some(Confirm::FollowLink(node, target));
+                return;
+            }
+        }
+        self.toggle_dir(node);
+    }
+
+    /// Close the hovered directory, or hover the directory it's in when
+    /// there's nothing to close. Does nothing on the root.
+    fn handle_go_to_parent(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+        let Some(parent) = node.borrow().parent.borrow().upgrade() else {
+            return;
+        };
+        if node.borrow().is_open {
+            self.toggle_dir(node);
+            return;
+        }
+        let path = parent.borrow().full_path();
+        self.rehover(Some(path));
+    }
+
+    /// Open the hovered directory if it's closed
+    fn handle_expand(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+        let closed_dir = {
+            let node = node.borrow();
+            node.type_ == DirType::Dir && !node.is_open
+        };
+        if closed_dir {
+            self.handle_toggle_dir();
+        }
+    }
+
+    fn toggle_dir(&mut self, node: NodeRef) {
+        let opening = !node.borrow().is_open;
+        if opening && !self.dir_tree.can_open(&node.borrow()) {
+            self.refuse_beyond_max_depth(&node);
+            return;
+        }
+        Node::toggle_open(node.clone());
+
+        let needs_scan = {
+            let node = node.borrow();
+            node.type_ == DirType::Dir && node.children.borrow().is_empty() && !node.has_more()
+        };
+        if needs_scan {
+            self.start_scan(&node);
+        }
+    }
+
+    fn refuse_beyond_max_depth(&mut self, node: &NodeRef) {
+        if node.borrow().type_ == DirType::Dir {
+            self.set_status(format!(
+                "{} is as deep as --max-depth allows",
+                node.borrow().full_path()
+            ));
+        }
+    }
+
+    /// Where `node` really leads, if it's a symlinked directory whose target
+    /// lies outside the tree's root
+    fn outside_link_target(&self, node: &NodeRef) -> Option<PathBuf> {
+        let node = node.borrow();
+        if node.type_ != DirType::Dir || !node.is_symlink() {
+            return None;
+        }
+        let target = fs::canonicalize(node.path()).ok()?;
+        let root = fs::canonicalize(self.dir_tree.base_node.borrow().path()).ok()?;
+        (!target.starts_with(root)).then_some(target)
+    }
+
+    /// Start opening every directory below the hovered one. It goes on a
+    /// few directories at a time in `expand_step`, so it can be watched
+    /// and stopped with <Esc>.
+    fn handle_expand_all(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+        if node.borrow().type_ != DirType::Dir {
+            return;
+        }
+        if !self.dir_tree.can_open(&node.borrow()) {
+            self.refuse_beyond_max_depth(&node);
+            return;
+        }
+        self.expanding = VecDeque::from([(node, 0)]);
+        self.expanded = 0;
+        self.expand_visited.clear();
+    }
+
+    /// Open the next directories of the expand-all in progress, for up to
+    /// `EXPAND_BUDGET`. Symlinked directories are left closed unless
+    /// `follow_symlinks` is set, and then none is opened twice, so a link
+    /// cycle can't expand forever.
+    fn expand_step(&mut self) {
+        if self.expanding.is_empty() {
+            return;
+        }
+        let hovered = self.hovered_path();
+        let started = Instant::now();
+
+        while let Some((node, depth)) = self.expanding.pop_front() {
+            let descend = if self.follow_symlinks {
+                first_visit(&node.borrow().path(), &mut self.expand_visited)
+            } else {
+                depth == 0 || !node.borrow().is_symlink()
+            };
+            if !descend {
+                continue;
+            }
+            let loaded = if node.borrow().children.borrow().is_empty() {
+                Node::scan_dir(node.clone())
+            } else {
+                Node::finish_scan(node.clone())
+            };
+            if let Err(err) = loaded {
+                self.set_status(format!(
+                    "Could not read {}: {err}",
+                    node.borrow().full_path()
+                ));
+                continue;
+            }
+            node.borrow_mut().is_open = true;
+            self.expanded += 1;
+            self.note_repo(&node.borrow().path());
+
+            if depth < MAX_SUBTREE_DEPTH {
+                let children = node.borrow().children.borrow().clone();
+                self.expanding.extend(
+                    children
+                        .into_iter()
+                        .filter(|child| {
+                            let child = child.borrow();
+                            child.type_ == DirType::Dir && self.dir_tree.can_open(&child)
+                        })
+                        .map(|child| (child, depth + 1)),
+                );
+            }
+            if started.elapsed() >= EXPAND_BUDGET {
+                break;
+            }
+        }
+        self.rehover(hovered);
+    }
+
+    /// Close the hovered directory and everything below it, keeping what
+    /// was read so opening them again doesn't touch the disk
+    fn handle_collapse_all(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+        self.expanding.clear();
+        Node::set_open_recursive(node, false, 0, MAX_SUBTREE_DEPTH, None);
+    }
+
+    fn handle_toggle_subtree(&mut self) {
+        let Some(node) = self
+            .hovered_path()
+            .and_then(|path| self.dir_tree.find_node(&path))
+        else {
+            return;
+        };
+
+        let open = !node.borrow().is_open;
+        let limit = if open {
+            self.dir_tree.open_limit(&node.borrow())
+        } else {
+            Some(MAX_SUBTREE_DEPTH)
+        };
+        match limit {
+            Some(limit) => {
+                let mut visited = HashSet::new();
+                let visited = self.follow_symlinks.then_some(&mut visited);
+                Node::set_open_recursive(node, open, 0, limit, visited);
+            }
+            None => self.refuse_beyond_max_depth(&node),
+        }
+    }
+
+    /// Move down a row, staying on the last one rather than wrapping
+    fn handle_hover_down(&mut self) {
+        let last = self.dir_tree.to_array(self.view()).len().saturating_sub(1);
+        let i = self.hovered.selected().map_or(0, |i| (i + 1).min(last));
+        self.hovered.select(Some(i));
+    }
+
+    /// Move up a row, staying on the first one rather than wrapping
+    fn handle_hover_up(&mut self) {
+        let i = self.hovered.selected().map_or(0, |i| i.saturating_sub(1));
+        self.hovered.select(Some(i));
+    }
+
+    /// Hover the next (or previous) selected row, wrapping around at the
+    /// ends
+    fn handle_jump_to_selected(&mut self, forward: bool) {
+        if self.selected.is_empty() {
+            self.set_status("Nothing is selected".to_string());
+            return;
+        }
+        let rows = self.dir_tree.to_enriched_array(&self.selected, self.view());
+        let current = self.hovered.selected().unwrap_or(0);
+        let len = rows.len();
+        let next = (1..=len)
+            .map(|step| {
+                if forward {
+                    (current + step) % len
+                } else {
+                    (current + len - step % len) % len
+                }
+            })
+            .find(|&i| rows[i].is_selected && !rows[i].is_placeholder);
+        match next {
+            Some(i) => self.hovered.select(Some(i)),
+            None => self.set_status("None of the selected entries is shown".to_string()),
+        }
+    }
+
+    fn handle_hover_last(&mut self) {
+        let len = self.dir_tree.to_array(self.view()).len();
+        self.hovered.select(Some(len.saturating_sub(1)));
+    }
+
+    /// Hover row `input`, counted from 1 like the `row X/Y` in the corner.
+    /// A number past either end goes to the first or last row.
+    fn handle_go_to_row(&mut self, input: &str) {
+        if input.is_empty() {
+            return;
+        }
+        let len = self.dir_tree.to_array(self.view()).len();
+        // only digits can be typed, so a number too big to parse is still
+        // past the end
+        let row = input.parse::<usize>().unwrap_or(usize::MAX);
+        let clamped = row.clamp(1, len.max(1));
+        if clamped != row {
+            self.set_status(format!(
+                "There's no row {}, went to row {}",
+                format::count(row as u64),
+                format::count(clamped as u64)
+            ));
+        }
+        self.hovered.select(Some(clamped - 1));
+        self.extend_range();
+    }
+
+    /// Move a screenful down, stopping at the last row
+    fn handle_page_down(&mut self) {
+        let last = self.dir_tree.to_array(self.view()).len().saturating_sub(1);
+        let i = self.hovered.selected().unwrap_or(0) + self.list_height.max(1);
+        self.hovered.select(Some(i.min(last)));
+    }
+
+    /// Move a screenful up, stopping at the first row
+    fn handle_page_up(&mut self) {
+        let i = self
+            .hovered
+            .selected()
+            .unwrap_or(0)
+            .saturating_sub(self.list_height.max(1));
+        self.hovered.select(Some(i));
+    }
+
+    fn handle_clear_hovered(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        let Some(node_path) = self.hovered_path() else {
+            return;
+        };
+        let Some(node) = self.dir_tree.find_node(&node_path) else {
+            return;
+        };
+        if Rc::ptr_eq(&node, &self.dir_tree.base_node) {
+            self.set_status("Refusing to remove the root directory".to_string());
+            return;
+        }
+        if let Some(reason) = self.dir_tree.protection_refusal(&node, true) {
+            self.set_status(format!("Refusing to remove {node_path}: {reason}"));
+            return;
+        }
+
+        let mut totals = DeletionSummary::default();
+        let counted_all =
+            totals.count_until(&node.borrow().path(), Instant::now() + MEASURE_BUDGET);
+        self.confirm_removal(Confirm::RemoveHovered(node_path, totals, counted_all));
+    }
+
+    fn handle_clear_all(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        if self.selected.is_empty() {
+            self.set_status("Nothing is selected".to_string());
+            return;
+        }
+
+        for node in self.selected_nodes() {
+            let refusal = self
+                .dir_tree
+                .protection_refusal(&node, self.select_implies_children);
+            if let Some(reason) = refusal {
+                let path = node.borrow().full_path();
+                self.set_status(format!(
+                    "Refusing to remove the selection, {path}: {reason}"
+                ));
+                return;
+            }
+        }
+
+        let paths = self.selected.clone();
+        let mut totals = DeletionSummary::default();
+        let deadline = Instant::now() + MEASURE_BUDGET;
+        let mut counted_all = true;
+        for path in &paths {
+            // counted along with a selected ancestor already
+            let under_other = self.select_implies_children
+                && paths
+                    .iter()
+                    .any(|other| other != path && Path::new(path).starts_with(other));
+            if !under_other && !totals.count_until(&self.dir_tree.disk_path(path), deadline) {
+                counted_all = false;
+                break;
+            }
+        }
+        self.confirm_removal(Confirm::RemoveSelected(paths, totals, counted_all));
+    }
+
+    /// Ask before removing everything in the hovered directory
+    fn handle_empty_hovered(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        let Some(path) = self.hovered_path() else {
+            return;
+        };
+        let Some(node) = self.dir_tree.find_node(&path) else {
+            return;
+        };
+        let node = node.borrow();
+        if node.type_ != DirType::Dir {
+            self.set_status(format!("{path} is not a directory"));
+            return;
+        }
+        if node.is_symlink() {
+            // its entries belong to wherever it leads
+            self.set_status(format!("Refusing to empty {path}, it's a symlink"));
+            return;
+        }
+
+        let mut totals = DeletionSummary::default();
+        let deadline = Instant::now() + MEASURE_BUDGET;
+        let mut counted_all = true;
+        for entry in fs::read_dir(node.path()).into_iter().flatten().flatten() {
+            if !totals.count_until(&entry.path(), deadline) {
+                counted_all = false;
+                break;
+            }
+        }
+        drop(node);
+        if counted_all && totals.files == 0 && totals.dirs == 0 {
+            self.set_status(format!("{path} is empty already"));
+            return;
+        }
+        self.confirm_removal(Confirm::EmptyHovered(path, totals, counted_all));
+    }
+
+    /// Remove every entry in the directory at `path`, leaving the directory
+    /// itself in place
+    fn empty_dir(&mut self, path: &str) {
+        let Some(node) = self.dir_tree.find_node(path) else {
+            return;
+        };
+        // whatever isn't loaded yet has to be in the tree to be removed
+        let loaded = if node.borrow().children.borrow().is_empty() {
+            Node::scan_dir(node.clone())
+        } else {
+            Node::rescan(node.clone())
+        };
+        if let Err(err) = loaded {
+            self.set_status(format!("Could not read {path}: {err}"));
+            return;
+        }
+
+        let children: Vec<String> = node
+            .borrow()
+            .children
+            .borrow()
+            .iter()
+            .map(|child| child.borrow().full_path())
+            .collect();
+        let before = (self.summary.bytes, self.summary.failures.len());
+        for child in &children {
+            self.remove_path(child, true);
+        }
+        self.prune_selection();
+
+        let left = node.borrow().children.borrow().len();
+        let removed = match children.len() - left {
+            1 => "1 entry".to_string(),
+            n => format!("{} entries", format::count(n as u64)),
+        };
+        let status = format!(
+            "Removed {removed} from {path}. {}",
+            self.freed_since(before)
+        );
+        self.set_status(status);
+    }
+
+    /// Drop the oldest of `trashed` while there are more of them, or more
+    /// bytes, than `[restore]` allows. The newest is always kept. What's
+    /// dropped stays in the trash, but `u` can't put it back any more.
+    fn forget_old_trash(&mut self) {
+        let mut bytes: u64 = self
+            .trashed
+            .iter()
+            .map(|item| item.removed.trashed_bytes)
+            .sum();
+        while self.trashed.len() > 1
+            && (self.trashed.len() > self.restore_max_items || bytes > self.restore_max_size)
+        {
+            let item = self.trashed.remove(0);
+            bytes = bytes.saturating_sub(item.removed.trashed_bytes);
+            self.unrestorable.push(item.path);
+        }
+    }
+
+    /// How much space the removals since `before` (the session's bytes and
+    /// failures then) freed, for the status line, along with what can no
+    /// longer be restored since
+    fn freed_since(&mut self, before: (u64, usize)) -> String {
+        let freed = format::size(self.summary.bytes.saturating_sub(before.0));
+        let done = if self.dir_tree.dry_run {
+            format!("Would free {freed}")
+        } else if self.dir_tree.delete_mode == DeleteMode::Trash {
+            format!("Moved {freed} to the trash")
+        } else {
+            format!("Freed {freed}")
+        };
+        let status = match self.summary.failures.len() - before.1 {
+            0 => done,
+            1 => format!(
+                "{done}, failed to remove {}",
+                self.summary.failures[before.1]
+            ),
+            n => format!(
+                "{done}, {} entries could not be removed",
+                format::count(n as u64)
+            ),
+        };
+        match mem::take(&mut self.unrestorable).as_slice() {
+            [] => status,
+            [path] => format!(
+                "{status}. {} can no longer be restored, being past the [restore] limits",
+                self.dir_tree.display_path(path)
+            ),
+            paths => format!(
+                "{status}. The {} oldest entries trashed can no longer be restored, being past \
+                 the [restore] limits",
+                format::count(paths.len() as u64)
+            ),
+        }
+    }
+
+    /// Remove every selected entry, keeping whatever couldn't be removed
+    /// selected
+    fn remove_selected(&mut self) {
+        self.remove_paths(self.selected.clone());
+    }
+
+    /// Remove each of `paths` the way selected entries are, then drop what's
+    /// gone from the selection
+    fn remove_paths(&mut self, paths: Vec<String>) {
+        for path in paths {
+            // already gone along with a selected ancestor
+            if self.dir_tree.find_node(&path).is_none() {
+                continue;
+            }
+            self.remove_path(&path, self.select_implies_children);
+        }
+        self.prune_selection();
+    }
+
+    /// Ask to remove every empty directory in the loaded tree, the way a
+    /// selection of them would be. The selection itself is left as it was.
+    fn handle_remove_empty_dirs(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        let dirs = self.dir_tree.collect_empty_dirs();
+        if dirs.is_empty() {
+            self.set_status("No empty directories in the tree".to_string());
+            return;
+        }
+        // the question takes the paths along, so declining loses nothing
+        let selected = mem::take(&mut self.selected);
+        for dir in dirs {
+            self.select_node(dir);
+        }
+        self.handle_clear_all();
+        self.selected = selected;
+        self.prune_selection();
+    }
+
+    /// Mark the loaded files that have the same contents as another, or
+    /// clear the marks when they're shown
+    fn handle_find_duplicates(&mut self) {
+        if !self.duplicates.is_empty() {
+            self.duplicates = Duplicates::default();
+            self.set_status("Cleared the duplicate marks".to_string());
+            return;
+        }
+        let mut nodes = Vec::new();
+        self.dir_tree.base_node.borrow().descendants(&mut nodes);
+        let files = nodes
+            .iter()
+            .filter(|node| node.borrow().type_ == DirType::File)
+            .map(|node| {
+                let node = node.borrow();
+                (node.path(), node.size())
+            })
+            .collect();
+
+        let (duplicates, finished) = Duplicates::find(files, Instant::now() + DUPLICATE_BUDGET);
+        self.duplicates = duplicates;
+        let found = match self.duplicates.len() {
+            0 => "No duplicate files among what's been read".to_string(),
+            n => format!(
+                "{} sets of duplicate files, press - to select all but one of each",
+                format::count(n as u64)
+            ),
+        };
+        self.set_status(if finished {
+            found
+        } else {
+            format!(
+                "{found} (stopped reading after {}s)",
+                DUPLICATE_BUDGET.as_secs()
+            )
+        });
+    }
+
+    /// Select every marked duplicate but the first of each set
+    fn handle_select_duplicates(&mut self) {
+        if self.duplicates.is_empty() {
+            self.set_status("No duplicates are marked, press = to look for them".to_string());
+            return;
+        }
+        let extras: Vec<String> = self
+            .duplicates
+            .extras()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect();
+        let mut count = 0;
+        for path in extras {
+            if let Some(node) = self.dir_tree.find_node(&path) {
+                count += u64::from(self.select_node(node));
+            }
+        }
+        self.set_status(format!(
+            "Selected {} duplicates, keeping one of each",
+            format::count(count)
+        ));
+    }
+
+    /// Remove every zero-byte regular file among the selected entries (or
+    /// their loaded contents), or anywhere in the loaded tree when nothing is
+    /// selected. Directories and symlinks are never touched.
+    fn handle_remove_empty_files(&mut self) {
+        if self.refuses_changes() {
+            return;
+        }
+        let mut candidates = Vec::new();
+        let scope = if self.selected.is_empty() {
+            "the tree"
+        } else {
+            "the selection"
+        };
+        if self.selected.is_empty() {
+            self.dir_tree
+                .base_node
+                .borrow()
+                .descendants(&mut candidates);
+        } else {
+            for node in self.selected_nodes() {
+                candidates.push(node.clone());
+                if self.select_implies_children {
+                    node.borrow().descendants(&mut candidates);
+                }
+            }
+        }
+
+        let mut paths: Vec<String> = candidates
+            .iter()
+            .filter(|node| {
+                let node = node.borrow();
+                node.type_ == DirType::File && node.size() == 0
+            })
+            .map(|node| node.borrow().full_path())
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        let failures = self.summary.failures.len();
+        for path in &paths {
+            self.remove_path(path, false);
+        }
+        self.prune_selection();
+
+        // a failed removal has already put its error in the status line
+        if self.summary.failures.len() == failures {
+            self.set_status(match paths.len() {
+                0 => format!("No empty files in {scope}"),
+                n => format!("Removed {} empty files", format::count(n as u64)),
+            });
+        }
+    }
+
+    /// Ask where to move the selection, see `handle_move_selected`
+    fn handle_start_move(&mut self) {
+        if self.selected.is_empty() {
+            self.set_status("Nothing is selected".to_string());
+            return;
+        }
+        if self.refuses_changes() {
+            return;
+        }
+        if self.dir_tree.dry_run {
+            self.set_status("Nothing is moved in a dry run".to_string());
+            return;
+        }
+        let mut prompt = Prompt::new(PromptKind::Move);
+        prompt.input = self.dir_tree.base_node.borrow().full_path();
+        self.prompt = Some(prompt);
+    }
+
+    /// Move the selection into the directory at `input`, found relative to
+    /// the root, asking first if any of it would take the place of
+    /// something already there
+    fn handle_move_selected(&mut self, input: &str) {
+        let input = input.trim();
+        if input.is_empty() {
+            return;
+        }
+        let dest = normalize(&self.dir_tree.base_node.borrow().path().join(input));
+        if !dest.is_dir() {
+            self.set_status(format!("{} is not a directory", dest.display()));
+            return;
+        }
+
+        for node in self.selected_nodes() {
+            let node_ref = node.borrow();
+            let refusal = if node_ref.parent.borrow().upgrade().is_none() {
+                Some("it's the root directory".to_string())
+            } else {
+                self.dir_tree.protection_refusal(&node, true)
+            };
+            if let Some(reason) = refusal {
+                let path = node_ref.full_path();
+                self.set_status(format!("Refusing to move the selection, {path}: {reason}"));
+                return;
+            }
+        }
+
+        // whatever is in a selected directory goes along with it
+        let paths: Vec<String> = self
+            .selected
+            .iter()
+            .filter(|path| {
+                !self
+                    .selected
+                    .iter()
+                    .any(|other| other != *path && Path::new(path).starts_with(other))
+            })
+            .cloned()
+            .collect();
+        let mut names = HashSet::new();
+        let collisions = paths
+            .iter()
+            .filter(|path| {
+                let Some(node) = self.dir_tree.find_node(path) else {
+                    return false;
+                };
+                let node = node.borrow();
+                let already_there = fs::canonicalize(node.path().parent().unwrap())
+                    .ok()
+                    .zip(fs::canonicalize(&dest).ok())
+                    .is_some_and(|(parent, dest)| parent == dest);
+                // two selected entries with the same name land on each other
+                !already_there
+                    && (!names.insert(node.os_name.clone())
+                        || fs::symlink_metadata(dest.join(&node.os_name)).is_ok())
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if collisions.is_empty() {
+            self.move_selection(paths, &dest, false);
+        } else {
+            self.confirm = Some(Confirm::Move(paths, dest, collisions));
+        }
+    }
+
+    /// Move each of `paths` into `dest`, replacing what's in the way with
+    /// `overwrite` or skipping it without, and take them out of the tree.
+    /// The status line says how each went.
+    fn move_selection(&mut self, paths: Vec<String>, dest: &Path, overwrite: bool) {
+        let dest = match fs::canonicalize(dest) {
+            Ok(dest) => dest,
+            Err(err) => {
+                self.set_status(format!("Could not move to {}: {err}", dest.display()));
+                return;
+            }
+        };
+        let hovered = self.hovered_path();
+        let (mut moved, mut skipped, mut failed) = (0, Vec::new(), Vec::new());
+        for path in &paths {
+            let Some(node) = self.dir_tree.find_node(path) else {
+                continue;
+            };
+            let source = node.borrow().path();
+            let shown = self.dir_tree.display_path(path);
+            match self.move_entry(&source, &dest, overwrite) {
+                Ok(true) => {
+                    self.dir_tree.detach(path);
+                    self.duplicates.forget(&source);
+                    moved += 1;
+                }
+                Ok(false) => skipped.push(shown),
+                Err(err) => failed.push(format!("{shown}: {err}")),
+            }
+        }
+        self.prune_selection();
+
+        // the moved entries show up where they went, if that's loaded
+        let base = self.dir_tree.base_node.borrow().path();
+        let within = fs::canonicalize(&base)
+            .ok()
+            .and_then(|real| {
+                dest.strip_prefix(real)
+                    .ok()
+                    .map(|relative| base.join(relative))
+            })
+            .and_then(|path| self.dir_tree.find_node(&path.to_string_lossy()));
+        if let Some(node) = within {
+            if node.borrow().listed.get() {
+                let _ = Node::rescan(node.clone());
+            }
+            node.borrow_mut().item_count.take();
+            node.borrow().forget_size();
+            node.borrow().invalidate_size();
+        }
+        self.refresh_disk_space();
+        self.rehover(hovered);
+
+        let items = match moved {
+            0 => "nothing".to_string(),
+            1 => "1 item".to_string(),
+            n => format!("{} items", format::count(n as u64)),
+        };
+        let mut status = format!("Moved {items} to {}", dest.display());
+        if !skipped.is_empty() {
+            status.push_str(&format!(", skipped {}", skipped.join(", ")));
+        }
+        if !failed.is_empty() {
+            status.push_str(&format!(", could not move {}", failed.join(", ")));
+        }
+        self.set_status(status);
+    }
+
+    /// Move `source` into the directory `dest`, given as its real path.
+    /// Returns whether it went: it's skipped if it's there already, or
+    /// without `overwrite` if something of the same name is.
+    fn move_entry(&mut self, source: &Path, dest: &Path, overwrite: bool) -> io::Result<bool> {
+        let name = source.file_name().unwrap();
+        // a symlink is moved, not what it leads to
+        let real = fs::canonicalize(source.parent().unwrap())?.join(name);
+        if real.parent() == Some(dest) {
+            return Ok(false);
+        }
+        let refuse = |reason: String| Err(io::Error::new(io::ErrorKind::PermissionDenied, reason));
+        if dest.starts_with(&real) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "it can't be moved into itself",
+            ));
+        }
+        if let Some(safe) = self.dir_tree.safe_list.protecting(&real) {
+            return refuse(format!("{} is on the safe list", safe.display()));
+        }
+        if let Some(safe) = self.dir_tree.safe_list.inside(&real) {
+            return refuse(format!(
+                "it contains {}, which is on the safe list",
+                safe.display()
+            ));
+        }
+
+        let target = dest.join(name);
+        if let Ok(meta) = fs::symlink_metadata(&target) {
+            if !overwrite {
+                return Ok(false);
+            }
+            if real.starts_with(&target) {
+                return refuse(format!(
+                    "it's inside {}, which it would replace",
+                    target.display()
+                ));
+            }
+            if let Some(safe) = self.dir_tree.safe_list.protecting(&target) {
+                return refuse(format!("{} is on the safe list", safe.display()));
+            }
+            if let Some(safe) = self.dir_tree.safe_list.inside(&target) {
+                return refuse(format!(
+                    "{} contains {}, which is on the safe list",
+                    target.display(),
+                    safe.display()
+                ));
+            }
+            let replaced = if meta.is_symlink() {
+                symlink::remove(&target)
+            } else if meta.is_dir() {
+                disk::remove_tree(&target, &mut |_| {})
+            } else {
+                fs::remove_file(&target)
+            };
+            let shown = target.to_string_lossy();
+            match &replaced {
+                Ok(()) => self.log(audit::Event::Removed, &shown),
+                Err(err) => self.log(audit::Event::RemoveFailed(&err.to_string()), &shown),
+            }
+            replaced?;
+        }
+        disk::move_to(source, &target)?;
+        Ok(true)
+    }
+
+    /// Add `event` to the audit log, saying so when that fails
+    fn log(&mut self, event: audit::Event, path: &str) {
+        if let Err(err) = self.audit.record(event, path) {
+            self.set_status(format!("Could not write to the log: {err}"));
+        }
+    }
+
+    /// Remove `path` from disk and the tree, recording the outcome in the
+    /// session summary and the audit log
+    fn remove_path(&mut self, path: &str, recursive: bool) {
+        let disk_path = self.dir_tree.disk_path(path);
+        let mut removed = DeletionSummary::measure(&disk_path);
+        if self.dir_tree.delete_mode == DeleteMode::Trash {
+            removed.trashed_bytes = removed.bytes;
+        }
+        let hovered = self.hovered_path();
+
+        let mut progress = self.shows_progress.then(|| {
+            let name = self.dir_tree.display_path(path);
+            progress::Progress::new(name, (removed.files + removed.dirs) as u64)
+        });
+        let result = self.dir_tree.remove_node(path, recursive, &mut |count| {
+            if let Some(progress) = &mut progress {
+                progress.update(count);
+            }
+        });
+        self.redraw |= progress.is_some_and(|progress| progress.was_shown());
+
+        match result {
+            Ok(()) if self.dir_tree.dry_run => {
+                let shown = self.dir_tree.display_path(path);
+                self.set_status(format!("Would remove {shown}"));
+                self.log(audit::Event::WouldRemove, path);
+                removed.would_remove.push(path.to_string());
+                self.summary.add(removed);
+                self.removed.push(path.to_string());
+                self.duplicates.forget(&disk_path);
+            }
+            Ok(()) => {
+                if self.dir_tree.delete_mode == DeleteMode::Trash {
+                    self.log(audit::Event::Trashed, path);
+                    self.trashed.push(TrashedItem {
+                        path: path.to_string(),
+                        removed: removed.clone(),
+                    });
+                    self.forget_old_trash();
+                } else {
+                    self.log(audit::Event::Removed, path);
+                }
+                self.summary.add(removed);
+                self.removed.push(path.to_string());
+                self.duplicates.forget(&disk_path);
+            }
+            Err(err) => {
+                let failure = format!("{path}: {err}");
+                let shown = self.dir_tree.display_path(path);
+                self.set_status(format!("Failed to remove {shown}: {err}"));
+                self.log(audit::Event::RemoveFailed(&err.to_string()), path);
+                self.summary.failures.push(failure);
+            }
+        }
+        // rows below may have shifted up, or the hovered one may be gone
+        self.rehover(hovered);
+    }
+}
+
+/// The list opened with <Ctrl + U>
+#[derive(Debug)]
+struct TrashList {
+    /// The hovered row, counting from the most recently trashed item. The
+    /// row after the last item stands for everything else in the trash.
+    row: usize,
+    /// Which of `App::trashed` are picked, in the same order
+    picked: Vec<bool>,
+    /// Also delete whatever else is in the trash
+    everything: bool,
+}
+
+/// An entry this session moved to the trash
+#[derive(Debug)]
+struct TrashedItem {
+    path: String,
+    /// What removing it added to the session's totals
+    removed: DeletionSummary,
+}
+
+/// Running totals of what a session has deleted
+#[derive(Debug, Default, Clone)]
+struct DeletionSummary {
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+    /// How much of `bytes` went to the trash rather than being freed
+    trashed_bytes: u64,
+    failures: Vec<String>,
+    /// What `--dry-run` left in place, in the order it would have gone
+    would_remove: Vec<String>,
+}
+
+impl DeletionSummary {
+    /// Count everything at and below `path` without following symlinks
+    fn measure(path: &Path) -> Self {
+        let mut summary = Self::default();
+        summary.count(path);
+        summary
+    }
+
+    /// Like `count`, but giving up at `deadline`. Returns whether it got
+    /// through everything.
+    fn count_until(&mut self, path: &Path, deadline: Instant) -> bool {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return true;
+        };
+
+        if !meta.is_dir() {
+            self.files += 1;
+            self.bytes += meta.len();
+            return true;
+        }
+
+        self.dirs += 1;
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                if Instant::now() >= deadline || !self.count_until(&entry.path(), deadline) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn count(&mut self, path: &Path) {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return;
+        };
+
+        if !meta.is_dir() {
+            self.files += 1;
+            self.bytes += meta.len();
+            return;
+        }
+
+        self.dirs += 1;
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                self.count(&entry.path());
+            }
+        }
+    }
+
+    fn add(&mut self, other: Self) {
+        self.files += other.files;
+        self.dirs += other.dirs;
+        self.bytes += other.bytes;
+        self.trashed_bytes += other.trashed_bytes;
+        self.failures.extend(other.failures);
+        self.would_remove.extend(other.would_remove);
+    }
+
+    /// Take back what `add` counted for an entry that has been restored
+    fn subtract(&mut self, other: &Self) {
+        // measured at different times, so they needn't add up
+        self.files = self.files.saturating_sub(other.files);
+        self.dirs = self.dirs.saturating_sub(other.dirs);
+        self.bytes = self.bytes.saturating_sub(other.bytes);
+        self.trashed_bytes = self.trashed_bytes.saturating_sub(other.trashed_bytes);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.files == 0 && self.dirs == 0 && self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for DeletionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let count = |n: usize, one: &str, many: &str| {
+            let word = if n == 1 { one } else { many };
+            format!("{} {word}", format::count(n as u64))
+        };
+        let dry_run = !self.would_remove.is_empty();
+        let (removed, freed_verb, moved_verb) = if dry_run {
+            (
+                "Dry run, nothing was touched. Would have removed",
+                "freeing",
+                "moving",
+            )
+        } else {
+            ("Removed", "freed", "moved")
+        };
+        let freed = self.bytes.saturating_sub(self.trashed_bytes);
+        let space = match (freed, self.trashed_bytes) {
+            (_, 0) => format!("{freed_verb} {}", format::size(freed)),
+            (0, trashed) => format!("{moved_verb} {} to the trash", format::size(trashed)),
+            (freed, trashed) => format!(
+                "{freed_verb} {} and {moved_verb} {} to the trash",
+                format::size(freed),
+                format::size(trashed)
+            ),
+        };
+        write!(
+            f,
+            "{removed} {} and {}, {space}",
+            count(self.files, "file", "files"),
+            count(self.dirs, "directory", "directories"),
+        )?;
+        for path in &self.would_remove {
+            write!(f, "\n  {path}")?;
+        }
+        for failure in &self.failures {
+            write!(f, "\nFailed to remove {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+impl App {
+    /// Each tab's number and root name, the shown one highlighted
+    fn tab_bar(&self) -> Title<'static> {
+        let name = |dir_tree: &DirTree| {
+            let path = dir_tree.base_node.borrow().path();
+            std::path::absolute(&path)
+                .ok()
+                .and_then(|path| {
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                })
+                .unwrap_or_else(|| path.to_string_lossy().into_owned())
+        };
+        let mut trees: Vec<&DirTree> = self.tabs.iter().map(|pane| &pane.dir_tree).collect();
+        trees.insert(self.active, &self.dir_tree);
+        let mut spans = vec![" ".into()];
+        for (i, dir_tree) in trees.into_iter().enumerate() {
+            let label = format!(" {} {} ", i + 1, name(dir_tree));
+            spans.push(if i == self.active {
+                label.reversed().bold()
+            } else {
+                label.dim()
+            });
+        }
+        spans.push(" ".into());
+        Title::from(Line::from(spans))
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let title = Title::from(" Interactive file remover ".bold());
+
+        let mut instructions = vec![
+            " Help: ".into(),
+            "<?>".blue().bold(),
+            " Move: ".into(),
+            format!(
+                "<{}/{}>",
+                self.keymap.first(KeyAction::Up),
+                self.keymap.first(KeyAction::Down)
+            )
+            .blue()
+            .bold(),
+            " Open/close dir: ".into(),
+            format!("<{}>", self.keymap.first(KeyAction::Open))
+                .blue()
+                .bold(),
+            " Select: ".into(),
+            format!("<{}>", self.keymap.first(KeyAction::Select))
+                .blue()
+                .bold(),
+        ];
+        // there's nothing to remove with in read-only mode
+        if !self.readonly {
+            instructions.extend([
+                " Remove: ".into(),
+                format!("<{}>", self.keymap.first(KeyAction::Remove))
+                    .red()
+                    .bold(),
+            ]);
+        }
+        instructions.extend([
+            " Quit: ".into(),
+            format!("<{}> ", self.keymap.first(KeyAction::Quit))
+                .blue()
+                .bold(),
+        ]);
+        let instructions = Title::from(Line::from(instructions));
+        let block = Block::bordered()
+            .title(title.alignment(Alignment::Center))
+            .title(
+                instructions
+                    .alignment(Alignment::Center)
+                    .position(Position::Bottom),
+            )
+            .border_set(self.render_style.border());
+        let selection_mode = if self.select_implies_children {
+            " Selecting: dirs + contents "
+        } else {
+            " Selecting: entries only "
+        };
+        let block = block.title(Title::from(selection_mode.dim()).alignment(Alignment::Right));
+        let block = if self.stats {
+            block.title(Title::from(format!(" {} ", self.stats_line()).dim()))
+        } else {
+            block
+        };
+        let block = if self.flat {
+            block.title(Title::from(" Flat ".dim()))
+        } else {
+            block
+        };
+        let block = if self.tabs.is_empty() {
+            block
+        } else {
+            block.title(self.tab_bar())
+        };
+        let block = if self.sort.mode != SortMode::Name || self.sort.reverse {
+            block.title(Title::from(format!(" Sort: {} ", self.sort.label()).dim()))
+        } else {
+            block
+        };
+        let block = match self.dir_tree.delete_mode {
+            DeleteMode::Permanent => block,
+            DeleteMode::Trash => block.title(Title::from(" Removing to trash ".green())),
+        };
+        let block = if self.expanding.is_empty() {
+            block
+        } else {
+            block.title(Title::from(
+                format!(
+                    " Expanding: {} opened, <Esc> to stop ",
+                    format::count(self.expanded as u64)
+                )
+                .yellow()
+                .bold(),
+            ))
+        };
+        let block = if self.scanning > 0 {
+            let elapsed = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            let frame = SPINNER
+                [(elapsed.as_millis() / SPINNER_INTERVAL.as_millis()) as usize % SPINNER.len()];
+            block.title(Title::from(format!(" {frame} Reading ").cyan()))
+        } else {
+            block
+        };
+        let block = if self.dir_tree.dry_run {
+            block.title(Title::from(" Dry run ".yellow().bold()))
+        } else {
+            block
+        };
+        let block = if self.readonly {
+            block.title(Title::from(" [READ-ONLY] ".green().bold()))
+        } else {
+            block
+        };
+        let block = if self.force && !self.readonly {
+            block.title(Title::from(" Removing without asking ".red().bold()))
+        } else {
+            block
+        };
+        let block = if self.range_anchor.is_some() {
+            block.title(Title::from(
+                " Range: move to extend, <V> to stop ".yellow().bold(),
+            ))
+        } else {
+            block
+        };
+        let block = if self.awaiting_depth {
+            block.title(
+                Title::from(" Select everything deeper than: <0-9> ".yellow().bold())
+                    .alignment(Alignment::Right),
+            )
+        } else {
+            block
+        };
+        let block = if self.awaiting_type {
+            block.title(
+                Title::from(" Select all: <f>iles <d>irs <l>inks ".yellow().bold())
+                    .alignment(Alignment::Right),
+            )
+        } else {
+            block
+        };
+        let block = match &self.root_link {
+            Some(link) => block.title(Title::from(format!(" {link} is a symlink ").dim())),
+            None => block,
+        };
+
+        let [area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.size());
+        let selection = self.selection_line();
+        let [status_area, selection_area] = Layout::horizontal([
+            Constraint::Min(0),
+            Constraint::Length(selection.width() as u16),
+        ])
+        .areas(status_area);
+        if let Some(status) = self.status_line() {
+            f.render_widget(Paragraph::new(status), status_area);
+        }
+        f.render_widget(Paragraph::new(selection), selection_area);
+        let inner = block.inner(area);
+        let (list_area, details_area) = if self.show_details {
+            let [list_area, details_area] =
+                Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .areas(inner);
+            (list_area, Some(details_area))
+        } else {
+            (inner, None)
+        };
+        self.list_height = list_area.height as usize;
+        self.list_area = list_area;
+        // after the highlight symbol
+        let row_width = (list_area.width as usize)
+            .saturating_sub(Line::from(self.render_style.highlight()).width());
+
+        let enriched = self.dir_tree.to_enriched_array(&self.selected, self.view());
+        if let Some(details_area) = details_area {
+            let hovered = self.hovered.selected().and_then(|i| enriched.get(i));
+            let size = hovered.and_then(|row| row.size);
+            let lines = match self.hovered_path() {
+                Some(path) => {
+                    // built again only once something else is hovered or
+                    // measured, since it reads the file
+                    let fresh = matches!(&self.details,
+                        Some((shown, shown_size, _)) if *shown == path && *shown_size == size);
+                    if !fresh {
+                        let disk_path = self.dir_tree.disk_path(&path);
+                        let lines = details::lines(&disk_path, size, self.show_real_path);
+                        self.details = Some((path, size, lines));
+                    }
+                    self.details
+                        .as_ref()
+                        .map_or(Vec::new(), |(_, _, lines)| lines.clone())
+                }
+                None => Vec::new(),
+            };
+            let pane = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+                Block::new()
+                    .borders(Borders::LEFT)
+                    .title(Title::from(" Details ".bold()).alignment(Alignment::Center))
+                    .padding(Padding::horizontal(1)),
+            );
+            f.render_widget(pane, details_area);
+        }
+        let items = enriched.iter().map(
+            |Row {
+                 name,
+                 type_,
+                 depth,
+                 is_last,
+                 is_selected,
+                 accessed,
+                 size,
+                 is_ignored,
+                 is_placeholder,
+                 link_target,
+                 icon,
+                 item_count,
+                 is_locked,
+                 share,
+                 is_protected,
+                 is_own,
+                 read_error,
+                 git_mark,
+                 duplicate_group,
+                 selection_count,
+             }| {
+                let list_prefix = self.render_style.branch(*is_last);
+                let depth_prefix = self.render_style.indent().repeat(*depth);
+                if *is_placeholder {
+                    return ListItem::new(format!("{depth_prefix}{list_prefix} {name}")).dim();
+                }
+
+                let type_prefix = self.render_style.icon(type_, icon);
+                let prefix = format!("{depth_prefix}{list_prefix} {type_prefix} ");
+
+                let mut extras: Vec<Span> = Vec::new();
+                if *type_ == DirType::Dir
+                    && self.dir_tree.max_depth.is_some_and(|max| *depth >= max)
+                {
+                    extras.push(" …".dim());
+                }
+                match (read_error, item_count) {
+                    (Some(kind), _) => extras.push(format!(" (can't read: {kind})").red()),
+                    (None, Some(Some(1))) => extras.push(" (1 item)".dim()),
+                    (None, Some(Some(n))) => {
+                        extras.push(format!(" ({} items)", format::count(*n as u64)).dim())
+                    }
+                    (None, Some(None)) => extras.push(" (?)".dim()),
+                    (None, None) => {}
+                }
+                if let Some(mark) = git_mark {
+                    let color = match mark {
+                        git::Mark::Modified => Color::Yellow,
+                        git::Mark::Added => Color::Green,
+                        git::Mark::Untracked => Color::Red,
+                        git::Mark::Ignored => Color::DarkGray,
+                    };
+                    extras.push(Span::styled(format!(" {}", mark.symbol()), color));
+                }
+                if let Some((picked, total)) = selection_count {
+                    extras.push(
+                        format!(
+                            " [{}/{}]",
+                            format::count(*picked as u64),
+                            format::count(*total as u64)
+                        )
+                        .into(),
+                    );
+                }
+                if let Some(group) = duplicate_group {
+                    let color = DUPLICATE_COLORS[group % DUPLICATE_COLORS.len()];
+                    extras.push(Span::styled(format!(" ={}", group + 1), color));
+                }
+                if *is_locked {
+                    extras.push(format!(" {}", self.render_style.lock()).into());
+                }
+                if *is_protected {
+                    extras.push(format!(" {}", self.render_style.shield()).into());
+                }
+                if *is_own {
+                    extras.push(" (irm's own)".magenta().dim());
+                }
+                match link_target {
+                    Some((target, false)) => extras.push(format!(" -> {target}").dim()),
+                    Some((target, true)) => {
+                        extras.push(format!(" -> {target} (broken)").red().dim())
+                    }
+                    None => {}
+                }
+                if self.show_accessed {
+                    let accessed = accessed.map_or("-".to_string(), format::date);
+                    extras.push(format!("  accessed {accessed}").dim());
+                }
+                // still being measured in the background
+                let calculating = size.is_none() && *type_ == DirType::Dir;
+                // sizes as wide as any "1023.9 KiB" keep the bars in line
+                let size = size
+                    .map(format::size)
+                    .map(|size| if self.du { format!("{size:>10}") } else { size })
+                    .or_else(|| calculating.then(|| "calculating…".to_string()));
+                let bar = share.filter(|_| self.du).map(|share| {
+                    let color = if share < 1.0 / 3.0 {
+                        Color::Green
+                    } else if share < 2.0 / 3.0 {
+                        Color::Yellow
+                    } else {
+                        Color::Red
+                    };
+                    Span::styled(self.render_style.bar(share), color)
+                });
+
+                // a name too long for what's left of the row loses its
+                // middle rather than its end
+                let taken = prefix.width()
+                    + extras.iter().map(Span::width).sum::<usize>()
+                    + bar.as_ref().map_or(0, |_| icons::BAR_WIDTH + 1)
+                    + size.as_ref().map_or(0, |size| size.width() + 3);
+                let name_width = row_width.saturating_sub(taken).max(MIN_NAME_WIDTH);
+                let mut line = Line::from(format!(
+                    "{prefix}{}",
+                    format::elide_middle(name, name_width)
+                ));
+                for extra in extras {
+                    line.push_span(extra);
+                }
+                if let Some(size) = size {
+                    let bar_width = bar.as_ref().map_or(0, |_| icons::BAR_WIDTH + 1);
+                    // in display columns rather than chars, so wide (CJK,
+                    // emoji) names keep the sizes in line
+                    let padding = row_width
+                        .saturating_sub(line.width() + bar_width + size.width() + 1)
+                        .max(2);
+                    line.push_span(" ".repeat(padding));
+                    if let Some(bar) = bar {
+                        line.push_span(bar);
+                        line.push_span(" ");
+                    }
+                    line.push_span(if calculating { size.dim() } else { size.into() });
+                }
+
+                // in the ignored-only view, the rest are just there to lead
+                // the way
+                let dim = match self.ignored_view {
+                    IgnoredView::Everything => false,
+                    IgnoredView::Dim => *is_ignored,
+                    IgnoredView::OnlyIgnored => !*is_ignored,
+                };
+                let partial = selection_count.is_some_and(|(picked, total)| picked < total);
+                let style = if *is_selected {
+                    self.theme.selected_style()
+                } else if partial {
+                    self.theme.partial_style()
+                } else {
+                    self.theme.entry_style(type_)
+                };
+                let li = ListItem::new(line).style(style);
+                if dim {
+                    li.dim()
+                } else {
+                    li
+                }
+            },
+        );
+
+        let rows = enriched.len();
+        let position = format!(
+            " row {}/{} ",
+            format::count(self.hovered.selected().map_or(0, |i| i + 1) as u64),
+            format::count(rows as u64)
+        );
+        let block = block.title(Title::from(position.dim()).alignment(Alignment::Right));
+        f.render_widget(block, area);
+        let list = List::new(items)
+            .scroll_padding(SCROLL_MARGIN)
+            .highlight_style(self.theme.hovered_style())
+            .highlight_symbol(self.render_style.highlight())
+            .repeat_highlight_symbol(true)
+            .direction(ListDirection::TopToBottom);
+
+        f.render_stateful_widget(list, list_area, &mut self.hovered);
+
+        // on the border right of the list, once the rows no longer fit
+        if rows > self.list_height {
+            let mut scrollbar = ScrollbarState::new(rows.saturating_sub(self.list_height))
+                .position(self.hovered.offset());
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .symbols(self.render_style.scrollbar())
+                    .begin_symbol(None)
+                    .end_symbol(None),
+                Rect::new(list_area.right(), list_area.top(), 1, list_area.height),
+                &mut scrollbar,
+            );
+        }
+
+        if let Some(confirm) = &self.confirm {
+            let (message, accept, decline) = confirm.describe(&self.dir_tree);
+            let mut lines = vec![Line::from(message)];
+            // a lone path is already spelled out in the question
+            if let paths @ [_, _, ..] = confirm.paths() {
+                lines.extend(
+                    paths
+                        .iter()
+                        .take(MAX_LISTED_PATHS)
+                        .map(|p| Line::from(self.dir_tree.display_path(p)).dim()),
+                );
+                if paths.len() > MAX_LISTED_PATHS {
+                    let more = format::count((paths.len() - MAX_LISTED_PATHS) as u64);
+                    lines.push(Line::from(format!("...and {more} more")).dim());
+                }
+            }
+            if let Confirm::Typed(removal, typed) = confirm {
+                let answers = match removal.entries() {
+                    Some(entries) => format!("delete or {entries}"),
+                    None => "delete".to_string(),
+                };
+                lines.push(Line::from(vec![
+                    format!("Type {answers} to {accept}: ").bold(),
+                    typed.clone().into(),
+                    "█".into(),
+                ]));
+                lines.push(Line::from(vec![
+                    "<Enter>".red().bold(),
+                    format!(" {accept}  ").into(),
+                    "<Esc>".blue().bold(),
+                    format!(" {decline}").into(),
+                ]));
+            } else if let Confirm::Move(..) = confirm {
+                lines.push(Line::from(vec![
+                    "<O>".red().bold(),
+                    format!(" {accept}  ").into(),
+                    "<S>".blue().bold(),
+                    " skip  ".into(),
+                    "<any other key>".blue().bold(),
+                    format!(" {decline}").into(),
+                ]));
+            } else {
+                lines.push(Line::from(vec![
+                    "<Y>".red().bold(),
+                    format!(" {accept}  ").into(),
+                    "<any other key>".blue().bold(),
+                    format!(" {decline}").into(),
+                ]));
+            }
+            let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+            let height = lines.len() as u16 + 2;
+            let popup = Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .block(Block::bordered().border_set(self.render_style.border()));
+
+            let area = centered_rect(width.max(40), height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+
+        if let Some(comparison) = &self.comparison {
+            let lines = comparison.lines();
+            let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+            let height = lines.len() as u16 + 2;
+            let popup = Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Title::from(" Compare ".bold()).alignment(Alignment::Center))
+                    .border_set(self.render_style.border())
+                    .padding(Padding::horizontal(1)),
+            );
+
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+
+        if let Some(finder) = &self.finder {
+            let mut lines = vec![Line::from(vec![
+                "Find: ".bold(),
+                finder.query.clone().into(),
+                "█".into(),
+            ])];
+            if finder.matches.is_empty() {
+                lines.push(Line::from("No matches").dim());
+            }
+            lines.extend(finder.matches.iter().enumerate().map(|(i, path)| {
+                let line = Line::from(path.clone());
+                if i == finder.picked {
+                    line.reversed()
+                } else {
+                    line
+                }
+            }));
+            let height = finder::MAX_MATCHES as u16 + 3;
+            let popup = Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Title::from(" Find ".bold()).alignment(Alignment::Center))
+                    .title(
+                        Title::from(" <Enter> to jump, <Esc> to close ".dim())
+                            .alignment(Alignment::Center)
+                            .position(Position::Bottom),
+                    )
+                    .border_set(self.render_style.border())
+                    .padding(Padding::horizontal(1)),
+            );
+
+            let area = centered_rect(f.size().width.saturating_sub(8).min(80), height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+
+        if let Some(picked) = self.bookmark_list {
+            let lines: Vec<_> = self
+                .bookmarks
+                .paths()
+                .iter()
+                .enumerate()
+                .map(|(i, path)| {
+                    let line = Line::from(path.clone());
+                    if i == picked {
+                        line.reversed()
+                    } else {
+                        line
+                    }
+                })
+                .collect();
+            let hint = " <Enter> to browse, any other key to close ";
+            let width = lines
+                .iter()
+                .map(Line::width)
+                .max()
+                .unwrap_or(0)
+                .max(hint.len()) as u16
+                + 4;
+            let height = lines.len() as u16 + 2;
+            let popup = Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Title::from(" Bookmarks ".bold()).alignment(Alignment::Center))
+                    .title(
+                        Title::from(hint.dim())
+                            .alignment(Alignment::Center)
+                            .position(Position::Bottom),
+                    )
+                    .border_set(self.render_style.border())
+                    .padding(Padding::horizontal(1)),
+            );
+
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+
+        if let Some(list) = &self.trash_list {
+            let mark = |picked: bool| if picked { "[x] " } else { "[ ] " };
+            let mut lines: Vec<_> = self
+                .trashed
+                .iter()
+                .zip(&list.picked)
+                .rev()
+                .map(|(item, picked)| {
+                    Line::from(vec![
+                        mark(*picked).into(),
+                        item.path.clone().into(),
+                        format!("  {}", format::size(item.removed.trashed_bytes)).dim(),
+                    ])
+                })
+                .collect();
+            lines.push(Line::from(vec![
+                mark(list.everything).into(),
+                "Everything else in the trash".italic(),
+            ]));
+            lines[list.row] = lines[list.row].clone().reversed();
+            let hint = " <Space> pick  <a> all  <Enter> delete for good  <Esc> close ";
+            let width = lines
+                .iter()
+                .map(Line::width)
+                .max()
+                .unwrap_or(0)
+                .max(hint.len()) as u16
+                + 4;
+            let height = lines.len() as u16 + 2;
+            let popup = Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Title::from(" Moved to the trash ".bold()).alignment(Alignment::Center))
+                    .title(
+                        Title::from(hint.dim())
+                            .alignment(Alignment::Center)
+                            .position(Position::Bottom),
+                    )
+                    .border_set(self.render_style.border())
+                    .padding(Padding::horizontal(1)),
+            );
+
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+
+        if self.show_help {
+            let lines = self.help_lines();
+            let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+            let height = lines.len() as u16 + 2;
+            let popup = Paragraph::new(lines).block(
+                Block::bordered()
+                    .title(Title::from(" Keys ".bold()).alignment(Alignment::Center))
+                    .title(
+                        Title::from(" Any key to close ".dim())
+                            .alignment(Alignment::Center)
+                            .position(Position::Bottom),
+                    )
+                    .border_set(self.render_style.border())
+                    .padding(Padding::horizontal(1)),
+            );
+
+            let area = centered_rect(width, height, f.size());
+            f.render_widget(Clear, area);
+            f.render_widget(popup, area);
+        }
+
+        if self.no_color {
+            for cell in &mut f.buffer_mut().content {
+                cell.set_fg(Color::Reset).set_bg(Color::Reset);
+            }
+        }
+    }
+
+    fn help_lines(&self) -> Vec<Line<'static>> {
+        let mut bindings: Vec<(String, &str)> = KEYBINDINGS
+            .iter()
+            .map(|(keys, description)| {
+                let keys = match keys {
+                    Fixed(keys) => keys.to_string(),
+                    Mapped(actions) => actions
+                        .iter()
+                        .map(|&action| self.keymap.describe(action))
+                        .collect::<Vec<_>>()
+                        .join(" / "),
+                };
+                (keys, *description)
+            })
+            .collect();
+        bindings.insert(
+            bindings.len() - 2,
+            (
+                self.repeat_key.to_string(),
+                "Repeat the last selection or removal",
+            ),
+        );
+
+        let key_width = bindings.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        bindings
+            .into_iter()
+            .map(|(key, description)| {
+                Line::from(vec![
+                    format!("{key:<key_width$}").blue().bold(),
+                    format!("  {description}").into(),
+                ])
+            })
+            .collect()
+    }
+}
+
+impl App {
+    /// How many entries are selected and how much space they take up, shown
+    /// at the right of the status line
+    fn selection_line(&self) -> Line<'static> {
+        let mut line = Line::default();
+        if let Some(space) = self.disk_space {
+            line.push_span(
+                format!(
+                    " Disk: {} free of {} ·",
+                    format::size(space.free),
+                    format::size(space.total)
+                )
+                .dim(),
+            );
+        }
+        let count = format!(" {} selected", format::count(self.selected.len() as u64));
+        if self.selected.is_empty() {
+            line.push_span(count.dim());
+            return line;
+        }
+
+        let size = self.selected_size();
+        line.push_span(format!("{count} · {}", format::size(size)).bold());
+        // how much of what's left removing it would win back
+        let share = self
+            .disk_space
+            .and_then(|space| format::percent(size, space.free));
+        if let Some(share) = share {
+            line.push_span(format!(" — {share} of free space").bold());
+        }
+        line.push_span(" ");
+        line
+    }
+
+    /// Ask the root's filesystem how much room is left, after whatever
+    /// may have changed it
+    fn refresh_disk_space(&mut self) {
+        self.disk_space = disk::space(&self.dir_tree.base_node.borrow().path());
+    }
+
+    /// The loaded entries, the selection and the root's size once it's
+    /// known, for `--stats`
+    fn stats_line(&self) -> String {
+        let loaded = self.dir_tree.base_node.borrow().loaded_count();
+        let mut line = format!(
+            "{} items · {} selected",
+            format::count(loaded as u64),
+            format::count(self.selected.len() as u64)
+        );
+        let base = self.dir_tree.base_node.borrow();
+        if !base.children.borrow().is_empty() && !base.has_more() {
+            line.push_str(&format!(" · {}", format::size(base.size())));
+        }
+        line
+    }
+
+    /// Combined size of the selection, counting entries inside another
+    /// selected directory only once
+    fn selected_size(&self) -> u64 {
+        self.selected
+            .iter()
+            .filter(|path| {
+                !(self.select_implies_children
+                    && self
+                        .selected
+                        .iter()
+                        .any(|other| other != *path && Path::new(path).starts_with(other)))
+            })
+            .filter_map(|path| self.dir_tree.find_node(path))
+            .map(|node| node.borrow().size())
+            .sum()
+    }
+
+    /// What to show under the list: the prompt being typed, the latest
+    /// message, or the active filters
+    fn status_line(&self) -> Option<Line<'static>> {
+        if let Some(prompt) = &self.prompt {
+            return Some(Line::from(vec![
+                prompt.kind.label().bold(),
+                prompt.input.clone().into(),
+                "█".into(),
+            ]));
+        }
+        if let Some((status, _)) = &self.status {
+            return Some(Line::from(status.clone().yellow()));
+        }
+        if self.show_real_path && !self.show_details {
+            if let Some(path) = self.hovered_path() {
+                return Some(details::real_path(&self.dir_tree.disk_path(&path)));
+            }
+        }
+        let mut filters = Vec::new();
+        if let Some(min_size) = self.min_size {
+            filters.push(format!("of at least {}", format::size(min_size)));
+        }
+        if let Some(age) = self.age {
+            filters.push(age.describe());
+        }
+        if let Some(query) = &self.search {
+            filters.push(format!("matching '{query}' (/ then Esc to clear)"));
+        }
+        if self.ignored_view == IgnoredView::OnlyIgnored {
+            filters.push("ignored by .gitignore files (Ctrl + G for all)".to_string());
+        }
+        if !self.path_filter.is_empty() {
+            filters.push(self.path_filter.describe());
+        }
+        if filters.is_empty() {
+            return None;
+        }
+        Some(Line::from(
+            format!("Showing items {}", filters.join(", ")).dim(),
+        ))
+    }
+}
+
+/// Questions that need a yes before going ahead
+#[derive(Debug)]
+enum Confirm {
+    /// Deselect this many items
+    ClearSelection(usize),
+    /// Select every node found under the directory at this path
+    SelectDescendants(String, Vec<NodeRef>),
+    /// Open a symlinked directory that resolves to this path outside the root
+    FollowLink(NodeRef, PathBuf),
+    /// Remove the hovered entry with everything in it. The totals are of
+    /// what it holds, and whether all of it could be counted in time.
+    RemoveHovered(String, DeletionSummary, bool),
+    /// Remove every selected entry, with totals as above
+    RemoveSelected(Vec<String>, DeletionSummary, bool),
+    /// Remove everything in the hovered directory but the directory itself,
+    /// with totals as above
+    EmptyHovered(String, DeletionSummary, bool),
+    /// Delete these entries trashed this session from the trash for good,
+    /// and with the flag everything else in it. Always asked as `Typed`.
+    Purge(Vec<String>, bool),
+    /// Move these selected entries into this directory, where the ones in
+    /// the second list would take the place of what has the same name
+    Move(Vec<String>, PathBuf, Vec<String>),
+    /// One of the removals above, too big to go ahead on a single key: it
+    /// takes typing `delete`, or how many entries go. With what's been
+    /// typed so far.
+    Typed(Box<Confirm>, String),
+}
+
+impl Confirm {
+    /// The question, and what answering yes or no does. Paths are shown
+    /// from `tree`'s root down.
+    fn describe(&self, tree: &DirTree) -> (String, &'static str, &'static str) {
+        match self {
+            Confirm::Typed(removal, _) => removal.describe(tree),
+            Confirm::ClearSelection(count) => (
+                format!("Clear {} selected items?", format::count(*count as u64)),
+                "clear",
+                "keep",
+            ),
+            Confirm::SelectDescendants(path, nodes) => (
+                format!(
+                    "Select all {} entries under {path}?",
+                    format::count(nodes.len() as u64)
+                ),
+                "select",
+                "cancel",
+            ),
+            Confirm::FollowLink(node, target) => (
+                format!(
+                    "{} leads outside the tree to {}. Open it?",
+                    node.borrow().full_path(),
+                    target.display()
+                ),
+                "open",
+                "stay",
+            ),
+            Confirm::RemoveHovered(_, totals, counted_all)
+            | Confirm::RemoveSelected(_, totals, counted_all)
+            | Confirm::EmptyHovered(_, totals, counted_all) => {
+                let paths = self.paths();
+                let entries = match paths.len() {
+                    1 => tree.display_path(&paths[0]),
+                    n => format!("{} entries", format::count(n as u64)),
+                };
+                let contents = if totals.files == 1 && totals.dirs == 0 {
+                    format::size(totals.bytes)
+                } else {
+                    format!(
+                        "{} files and {} directories, {}",
+                        format::count(totals.files as u64),
+                        format::count(totals.dirs as u64),
+                        format::size(totals.bytes)
+                    )
+                };
+                let at_least = if *counted_all { "" } else { "at least " };
+                let question = match self {
+                    Confirm::EmptyHovered(..) => {
+                        format!("Empty {entries}, keeping the directory ({at_least}{contents})?")
+                    }
+                    _ => format!("Remove {entries} ({at_least}{contents})?"),
+                };
+                (question, "remove", "cancel")
+            }
+            Confirm::Purge(paths, everything) => {
+                let items = match paths.len() {
+                    0 => String::new(),
+                    1 => tree.display_path(&paths[0]),
+                    n => format!("{} items", format::count(n as u64)),
+                };
+                let question = match (items.is_empty(), everything) {
+                    (_, false) => format!("Delete {items} from the trash for good?"),
+                    (true, true) => "Delete everything in the trash for good?".to_string(),
+                    (false, true) => {
+                        format!("Delete {items} and everything else in the trash for good?")
+                    }
+                };
+                (question, "delete", "keep")
+            }
+            Confirm::Move(_, dest, collisions) => {
+                let question = match collisions.len() {
+                    1 => format!(
+                        "{} would replace what's in {} by that name. Overwrite it?",
+                        tree.display_path(&collisions[0]),
+                        dest.display()
+                    ),
+                    n => format!(
+                        "{} entries would replace what's in {} by their names. Overwrite them?",
+                        format::count(n as u64),
+                        dest.display()
+                    ),
+                };
+                (question, "overwrite", "cancel")
+            }
+        }
+    }
+
+    /// Paths to list under the question, if any
+    fn paths(&self) -> &[String] {
+        match self {
+            Confirm::RemoveHovered(path, ..) | Confirm::EmptyHovered(path, ..) => {
+                std::slice::from_ref(path)
+            }
+            Confirm::RemoveSelected(paths, ..) | Confirm::Purge(paths, _) => paths,
+            Confirm::Move(_, _, collisions) => collisions,
+            Confirm::Typed(removal, _) => removal.paths(),
+            _ => &[],
+        }
+    }
+
+    /// What a removal would take with it
+    fn totals(&self) -> Option<&DeletionSummary> {
+        match self {
+            Confirm::RemoveHovered(_, totals, _)
+            | Confirm::RemoveSelected(_, totals, _)
+            | Confirm::EmptyHovered(_, totals, _) => Some(totals),
+            Confirm::Typed(removal, _) => removal.totals(),
+            _ => None,
+        }
+    }
+
+    /// How many entries a removal would take, as typed to confirm it. None
+    /// when only `delete` will do, as when the whole trash goes.
+    fn entries(&self) -> Option<usize> {
+        match self {
+            Confirm::Purge(_, true) => None,
+            Confirm::Purge(paths, false) => Some(paths.len()),
+            Confirm::Typed(removal, _) => removal.entries(),
+            _ => Some(self.totals().map_or(0, |totals| totals.files + totals.dirs)),
+        }
+    }
+
+    /// Whether `typed` confirms a removal that has to be typed for
+    fn accepts(&self, typed: &str) -> bool {
+        let typed = typed.trim();
+        typed.eq_ignore_ascii_case("delete")
+            || self
+                .entries()
+                .is_some_and(|entries| typed == entries.to_string())
+    }
+}
+
+/// Recursive totals of two directories, shown side by side
+#[derive(Debug)]
+struct Comparison {
+    a: (String, DeletionSummary),
+    b: (String, DeletionSummary),
+}
+
+impl Comparison {
+    /// Measure the directories at `a` and `b`, nodes' paths in `tree`
+    fn new(tree: &DirTree, a: String, b: String) -> Self {
+        let a_summary = DeletionSummary::measure(&tree.disk_path(&a));
+        let b_summary = DeletionSummary::measure(&tree.disk_path(&b));
+        Self {
+            a: (a, a_summary),
+            b: (b, b_summary),
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let row = |label: &'static str, (path, summary): &(String, DeletionSummary)| {
+            Line::from(vec![
+                label.bold(),
+                format!(
+                    " {path}: {}, {} files, {} subdirectories",
+                    format::size(summary.bytes),
+                    format::count(summary.files as u64),
+                    // the directory itself is counted too
+                    format::count(summary.dirs.saturating_sub(1) as u64)
+                )
+                .into(),
+            ])
+        };
+
+        let (a, b) = (&self.a.1, &self.b.1);
+        let verdict = if a.bytes == b.bytes {
+            "Both take up the same space".to_string()
+        } else {
+            let (label, larger, smaller) = if a.bytes > b.bytes {
+                ("A", a, b)
+            } else {
+                ("B", b, a)
+            };
+            let files = larger.files as i64 - smaller.files as i64;
+            format!(
+                "{label} is larger by {} ({}{} files)",
+                format::size(larger.bytes - smaller.bytes),
+                if files < 0 { "-" } else { "+" },
+                format::count(files.unsigned_abs())
+            )
+        };
+
+        vec![
+            row("A", &self.a),
+            row("B", &self.b),
+            Line::from(""),
+            Line::from(verdict),
+            Line::from(vec!["<any key>".blue().bold(), " close".into()]),
+        ]
+    }
+}
+
+/// Selection and removal actions that the repeat key can run again.
+/// Navigation and view toggles are deliberately left out.
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    ToggleSelect,
+    SelectBelowDepth(Depth),
+    SelectType(DirType),
+    SelectDescendants,
+    RemoveHovered,
+    RemoveSelected,
+    RemoveEmptyFiles,
+    EmptyHovered,
+}
+
+#[derive(Debug)]
+struct Prompt {
+    kind: PromptKind,
+    input: String,
+}
+
+impl Prompt {
+    fn new(kind: PromptKind) -> Self {
+        Self {
+            kind,
+            input: String::new(),
+        }
+    }
+}
+
+/// What the text typed into a `Prompt` is for
+#[derive(Debug)]
+enum PromptKind {
+    MinSize,
+    Age,
+    Search,
+    Root,
+    /// A directory to browse in a new tab
+    Tab,
+    Glob,
+    /// A row number to hover, see `App::handle_go_to_row`
+    Row,
+    /// A directory to move the selection to
+    Move,
+}
+
+impl PromptKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PromptKind::MinSize => "Show items at least (e.g. >100M, empty to clear): ",
+            PromptKind::Age => "Show files modified (e.g. >30d ago, <2h ago, empty to clear): ",
+            PromptKind::Search => "Search: ",
+            PromptKind::Root => "Browse directory: ",
+            PromptKind::Tab => "Browse in a new tab: ",
+            PromptKind::Glob => "Select matching (e.g. *.log or **/target): ",
+            PromptKind::Row => "Go to row: ",
+            PromptKind::Move => "Move the selection to: ",
+        }
+    }
+}
+
+/// A `width` x `height` rect in the middle of `area`, shrunk to fit
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Run the user's shell in `dir` and wait for it to exit. Falls back to the
+/// platform default when `$SHELL` isn't set.
+fn spawn_shell(dir: &Path) -> io::Result<()> {
+    let shell = env::var("SHELL").unwrap_or_else(|_| {
+        let fallback = if cfg!(windows) { "cmd.exe" } else { "/bin/sh" };
+        eprintln!("irm: $SHELL is not set, falling back to {fallback}");
+        fallback.to_string()
+    });
+
+    eprintln!(
+        "irm: starting {shell} in {}, exit it to return to irm",
+        dir.display()
+    );
+    Command::new(shell).current_dir(dir).status()?;
+    Ok(())
+}
+
+/// Work out which path to root the tree at. A symlinked entry point is
+/// resolved to its target unless asked not to, so `full_path()` and every
+/// deletion refer to the real tree. Returns the link itself too when it was
+/// resolved.
+fn resolve_root(path: &Path, follow_symlink: bool) -> io::Result<(PathBuf, Option<String>)> {
+    let is_link = fs::symlink_metadata(path)?.file_type().is_symlink();
+    if !fs::metadata(path)?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", path.display()),
+        ));
+    }
+
+    if is_link && follow_symlink {
+        let target = fs::canonicalize(path)?;
+        return Ok((target, Some(path.to_string_lossy().into_owned())));
+    }
+    // a trailing separator would be kept in every path below
+    let root = path.components().collect::<PathBuf>();
+    Ok((root, None))
+}
+
+/// What follows a bulk selection's status when it passed over `skipped`
+/// files for `[select] never`, nothing when it didn't
+fn skipped_note(skipped: usize) -> String {
+    match skipped {
+        0 => String::new(),
+        n => format!(
+            ", skipping {} by their extension ([select] never)",
+            format::count(n as u64)
+        ),
+    }
+}
+
+/// `path` with `.` and `..` worked out by name, the way `cd` does, and a
+/// leading `~` standing for the home directory
+fn normalize(path: &Path) -> PathBuf {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(
+                    normalized.components().next_back(),
+                    None | Some(Component::ParentDir)
+                ) {
+                    normalized.push("..");
+                } else {
+                    normalized.pop();
+                }
+            }
+            Component::Normal(name) if name == "~" && normalized.as_os_str().is_empty() => {
+                match &home {
+                    Some(home) => normalized.push(home),
+                    None => normalized.push(name),
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        normalized.push(".");
+    }
+    normalized
+}
+
+/// Write a rendered frame to a new file in the working directory, returning
+/// the message to show
+fn export_view(svg: &str) -> String {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let file = format!("irm-{secs}.svg");
+    match fs::write(&file, svg) {
+        Ok(()) => format!("Saved the view to {file}"),
+        Err(err) => format!("Could not write {file}: {err}"),
+    }
+}
+
+/// Record the real path behind `path`, telling whether it wasn't there yet.
+/// Paths that can't be resolved count as seen.
+fn first_visit(path: &Path, visited: &mut HashSet<PathBuf>) -> bool {
+    fs::canonicalize(path).is_ok_and(|real| visited.insert(real))
+}
+
+/// Run a command script instead of the TUI, then print the session summary
+fn run_script(app: &mut App, script: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    let failures = if script == "-" {
+        script::run(app, io::stdin().lock(), &mut out)?
+    } else {
+        let file = fs::File::open(script).map_err(|err| {
+            io::Error::new(err.kind(), format!("could not open script {script}: {err}"))
+        })?;
+        script::run(app, io::BufReader::new(file), &mut out)?
+    };
+
+    if !app.summary.is_empty() {
+        writeln!(out, "{}", app.summary)?;
+    }
+    if failures > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let args = cli::parse();
+    format::set_locale(args.locale);
+    let (root, root_link) = match resolve_root(&args.path, args.follow_root_symlink) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            eprintln!("irm: {}: {err}", args.path.display());
+            std::process::exit(1);
+        }
+    };
+
+    if args.watch {
+        let interval = Duration::from_secs(args.interval);
+        return watch::run(&root, interval, &mut io::stdout().lock());
+    }
+
+    let safe_list = match &args.safe_list {
+        Some(file) => match SafeList::load(file) {
+            Ok(safe_list) => safe_list,
+            Err(err) => {
+                eprintln!("irm: {err}");
+                std::process::exit(1);
+            }
+        },
+        None => SafeList::default(),
+    };
+
+    // scripts read it too, for what it protects
+    let config = Config::load(&args).unwrap_or_else(|msg| {
+        eprintln!("irm: {msg}");
+        std::process::exit(1);
+    });
+    let audit = match &config.log {
+        Some(file) => AuditLog::open(file).unwrap_or_else(|err| {
+            eprintln!("irm: {err}");
+            std::process::exit(1);
+        }),
+        None => AuditLog::default(),
+    };
+
+    let path_filter = PathFilter::new(&args.include, &args.exclude).unwrap_or_else(|msg| {
+        eprintln!("irm: {msg}");
+        std::process::exit(2);
+    });
+
+    let mut app = App::new(
+        root,
+        root_link,
+        safe_list,
+        audit,
+        config,
+        path_filter,
+        &args,
+    );
+    if let Some(reference) = &args.since {
+        if let Err(err) = app.handle_select_since(reference) {
+            eprintln!("irm: --since {reference}: {err}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(script) = &args.script {
+        return run_script(&mut app, script);
+    }
+
+    // the repository the root is in, if any; those below it are found as
+    // they're read
+    let root = app.dir_tree.base_node.borrow().path();
+    app.git_marks.load(&root);
+    app.restore_expanded();
+    let mut terminal = match tui::init() {
+        Ok(terminal) => terminal,
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => {
+            eprintln!("irm: {err}; use --script <FILE> to run without one");
+            std::process::exit(1);
+        }
+        Err(err) => return Err(err),
+    };
+    let app_result = app.run(&mut terminal);
+    tui::restore()?;
+    let trees = iter::once(&app.dir_tree).chain(app.tabs.iter().map(|pane| &pane.dir_tree));
+    for dir_tree in trees {
+        if let Err(err) = dir_tree.save_expanded() {
+            eprintln!("irm: could not save the open directories: {err}");
+        }
+    }
+
+    // stdout gets the removed paths alone, so it can be piped into other
+    // tools; the rest is for whoever is at the terminal
+    for path in &app.uncopied {
+        eprintln!("{path}");
+    }
+    if !app.summary.is_empty() {
+        eprintln!("{}", app.summary);
+    }
+    let separator = if args.print0 { '\0' } else { '\n' };
+    let mut out = io::stdout().lock();
+    for path in &app.removed {
+        write!(out, "{path}{separator}")?;
+    }
+    out.flush()?;
+    app_result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// An app browsing `root` with every setting at its default, the root
+    /// open
+    fn app(root: &Path) -> App {
+        app_with(root, Config::default())
+    }
+
+    fn app_with(root: &Path, config: Config) -> App {
+        let app = App::new(
+            root.to_path_buf(),
+            None,
+            SafeList::default(),
+            AuditLog::default(),
+            config,
+            PathFilter::default(),
+            &cli::Args::default(),
+        );
+        Node::open(app.dir_tree.base_node.clone()).unwrap();
+        app
+    }
+
+    /// The node at `path`, a real path below the app's root
+    fn node(app: &App, path: &Path) -> NodeRef {
+        app.dir_tree
+            .load_path(&path.to_string_lossy())
+            .unwrap_or_else(|| panic!("{} isn't in the tree", path.display()))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_symlinked_root_is_resolved_unless_asked_not_to() {
+        let dir = TestDir::new();
+        let real = dir.dir("real");
+        dir.file("real/file", "");
+        let link = dir.symlink(&real, "link");
+        let link_str = link.to_str().unwrap();
+
+        let (root, root_link) = resolve_root(&link, true).unwrap();
+        assert_eq!(root, real);
+        assert_eq!(root_link.as_deref(), Some(link_str));
+        let app = app(&root);
+        assert_eq!(
+            node(&app, &real.join("file")).borrow().path(),
+            real.join("file")
+        );
+
+        let (root, root_link) = resolve_root(&link, false).unwrap();
+        assert_eq!(root, link);
+        assert_eq!(root_link, None);
+    }
+
+    /// The paths of the rows, the root's first
+    fn rows(app: &App) -> Vec<String> {
+        app.dir_tree.to_array(app.view())
+    }
+
+    #[test]
+    fn a_range_follows_its_rows_when_they_are_sorted_again() {
+        let dir = TestDir::new();
+        let a = dir.file("a", "").to_string_lossy().into_owned();
+        let b = dir.file("b", "").to_string_lossy().into_owned();
+        let c = dir.file("c", "").to_string_lossy().into_owned();
+        let mut app = app(dir.path());
+        assert_eq!(rows(&app)[1..], [a.clone(), b.clone(), c.clone()]);
+
+        app.hovered.select(Some(1));
+        app.handle_start_range();
+        app.hovered.select(Some(2));
+        app.extend_range();
+        assert_eq!(app.selected, [a.clone(), b.clone()]);
+
+        app.handle_reverse_sort();
+        assert_eq!(rows(&app)[1..], [c.clone(), b.clone(), a.clone()]);
+        assert_eq!(app.hovered_path(), Some(b.clone()));
+        assert_eq!(app.selected, [a.clone(), b.clone()]);
+
+        // back where it started, only the anchor's row is in the range
+        app.hovered.select(Some(3));
+        app.extend_range();
+        assert_eq!(app.selected, vec![a.clone()]);
+
+        // past the end, or with the anchor gone, nothing is out of bounds
+        app.hovered.select(Some(10));
+        app.extend_range();
+        assert_eq!(app.selected, vec![a.clone()]);
+        app.dir_tree.remove_node(&a, false, &mut |_| {}).unwrap();
+        app.extend_range();
+        assert_eq!(app.range_anchor, None);
+    }
+
+    #[test]
+    fn irms_own_files_are_marked_and_kept() {
+        let dir = TestDir::new();
+        let log = dir.file("logs/irm.log", "");
+        let other = dir.file("logs/other.log", "");
+        let config = Config {
+            log: Some(log.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let mut app = app_with(dir.path(), config);
+        let log_node = node(&app, &log);
+        let view = app.view();
+        assert!(log_node.borrow().row(view, 1, false, false).is_own);
+        assert!(
+            !node(&app, &other)
+                .borrow()
+                .row(view, 1, false, false)
+                .is_own
+        );
+
+        assert!(!app.select_node(log_node));
+        assert!(app.selected.is_empty());
+
+        let logs = dir.join("logs").to_string_lossy().into_owned();
+        let log_str = log.to_string_lossy().into_owned();
+        for (path, recursive) in [(&log_str, false), (&logs, true)] {
+            let err = app
+                .dir_tree
+                .remove_node(path, recursive, &mut |_| {})
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        }
+        assert!(log.exists());
+        let other = other.to_string_lossy().into_owned();
+        app.dir_tree
+            .remove_node(&other, false, &mut |_| {})
+            .unwrap();
+    }
+
+    #[test]
+    fn bulk_selections_skip_the_extensions_never_to_select() {
+        let dir = TestDir::new();
+        let pem = dir.file("a.pem", "");
+        let txt = dir.file("b.txt", "");
+        dir.file("sub/c.KEY", "");
+        let inner = dir.file("sub/d.txt", "");
+        let config = Config {
+            never_select: vec!["pem".to_string(), "key".to_string()],
+            ..Config::default()
+        };
+        let mut app = app_with(dir.path(), config);
+        node(&app, &inner);
+
+        app.rehover(Some(txt.to_string_lossy().into_owned()));
+        app.handle_select_siblings();
+        // the directory is in part, by what else it holds
+        let lossy = |path: &Path| path.to_string_lossy().into_owned();
+        assert_eq!(app.selected, [lossy(&inner), lossy(&txt)]);
+        assert!(app.status.as_ref().unwrap().0.contains("Left 2 files out"));
+
+        app.handle_select_glob("*.pem");
+        assert!(!app.is_path_selected(&lossy(&pem)));
+        assert!(app.status.as_ref().unwrap().0.contains("skipping 1"));
+
+        // one by one, they can be
+        assert!(app.select_node(node(&app, &pem)));
+    }
+
+    #[test]
+    fn the_minimum_size_waits_for_directories_to_be_measured() {
+        let dir = TestDir::new();
+        let small = dir.dir("small");
+        dir.file("small/file", "tiny");
+        let big = dir.file("big", &"x".repeat(2048));
+        dir.file("little", "x");
+        let mut app = app(dir.path());
+
+        app.handle_set_min_size("1K");
+        let small_str = small.to_string_lossy().into_owned();
+        let big_str = big.to_string_lossy().into_owned();
+        // shown, and not measured on the spot
+        assert_eq!(rows(&app)[1..], [small_str.clone(), big_str.clone()]);
+        assert_eq!(node(&app, &small).borrow().size.get(), None);
+
+        app.list_height = 10;
+        app.measure_visible();
+        assert!(node(&app, &small).borrow().sizing.borrow().is_some());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while node(&app, &small).borrow().size.get().is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            app.receive_sizes();
+        }
+        assert_eq!(rows(&app)[1..], [big_str]);
+    }
+
+    /// Remove the entry at `path` the way <R> does, without being asked
+    fn remove_hovered(app: &mut App, path: &Path) {
+        app.rehover(Some(path.to_string_lossy().into_owned()));
+        app.force = true;
+        app.handle_clear_hovered();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn removing_a_link_leaves_its_target() {
+        let dir = TestDir::new();
+        let target = dir.file("target", "kept");
+        let target_dir = dir.file("target_dir/inside", "kept");
+        let to_file = dir.symlink(&target, "to_file");
+        let to_dir = dir.symlink(dir.join("target_dir"), "to_dir");
+        let dangling = dir.symlink(dir.join("missing"), "dangling");
+        let mut app = app(dir.path());
+
+        for link in [&to_file, &to_dir, &dangling] {
+            remove_hovered(&mut app, link);
+            assert!(fs::symlink_metadata(link).is_err(), "{}", link.display());
+            assert!(app.dir_tree.find_node(&link.to_string_lossy()).is_none());
+        }
+        assert!(
+            app.summary.failures.is_empty(),
+            "{:?}",
+            app.summary.failures
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "kept");
+        assert_eq!(fs::read_to_string(&target_dir).unwrap(), "kept");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_removal_that_fails_part_way_keeps_what_is_left() {
+        let dir = TestDir::new();
+        let tree = dir.dir("tree");
+        let other = dir.file("tree/other", "");
+        let locked = dir.file("tree/locked/file", "");
+        let mut app = app(dir.path());
+        node(&app, &locked);
+        // root removes it anyway, leaving nothing to check
+        if !dir.lock("tree/locked") {
+            dir.unlock("tree/locked");
+            return;
+        }
+
+        remove_hovered(&mut app, &tree);
+        dir.unlock("tree/locked");
+        let (status, _) = app.status.as_ref().unwrap();
+        assert!(status.contains("failed to remove"), "{status}");
+        assert_eq!(app.summary.failures.len(), 1);
+        assert!(app.dir_tree.find_node(&tree.to_string_lossy()).is_some());
+        assert!(app.dir_tree.find_node(&locked.to_string_lossy()).is_some());
+        // whatever did go is gone from the tree too
+        let other_shown = app.dir_tree.find_node(&other.to_string_lossy()).is_some();
+        assert_eq!(other_shown, other.exists());
+    }
+
+    #[test]
+    fn removing_the_last_row_hovers_the_one_before() {
+        let dir = TestDir::new();
+        let first = dir.file("first", "");
+        let second = dir.file("second", "");
+        let mut app = app(dir.path());
+
+        remove_hovered(&mut app, &second);
+        assert!(!second.exists());
+        assert_eq!(app.hovered.selected(), Some(1));
+        assert_eq!(
+            app.hovered_path(),
+            Some(first.to_string_lossy().into_owned())
+        );
+        app.handle_select_dir();
+        assert_eq!(app.selected, [first.to_string_lossy().into_owned()]);
+
+        // with nothing left but the root, that's what is hovered
+        app.selected.clear();
+        remove_hovered(&mut app, &first);
+        assert_eq!(app.hovered.selected(), Some(0));
+        app.handle_select_dir();
+    }
+
+    #[test]
+    fn a_directory_and_something_in_it_are_never_both_selected() {
+        let dir = TestDir::new();
+        let sub = dir.dir("sub");
+        let file = dir.file("sub/file", "");
+        let mut app = app(dir.path());
+        let (sub_str, file_str) = (
+            sub.to_string_lossy().into_owned(),
+            file.to_string_lossy().into_owned(),
+        );
+
+        // the child first, then its directory takes its place
+        assert!(app.select_node(node(&app, &file)));
+        assert!(app.select_node(node(&app, &sub)));
+        assert_eq!(app.selected, vec![sub_str.clone()]);
+
+        // the directory first, and the child is selected along with it
+        assert!(app.select_node(node(&app, &file)));
+        assert_eq!(app.selected, vec![sub_str.clone()]);
+        assert_eq!(app.selected_ancestor(&file_str), Some(sub_str.clone()));
+
+        // unless a directory only stands for itself
+        app.selected.clear();
+        app.select_implies_children = false;
+        assert!(app.select_node(node(&app, &sub)));
+        assert!(app.select_node(node(&app, &file)));
+        assert_eq!(app.selected, [sub_str, file_str]);
+    }
+
+    #[test]
+    fn the_safe_list_refuses_selections_in_it() {
+        let dir = TestDir::new();
+        let safe = dir.file("safe/file", "");
+        let other = dir.file("other", "");
+        let list = dir.file("irm-safe", &dir.join("safe").to_string_lossy());
+        let mut app = app(dir.path());
+        app.dir_tree.safe_list = SafeList::load(&list.to_string_lossy()).unwrap();
+
+        assert!(!app.select_node(node(&app, &safe)));
+        assert!(!app.select_node(node(&app, &dir.join("safe"))));
+        assert!(app.selected.is_empty());
+        assert!(app
+            .status
+            .as_ref()
+            .unwrap()
+            .0
+            .contains("is on the safe list"));
+        assert!(app.select_node(node(&app, &other)));
+    }
+
+    #[test]
+    fn a_selection_outlasts_refreshing_its_directory() {
+        let dir = TestDir::new();
+        let file = dir.file("sub/file", "");
+        let gone = dir.file("sub/gone", "");
+        let mut app = app(dir.path());
+        assert!(app.select_node(node(&app, &file)));
+        assert!(app.select_node(node(&app, &gone)));
+
+        fs::remove_file(&gone).unwrap();
+        app.rehover(Some(file.to_string_lossy().into_owned()));
+        app.handle_refresh();
+        assert_eq!(app.selected, [file.to_string_lossy().into_owned()]);
+        assert!(Rc::ptr_eq(&app.selected_nodes()[0], &node(&app, &file)));
+    }
+
+    /// Whether each row is shown as selected, by its path
+    fn selected_rows(app: &App) -> Vec<(String, bool)> {
+        let enriched = app.dir_tree.to_enriched_array(&app.selected, app.view());
+        enriched
+            .iter()
+            .zip(rows(app))
+            .map(|(row, path)| (path, row.is_selected))
+            .collect()
+    }
+
+    #[test]
+    fn selecting_a_directory_takes_its_contents_only_when_that_is_on() {
+        let dir = TestDir::new();
+        let sub = dir.dir("sub");
+        let a = dir.file("sub/a", "");
+        let b = dir.file("sub/deep/b", "");
+        let mut app = app(dir.path());
+        for open in [&sub, &dir.join("sub/deep")] {
+            let open = node(&app, open);
+            Node::open(open.clone()).unwrap();
+        }
+        let lossy = |path: &Path| path.to_string_lossy().into_owned();
+        let shown = |app: &App, path: &Path| {
+            selected_rows(app)
+                .into_iter()
+                .find(|(row, _)| *row == lossy(path))
+                .unwrap()
+                .1
+        };
+
+        assert!(app.select_node(node(&app, &sub)));
+        assert!(shown(&app, &sub) && shown(&app, &a) && shown(&app, &b));
+
+        app.select_implies_children = false;
+        assert!(shown(&app, &sub));
+        assert!(!shown(&app, &a) && !shown(&app, &b));
+
+        // on its own, a directory with anything in it isn't removed
+        app.force = true;
+        app.handle_clear_all();
+        assert!(sub.exists() && a.exists());
+        assert_eq!(app.summary.failures.len(), 1);
+
+        app.select_implies_children = true;
+        app.handle_clear_all();
+        assert!(!sub.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_link_out_of_the_tree_is_only_followed_once_confirmed() {
+        let dir = TestDir::new();
+        let outside = dir.dir("outside");
+        let tree = dir.dir("tree");
+        dir.dir("tree/inner");
+        let out = dir.symlink(&outside, "tree/out");
+        let within = dir.symlink(tree.join("inner"), "tree/within");
+        let mut app = app(&tree);
+        app.confirm_outside_links = true;
+
+        app.rehover(Some(within.to_string_lossy().into_owned()));
+        app.handle_toggle_dir();
+        assert!(app.confirm.is_none());
+        assert!(node(&app, &within).borrow().is_open);
+
+        app.rehover(Some(out.to_string_lossy().into_owned()));
+        app.handle_toggle_dir();
+        assert!(!node(&app, &out).borrow().is_open);
+        let Some(Confirm::FollowLink(_, target)) = &app.confirm else {
+            panic!("not asked, {:?}", app.confirm);
+        };
+        assert_eq!(*target, outside);
+        let confirm = app.confirm.take().unwrap();
+        app.handle_confirmed(confirm);
+        assert!(node(&app, &out).borrow().is_open);
+
+        // without the option, it's opened straight away
+        let mut app = self::app(&tree);
+        app.rehover(Some(out.to_string_lossy().into_owned()));
+        app.handle_toggle_dir();
+        assert!(app.confirm.is_none());
+        assert!(node(&app, &out).borrow().is_open);
+    }
+
+    /// The names of what's been read in the directory at `path`, in order
+    fn children(app: &App, path: &Path) -> Vec<String> {
+        node(app, path)
+            .borrow()
+            .children
+            .borrow()
+            .iter()
+            .map(|child| child.borrow().name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn entries_are_read_in_order_directories_first() {
+        let dir = TestDir::new();
+        dir.file("b.txt", "");
+        dir.dir("Z");
+        dir.file("a.txt", "");
+        dir.dir("A");
+        dir.file("B.txt", "");
+        let app = app(dir.path());
+        assert_eq!(
+            children(&app, dir.path()),
+            ["A", "Z", "a.txt", "B.txt", "b.txt"]
+        );
+    }
+
+    #[test]
+    fn nodes_are_found_by_their_paths_component_by_component() {
+        let dir = TestDir::new();
+        let file = dir.file(Path::new("sub").join("deep").join("file"), "");
+        let app = app(dir.path());
+        let found = node(&app, &file);
+
+        let path = dir.path().join("sub").join(".").join("deep").join("file");
+        let again = app.dir_tree.find_node(&path.to_string_lossy()).unwrap();
+        assert!(Rc::ptr_eq(&found, &again));
+        assert_eq!(found.borrow().path(), file);
+
+        let root = app
+            .dir_tree
+            .find_node(&dir.path().to_string_lossy())
+            .unwrap();
+        assert!(Rc::ptr_eq(&root, &app.dir_tree.base_node));
+        for missing in [dir.join("sub/nothing"), dir.join("sub/deep/file/below")] {
+            assert!(app.dir_tree.find_node(&missing.to_string_lossy()).is_none());
+        }
+        assert!(app.dir_tree.find_node("/elsewhere/sub").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_backslash_is_part_of_a_name_on_unix() {
+        let dir = TestDir::new();
+        let file = dir.file("a\\b", "");
+        dir.file("a/b", "");
+        let app = app(dir.path());
+        assert_eq!(node(&app, &file).borrow().name, "a\\b");
+        assert_eq!(node(&app, &dir.join("a/b")).borrow().name, "b");
+    }
+
+    // Windows drops dots at the end of names
+    #[cfg(unix)]
+    #[test]
+    fn names_made_of_dots_are_names_like_any_other() {
+        let dir = TestDir::new();
+        let names = ["...", ".hidden", "a..b", "x.", ".x."];
+        for name in names {
+            dir.file(Path::new("sub").join(name), name);
+        }
+        // the root is absolute, rather than `.`
+        assert!(dir.path().is_absolute());
+        let app = app(dir.path());
+        for name in names {
+            let path = dir.join("sub").join(name);
+            let found = node(&app, &path);
+            assert_eq!(found.borrow().name, name);
+            assert_eq!(fs::read_to_string(found.borrow().path()).unwrap(), name);
+            assert_eq!(found.borrow().full_path(), path.to_string_lossy());
+        }
+    }
+
+    #[test]
+    fn selecting_a_directorys_entries_and_inverting() {
+        let dir = TestDir::new();
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let a = lossy(dir.file("a", ""));
+        let b = lossy(dir.file("b", ""));
+        let sub = lossy(dir.dir("sub"));
+        let inner = lossy(dir.file("sub/inner", ""));
+        let mut app = app(dir.path());
+
+        // in the hovered entry's directory, and only there
+        app.rehover(Some(a.clone()));
+        app.handle_select_siblings();
+        assert_eq!(app.selected, [sub.clone(), a.clone(), b.clone()]);
+        app.handle_invert_selection();
+        assert!(app.selected.is_empty());
+
+        app.selected = vec![a.clone()];
+        app.handle_invert_selection();
+        assert_eq!(app.selected, [sub.clone(), b.clone()]);
+
+        // what's hidden is left as it is
+        app.selected.clear();
+        app.rehover(Some(sub.clone()));
+        Node::open(node(&app, Path::new(&sub))).unwrap();
+        app.rehover(Some(inner.clone()));
+        app.handle_select_siblings();
+        assert_eq!(app.selected, vec![inner.clone()]);
+        app.search = Some("zzz".to_string());
+        app.handle_invert_selection();
+        assert_eq!(app.selected, [inner]);
+    }
+
+    #[test]
+    fn refreshing_picks_up_what_changed_on_disk() {
+        let dir = TestDir::new();
+        let kept = dir.file("sub/kept", "");
+        let gone = dir.file("sub/gone", "");
+        dir.file("sub/deep/file", "");
+        let mut app = app(dir.path());
+        let deep = node(&app, &dir.join("sub/deep"));
+        Node::open(deep).unwrap();
+        node(&app, &dir.join("sub")).borrow_mut().is_open = true;
+        assert!(app.select_node(node(&app, &gone)));
+        assert!(app.select_node(node(&app, &kept)));
+
+        fs::remove_file(&gone).unwrap();
+        dir.file("sub/new", "");
+        app.rehover(Some(kept.to_string_lossy().into_owned()));
+        app.handle_refresh();
+
+        assert_eq!(children(&app, &dir.join("sub")), ["deep", "kept", "new"]);
+        assert_eq!(app.selected, [kept.to_string_lossy().into_owned()]);
+        // what was open stays open
+        assert!(node(&app, &dir.join("sub/deep")).borrow().is_open);
+    }
+
+    #[test]
+    fn the_root_is_never_removed_and_a_lone_root_can_be_moved_around() {
+        let dir = TestDir::new();
+        let mut app = app(dir.path());
+        assert_eq!(rows(&app).len(), 1);
+
+        remove_hovered(&mut app, dir.path());
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            "Refusing to remove the root directory"
+        );
+        assert!(dir.path().exists());
+        let root = dir.path().to_string_lossy().into_owned();
+        let err = app
+            .dir_tree
+            .remove_node(&root, true, &mut |_| {})
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        for step in [
+            App::handle_hover_down,
+            App::handle_hover_up,
+            App::handle_page_down,
+            App::handle_page_up,
+            App::handle_hover_last,
+        ] {
+            step(&mut app);
+            assert_eq!(app.hovered.selected(), Some(0));
+        }
+        app.handle_go_to_row("5");
+        assert_eq!(app.hovered.selected(), Some(0));
+    }
+
+    /// The screen as `draw` leaves it, a line of text per row
+    fn screen(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wide_names_keep_the_sizes_in_line() {
+        let dir = TestDir::new();
+        // both take up eight columns
+        dir.file("漢字.txt", "12345");
+        dir.file("abcd.txt", "12345");
+        let mut app = app(dir.path());
+
+        let lines = screen(&mut app, 60, 12);
+        let size_ends: Vec<usize> = lines
+            .iter()
+            .filter(|line| line.contains(".txt"))
+            .map(|line| {
+                let before = &line[..line.find("5 B").unwrap()];
+                // a wide character is one symbol followed by an empty one
+                before.chars().count()
+            })
+            .collect();
+        assert_eq!(size_ends.len(), 2, "{lines:#?}");
+        assert_eq!(size_ends[0], size_ends[1], "{lines:#?}");
+    }
+
+    #[test]
+    fn nothing_opens_below_the_maximum_depth() {
+        let dir = TestDir::new();
+        dir.file("a/b/c/d/file", "");
+        let config = Config {
+            max_depth: Some(2),
+            ..Config::default()
+        };
+        let mut app = app_with(dir.path(), config);
+
+        app.handle_expand_all();
+        for _ in 0..100 {
+            if app.expanding.is_empty() {
+                break;
+            }
+            app.expand_step();
+        }
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let (a, b) = (lossy(dir.join("a")), lossy(dir.join("a/b")));
+        assert_eq!(rows(&app), [lossy(dir.path().to_path_buf()), a, b.clone()]);
+        assert!(node(&app, &dir.join("a/b"))
+            .borrow()
+            .children
+            .borrow()
+            .is_empty());
+
+        app.rehover(Some(b.clone()));
+        app.handle_toggle_dir();
+        assert!(!app.dir_tree.find_node(&b).unwrap().borrow().is_open);
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            format!("{b} is as deep as --max-depth allows")
+        );
+    }
+
+    #[test]
+    fn emptying_a_directory_keeps_it() {
+        let dir = TestDir::new();
+        let cache = dir.dir("cache");
+        for name in ["one", "two", "three", "sub/four"] {
+            dir.file(Path::new("cache").join(name), "data");
+        }
+        let mut app = app(dir.path());
+
+        // asked first, like any other removal
+        app.rehover(Some(cache.to_string_lossy().into_owned()));
+        app.handle_empty_hovered();
+        let confirm = app.confirm.take().unwrap();
+        assert!(matches!(confirm, Confirm::EmptyHovered(..)));
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 4);
+
+        app.handle_confirmed(confirm);
+        assert!(cache.is_dir());
+        assert_eq!(fs::read_dir(&cache).unwrap().count(), 0);
+        assert!(children(&app, &cache).is_empty());
+        let (status, _) = app.status.as_ref().unwrap();
+        assert!(status.starts_with("Removed 4 entries from"), "{status}");
+    }
+
+    #[test]
+    fn space_keeps_nested_selections_trimmed() {
+        let dir = TestDir::new();
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let sub = lossy(dir.dir("sub"));
+        let file = lossy(dir.file("sub/file", ""));
+        let mut app = app(dir.path());
+        Node::open(node(&app, Path::new(&sub))).unwrap();
+        node(&app, Path::new(&sub)).borrow_mut().is_open = true;
+
+        let toggle = |app: &mut App, path: &str| {
+            app.rehover(Some(path.to_string()));
+            app.handle_select_dir();
+        };
+        toggle(&mut app, &file);
+        toggle(&mut app, &sub);
+        assert_eq!(app.selected, vec![sub.clone()]);
+
+        toggle(&mut app, &file);
+        assert_eq!(app.selected, vec![sub.clone()]);
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            format!("{file} is selected already, with {sub}")
+        );
+
+        toggle(&mut app, &sub);
+        assert!(app.selected.is_empty());
+    }
+
+    #[test]
+    fn a_protect_pattern_blocks_removing_what_it_matches() {
+        let dir = TestDir::new();
+        let repo = dir.dir("repo");
+        let git = dir
+            .file("repo/.git/HEAD", "")
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        let src = dir
+            .file("repo/src/main.rs", "")
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        let config = Config {
+            protected: Protected::from_config(&"[protect]\npatterns = [\".git\"]".parse().unwrap())
+                .unwrap(),
+            ..Config::default()
+        };
+        let mut app = app_with(dir.path(), config);
+        Node::open(node(&app, &repo)).unwrap();
+        node(&app, &repo).borrow_mut().is_open = true;
+        app.show_hidden = true;
+
+        remove_hovered(&mut app, &git);
+        assert!(app.confirm.is_none());
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            format!(
+                "Refusing to remove {}: it's protected by .git",
+                git.display()
+            )
+        );
+        remove_hovered(&mut app, &repo);
+        assert!(app.confirm.is_none());
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            format!(
+                "Refusing to remove {}: it contains {}, protected by .git",
+                repo.display(),
+                git.display()
+            )
+        );
+
+        // nor can it go along with a selection
+        let lossy = |path: &Path| path.to_string_lossy().into_owned();
+        app.selected = vec![lossy(&src), lossy(&git)];
+        app.handle_clear_all();
+        assert!(app.confirm.is_none());
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            format!(
+                "Refusing to remove the selection, {}: it's protected by .git",
+                git.display()
+            )
+        );
+
+        app.selected = vec![lossy(&src)];
+        app.handle_clear_all();
+        assert!(!src.exists());
+        assert!(git.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_directory_that_cant_be_read_stays_closed_and_says_why() {
+        let dir = TestDir::new();
+        let sealed = dir.dir("sealed");
+        dir.file("sealed/file", "");
+        if !dir.seal("sealed") {
+            // root reads it anyway
+            return;
+        }
+        let mut app = app(dir.path());
+
+        app.rehover(Some(sealed.to_string_lossy().into_owned()));
+        app.handle_toggle_dir();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.scanning > 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            app.receive_scans();
+        }
+        dir.unlock("sealed");
+
+        let sealed_node = node(&app, &sealed);
+        assert!(!sealed_node.borrow().is_open);
+        assert_eq!(
+            sealed_node.borrow().read_error.get(),
+            Some(io::ErrorKind::PermissionDenied)
+        );
+        let (status, _) = app.status.as_ref().unwrap();
+        assert!(
+            status.starts_with(&format!("Could not open {}: ", sealed.display())),
+            "{status}"
+        );
+    }
+
+    #[test]
+    fn moving_stops_at_either_end_of_the_list() {
+        let dir = TestDir::new();
+        for name in ["a", "b", "c"] {
+            dir.file(name, "");
+        }
+        let mut app = app(dir.path());
+
+        app.hovered.select(Some(0));
+        app.handle_hover_up();
+        assert_eq!(app.hovered.selected(), Some(0));
+
+        for _ in 0..5 {
+            app.handle_hover_down();
+        }
+        assert_eq!(app.hovered.selected(), Some(3));
+        app.handle_hover_up();
+        assert_eq!(app.hovered.selected(), Some(2));
+    }
+
+    #[test]
+    fn directories_holding_only_empty_ones_count_as_empty() {
+        let dir = TestDir::new();
+        let top = dir.dir("top");
+        let a = dir.dir("top/a");
+        let c = dir.dir("top/b/c");
+        let keep = dir.file("keep/file", "").parent().unwrap().to_path_buf();
+        let mut app = app(dir.path());
+        for path in [&top, &top.join("b"), &keep] {
+            Node::open(node(&app, path)).unwrap();
+        }
+
+        let empty: Vec<PathBuf> = app
+            .dir_tree
+            .collect_empty_dirs()
+            .iter()
+            .map(|dir| dir.borrow().path())
+            .collect();
+        assert_eq!(empty, vec![a, c, top.join("b"), top.clone()]);
+
+        app.force = true;
+        app.handle_remove_empty_dirs();
+        assert!(!top.exists());
+        assert!(keep.join("file").exists());
+        assert!(app.selected.is_empty());
+    }
+
+    #[test]
+    fn nested_entries_are_shown_from_the_root_but_removed_by_full_path() {
+        let dir = TestDir::new();
+        let file = dir.file("a/b/c/file", "");
+        let mut app = app(dir.path());
+        let nested = node(&app, &file);
+        for parent in ["a", "a/b", "a/b/c"] {
+            node(&app, &dir.join(parent)).borrow_mut().is_open = true;
+        }
+        let full = file.to_string_lossy().into_owned();
+        assert_eq!(nested.borrow().display_path(), "a/b/c/file");
+        assert_eq!(nested.borrow().full_path(), full);
+        assert_eq!(app.dir_tree.display_path(&full), "a/b/c/file");
+
+        app.rehover(Some(full.clone()));
+        app.handle_clear_hovered();
+        let shown = screen(&mut app, 80, 20).join("\n");
+        assert!(shown.contains("a/b/c/file"), "{shown}");
+        assert!(!shown.contains(&full), "{shown}");
+
+        let confirm = app.confirm.take().unwrap();
+        app.handle_confirmed(confirm);
+        assert!(!file.exists());
+        assert_eq!(app.removed, vec![full]);
+    }
+
+    #[test]
+    fn excluded_entries_stay_hidden_even_when_included() {
+        let dir = TestDir::new();
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let a = lossy(dir.file("a.tmp", ""));
+        dir.file("b.txt", "");
+        dir.file("keep/c.tmp", "");
+        let sub = dir.dir("sub");
+        let d = lossy(dir.file("sub/d.tmp", ""));
+        dir.file("sub/e.txt", "");
+
+        let filter = PathFilter::new(&["*.tmp".to_string()], &["keep/**".to_string()]).unwrap();
+        let app = App::new(
+            dir.path().to_path_buf(),
+            None,
+            SafeList::default(),
+            AuditLog::default(),
+            Config::default(),
+            filter,
+            &cli::Args::default(),
+        );
+        Node::open(app.dir_tree.base_node.clone()).unwrap();
+        Node::open(node(&app, &sub)).unwrap();
+        node(&app, &sub).borrow_mut().is_open = true;
+        // until it's read, keep may hold something that's let in
+        let keep = dir.join("keep");
+        assert_eq!(rows(&app)[1], lossy(keep.clone()));
+
+        Node::open(node(&app, &keep)).unwrap();
+        assert_eq!(rows(&app)[1..], [lossy(sub), d, a]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn selecting_the_links_takes_every_kind_of_link_and_nothing_else() {
+        let dir = TestDir::new();
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let file = dir.file("file", "");
+        let sub = dir.dir("sub");
+        let links = [
+            lossy(dir.symlink(&file, "to_file")),
+            lossy(dir.symlink(&sub, "to_dir")),
+            lossy(dir.symlink(dir.join("missing"), "dangling")),
+        ];
+        let mut app = app(dir.path());
+
+        app.handle_select_type(DirType::Symlink);
+        let mut selected = app.selected.clone();
+        selected.sort();
+        let mut expected = links.to_vec();
+        expected.sort();
+        assert_eq!(selected, expected);
+        assert_eq!(app.status.as_ref().unwrap().0, "Selected 3 links");
+
+        app.selected.clear();
+        app.handle_select_type(DirType::Dir);
+        assert_eq!(app.selected, vec![lossy(sub)]);
+    }
+
+    #[test]
+    fn the_flat_view_has_no_root_row_to_skip() {
+        let dir = TestDir::new();
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let x = lossy(dir.file("a/x", ""));
+        let b = lossy(dir.file("b", ""));
+        let mut app = app(dir.path());
+        Node::open(node(&app, &dir.join("a"))).unwrap();
+        app.flat = true;
+        assert_eq!(rows(&app), [x.clone(), b.clone()]);
+
+        app.handle_invert_selection();
+        assert_eq!(app.selected, vec![x.clone(), b.clone()]);
+        app.selected.clear();
+        app.handle_select_type(DirType::File);
+        assert_eq!(app.selected, vec![x.clone(), b.clone()]);
+        app.selected.clear();
+
+        // the first row is a file like any other
+        remove_hovered(&mut app, Path::new(&x));
+        assert!(!Path::new(&x).exists());
+        assert_eq!(rows(&app), vec![b.clone()]);
+        remove_hovered(&mut app, Path::new(&b));
+        assert!(rows(&app).is_empty());
+
+        // and with none left, nothing is hovered to act on
+        app.hovered.select(Some(0));
+        app.handle_select_dir();
+        app.handle_toggle_dir();
+        app.handle_toggle_subtree();
+        app.handle_empty_hovered();
+        app.handle_clear_hovered();
+        app.handle_open_shell();
+        assert!(app.selected.is_empty() && app.confirm.is_none());
+        assert!(app.shell_request.is_none());
+    }
+
+    #[test]
+    fn the_oldest_trashed_entries_are_forgotten_past_the_limits() {
+        let dir = TestDir::new();
+        let config = Config {
+            restore_max_items: 2,
+            restore_max_size: 100,
+            ..Config::default()
+        };
+        let mut app = app_with(dir.path(), config);
+        let trash = |app: &mut App, name: &str, bytes: u64| {
+            app.trashed.push(TrashedItem {
+                path: dir.join(name).to_string_lossy().into_owned(),
+                removed: DeletionSummary {
+                    trashed_bytes: bytes,
+                    ..DeletionSummary::default()
+                },
+            });
+            app.forget_old_trash();
+        };
+        let names = |app: &App| -> Vec<String> {
+            app.trashed
+                .iter()
+                .map(|item| app.dir_tree.display_path(&item.path))
+                .collect()
+        };
+
+        trash(&mut app, "a", 10);
+        trash(&mut app, "b", 10);
+        assert!(app.unrestorable.is_empty());
+        trash(&mut app, "c", 10);
+        assert_eq!(names(&app), ["b", "c"]);
+        assert_eq!(
+            app.freed_since((0, 0)),
+            "Freed 0 B. a can no longer be restored, being past the [restore] limits"
+        );
+        assert!(app.unrestorable.is_empty());
+
+        // one too big for the rest keeps only itself
+        trash(&mut app, "d", 500);
+        assert_eq!(names(&app), ["d"]);
+        assert!(app.freed_since((0, 0)).ends_with(
+            "The 2 oldest entries trashed can no longer be restored, being past the [restore] limits"
+        ));
+    }
+
+    #[test]
+    fn sizes_are_measured_in_the_background_before_sorting_by_them() {
+        let dir = TestDir::new();
+        let lossy = |path: PathBuf| path.to_string_lossy().into_owned();
+        let small = dir.dir("a_small");
+        dir.file("a_small/file", "x");
+        let big = dir.dir("b_big");
+        dir.file("b_big/file", &"x".repeat(4096));
+        let mut app = app(dir.path());
+        app.sort.mode = SortMode::Size;
+
+        // neither is walked on the spot
+        app.hovered.select(Some(0));
+        app.handle_jump_to_largest();
+        let (status, _) = app.status.as_ref().unwrap();
+        assert!(
+            status.starts_with("Measuring 2 directories in "),
+            "{status}"
+        );
+        assert_eq!(app.hovered.selected(), Some(0));
+        assert_eq!(rows(&app)[1..], [lossy(small.clone()), lossy(big.clone())]);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while app.measuring > 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            app.receive_sizes();
+        }
+        assert_eq!(rows(&app)[1..], [lossy(big.clone()), lossy(small)]);
+        app.handle_jump_to_largest();
+        assert_eq!(app.hovered_path(), Some(lossy(big)));
+    }
+
+    #[test]
+    fn removing_what_isnt_loaded_is_an_error() {
+        let dir = TestDir::new();
+        let file = dir.file("sub/file", "");
+        let app = app(dir.path());
+
+        let err = app
+            .dir_tree
+            .remove_node(&file.to_string_lossy(), true, &mut |_| {})
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn declining_to_remove_the_empty_directories_keeps_the_selection() {
+        let dir = TestDir::new();
+        let empty = dir.dir("empty");
+        let file = dir.file("file", "").to_string_lossy().into_owned();
+        let mut app = app(dir.path());
+        app.selected = vec![file.clone()];
+
+        app.handle_remove_empty_dirs();
+        assert!(matches!(app.confirm, Some(Confirm::RemoveSelected(..))));
+        assert_eq!(app.selected, vec![file.clone()]);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE));
+        assert!(app.confirm.is_none());
+        assert!(empty.exists());
+        assert_eq!(app.selected, vec![file.clone()]);
+
+        // and going ahead only takes the directories
+        app.handle_remove_empty_dirs();
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
+        assert!(!empty.exists());
+        assert!(Path::new(&file).exists());
+        assert_eq!(app.selected, vec![file]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_name_that_isnt_utf8_is_looked_up_as_it_is() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = TestDir::new();
+        let file = dir.file(OsStr::from_bytes(b"f\xff"), "abc");
+        let link = dir.symlink(&file, OsStr::from_bytes(b"l\xfe"));
+        let mut app = app(dir.path());
+        let (file_row, link_row) = (
+            file.to_string_lossy().into_owned(),
+            link.to_string_lossy().into_owned(),
+        );
+
+        app.rehover(Some(file_row.clone()));
+        app.handle_show_usage();
+        assert_eq!(app.status.as_ref().unwrap().0, format!("{file_row}: 3 B"));
+
+        app.rehover(Some(link_row));
+        app.handle_show_link_chain();
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            format!("{} -> {}", link.display(), file.display())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_name_that_isnt_utf8_is_shown_lossily_but_removed_as_it_is() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = TestDir::new();
+        let file = dir.file(OsStr::from_bytes(b"f\xff"), "");
+        let inner = dir.dir(OsStr::from_bytes(b"d\xfe"));
+        fs::write(inner.join("inside"), "").unwrap();
+        let app = app(dir.path());
+
+        let names: Vec<String> = app
+            .dir_tree
+            .to_enriched_array(&app.selected, app.view())
+            .into_iter()
+            .map(|row| row.name)
+            .collect();
+        assert!(names.contains(&"f\u{FFFD}".to_string()), "{names:?}");
+
+        // a directory named so can be read too
+        let inner_node = node(&app, &inner);
+        assert_eq!(inner_node.borrow().path(), inner);
+        Node::open(inner_node.clone()).unwrap();
+        assert_eq!(inner_node.borrow().children.borrow().len(), 1);
+
+        let lossy = file.to_string_lossy().into_owned();
+        assert_eq!(node(&app, &file).borrow().full_path(), lossy);
+        app.dir_tree
+            .remove_node(&lossy, false, &mut |_| {})
+            .unwrap();
+        assert!(!file.exists());
+        assert!(app.dir_tree.find_node(&lossy).is_none());
+    }
+
+    #[test]
+    fn subtracting_more_than_was_counted_stops_at_zero() {
+        let mut summary = DeletionSummary {
+            files: 1,
+            bytes: 100,
+            trashed_bytes: 100,
+            ..DeletionSummary::default()
+        };
+        summary.subtract(&DeletionSummary {
+            files: 2,
+            dirs: 1,
+            bytes: 150,
+            trashed_bytes: 150,
+            ..DeletionSummary::default()
+        });
+        assert_eq!((summary.files, summary.dirs), (0, 0));
+        assert_eq!((summary.bytes, summary.trashed_bytes), (0, 0));
+    }
+
+    #[test]
+    fn a_glob_selection_says_why_it_left_entries_out() {
+        let dir = TestDir::new();
+        let log = dir.file("irm.log", "");
+        let safe = dir.file("safe.log", "");
+        dir.file("a.log", "");
+        dir.file("b.log", "");
+        let list = dir.file("safe-list", &safe.to_string_lossy());
+        let config = Config {
+            log: Some(log.to_string_lossy().into_owned()),
+            ..Config::default()
+        };
+        let mut app = app_with(dir.path(), config);
+        app.dir_tree.safe_list = SafeList::load(&list.to_string_lossy()).unwrap();
+
+        app.handle_select_glob("*.log");
+        assert_eq!(app.selected.len(), 2);
+        assert_eq!(
+            app.status.as_ref().unwrap().0,
+            "Selected 2 of the 4 entries matching *.log, leaving out 1 on the safe list \
+             and 1 of irm's own files"
+        );
+    }
 }
@@ -0,0 +1,117 @@
+//! irm's own files: the config directory, the cache directory and the audit
+//! log. When one of them lies inside the tree being browsed it's marked,
+//! and it can't be selected or removed, so irm doesn't delete its own
+//! state out from under itself.
+//!
+//! The paths are resolved once, and `OwnFiles::below` spells them the way
+//! the tree does, so checking a node is only a path comparison.
+
+use std::{
+    fs,
+    path::{self, Path, PathBuf},
+};
+
+use crate::config;
+
+#[derive(Debug, Default, Clone)]
+pub struct OwnFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl OwnFiles {
+    /// irm's files where they are now, with `log` the audit log if one is
+    /// kept
+    pub fn new(log: Option<&str>) -> Self {
+        let paths = [
+            config::config_dir(),
+            config::cache_dir(),
+            log.map(PathBuf::from),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| resolve(&path))
+        .collect();
+        Self { paths }
+    }
+
+    /// Those of the files that are in the tree at `root`, as paths from
+    /// `root` as it's written. When `root` itself is inside one of them,
+    /// that's all of the tree.
+    pub fn below(&self, root: &Path) -> Self {
+        let Ok(real_root) = fs::canonicalize(root) else {
+            return Self::default();
+        };
+        let paths = self
+            .paths
+            .iter()
+            .filter_map(|path| {
+                if real_root.starts_with(path) {
+                    Some(root.to_path_buf())
+                } else {
+                    let relative = path.strip_prefix(&real_root).ok()?;
+                    Some(root.join(relative))
+                }
+            })
+            .collect();
+        Self { paths }
+    }
+
+    /// The file of irm's that `path` is, or lies under
+    pub fn owning(&self, path: &Path) -> Option<&Path> {
+        self.paths
+            .iter()
+            .find(|own| path.starts_with(own))
+            .map(PathBuf::as_path)
+    }
+
+    /// A file of irm's strictly inside the directory at `path`
+    pub fn inside(&self, path: &Path) -> Option<&Path> {
+        self.paths
+            .iter()
+            .find(|own| own.starts_with(path) && own.as_path() != path)
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Absolute form of `path` with the symlinks leading to it resolved, which
+/// needn't exist yet
+fn resolve(path: &Path) -> PathBuf {
+    let path = path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Ok(real) = fs::canonicalize(&path) {
+        return real;
+    }
+    let parent = path
+        .parent()
+        .and_then(|parent| fs::canonicalize(parent).ok());
+    match (parent, path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn files_are_found_below_the_root_as_it_is_written() {
+        let dir = TestDir::new();
+        let log = dir.file("tree/logs/irm.log", "");
+        let own = OwnFiles {
+            paths: vec![log.clone(), PathBuf::from("/somewhere/else")],
+        };
+
+        let tree = dir.join("tree");
+        let below = own.below(&tree);
+        assert_eq!(below.paths, vec![log.clone()]);
+        assert_eq!(below.owning(&log), Some(log.as_path()));
+        assert_eq!(below.owning(&tree.join("logs")), None);
+        assert_eq!(below.inside(&tree.join("logs")), Some(log.as_path()));
+        assert_eq!(below.inside(&log), None);
+
+        // the whole tree is irm's when its root is
+        let inside = own.below(&dir.join("tree/logs/irm.log"));
+        assert!(inside.owning(&log).is_some());
+    }
+}
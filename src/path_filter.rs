@@ -0,0 +1,142 @@
+//! The `--include` and `--exclude` globs, which narrow the tree down from
+//! the start. Patterns are read the way `+` reads them: one without a `/`
+//! is matched against names, like a `.gitignore` line, and one with a `/`
+//! against the path below the root, where `*` stays within one directory.
+//! An entry inside one that matches counts as matching too, and excludes
+//! win over includes.
+
+use std::path::{Component, Path};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+#[derive(Debug, Default)]
+pub struct PathFilter {
+    include: Patterns,
+    exclude: Patterns,
+}
+
+/// Globs split by what they're matched against
+#[derive(Debug, Default)]
+struct Patterns {
+    names: GlobSet,
+    paths: GlobSet,
+    /// Each pattern as given, for the status line
+    given: Vec<String>,
+}
+
+impl Patterns {
+    fn new(patterns: &[String]) -> Result<Self, String> {
+        let (mut names, mut paths) = (GlobSetBuilder::new(), GlobSetBuilder::new());
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern.trim_start_matches('/'))
+                .literal_separator(true)
+                .build()
+                .map_err(|err| format!("invalid pattern '{pattern}': {err}"))?;
+            if pattern.contains('/') {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
+        }
+        let build = |set: GlobSetBuilder| set.build().map_err(|err| err.to_string());
+        Ok(Self {
+            names: build(names)?,
+            paths: build(paths)?,
+            given: patterns.to_vec(),
+        })
+    }
+
+    /// Whether `relative`, or a directory it's in, matches
+    fn matches(&self, relative: &Path) -> bool {
+        if self.given.is_empty() {
+            return false;
+        }
+        let mut within = Path::new("").to_path_buf();
+        relative.components().any(|component| {
+            let Component::Normal(name) = component else {
+                return false;
+            };
+            within.push(name);
+            self.names.is_match(name) || self.paths.is_match(&within)
+        })
+    }
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, String> {
+        Ok(Self {
+            include: Patterns::new(include)?,
+            exclude: Patterns::new(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.given.is_empty() && self.exclude.given.is_empty()
+    }
+
+    pub fn excludes(&self, relative: &Path) -> bool {
+        self.exclude.matches(relative)
+    }
+
+    /// Whether `relative` is let in by the includes, which it always is
+    /// when there are none. Directories leading to what's let in aren't,
+    /// by themselves.
+    pub fn includes(&self, relative: &Path) -> bool {
+        self.include.given.is_empty() || self.include.matches(relative)
+    }
+
+    /// The patterns, for the status line
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.include.given.is_empty() {
+            parts.push(format!("matching {}", self.include.given.join(" or ")));
+        }
+        if !self.exclude.given.is_empty() {
+            parts.push(format!("not matching {}", self.exclude.given.join(" or ")));
+        }
+        parts.join(" and ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        let strings =
+            |patterns: &[&str]| patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        PathFilter::new(&strings(include), &strings(exclude)).unwrap()
+    }
+
+    #[test]
+    fn names_match_anywhere_and_paths_from_the_root() {
+        let filter = filter(&["*.tmp", "logs/*"], &[]);
+        assert!(filter.includes(Path::new("a.tmp")));
+        assert!(filter.includes(Path::new("deep/down/a.tmp")));
+        assert!(filter.includes(Path::new("logs/today")));
+        // and what's inside a match
+        assert!(filter.includes(Path::new("logs/today/part")));
+        assert!(!filter.includes(Path::new("deep/logs/today")));
+        assert!(!filter.includes(Path::new("a.txt")));
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let filter = filter(&["*.tmp"], &["keep/**"]);
+        assert!(filter.includes(Path::new("keep/a.tmp")));
+        assert!(filter.excludes(Path::new("keep/a.tmp")));
+        assert!(!filter.excludes(Path::new("other/a.tmp")));
+        assert_eq!(filter.describe(), "matching *.tmp and not matching keep/**");
+
+        let none = PathFilter::default();
+        assert!(none.is_empty());
+        assert!(none.includes(Path::new("anything")));
+        assert!(!none.excludes(Path::new("anything")));
+    }
+
+    #[test]
+    fn a_bad_pattern_is_an_error() {
+        let err = PathFilter::new(&["[".to_string()], &[]).unwrap_err();
+        assert!(err.starts_with("invalid pattern '['"), "{err}");
+    }
+}
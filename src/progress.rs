@@ -0,0 +1,96 @@
+//! The gauge shown over the tree while a large directory is deleted for
+//! good. Deleting happens between two frames of the main loop, so the gauge
+//! gets a terminal of its own, fixed to a box in the middle of the screen;
+//! the tree is drawn again in full once it's done.
+
+use std::{
+    io::{self, stderr, Stderr},
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::terminal,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders, Gauge},
+    Terminal, TerminalOptions, Viewport,
+};
+
+use crate::centered_rect;
+
+/// How long a deletion goes before the gauge shows up, so that quick ones
+/// don't flash it
+const DELAY: Duration = Duration::from_millis(300);
+
+/// How often the gauge is drawn again
+const INTERVAL: Duration = Duration::from_millis(50);
+
+pub struct Progress {
+    /// What's being removed, as shown in the gauge's title
+    name: String,
+    /// How many entries there are to remove
+    total: u64,
+    started: Instant,
+    drawn: Option<Instant>,
+    /// Kept once opened, so that the last frame also goes where the first
+    /// did
+    terminal: Option<Terminal<CrosstermBackend<Stderr>>>,
+}
+
+impl Progress {
+    pub fn new(name: String, total: u64) -> Self {
+        Self {
+            name,
+            total: total.max(1),
+            started: Instant::now(),
+            drawn: None,
+            terminal: None,
+        }
+    }
+
+    /// Whether the gauge made it to the screen, which the tree's terminal
+    /// then has to be cleared of
+    pub fn was_shown(&self) -> bool {
+        self.drawn.is_some()
+    }
+
+    /// Note that `removed` entries are gone, drawing the gauge when it's
+    /// due. Failing to draw it isn't worth stopping the deletion for.
+    pub fn update(&mut self, removed: u64) {
+        if self.started.elapsed() < DELAY || self.drawn.is_some_and(|at| at.elapsed() < INTERVAL) {
+            return;
+        }
+        let _ = self.draw(removed);
+        self.drawn = Some(Instant::now());
+    }
+
+    fn draw(&mut self, removed: u64) -> io::Result<()> {
+        if self.terminal.is_none() {
+            let (width, height) = terminal::size()?;
+            let screen = Rect::new(0, 0, width, height);
+            let area = centered_rect(width.saturating_sub(8).min(60), 3, screen);
+            self.terminal = Some(Terminal::with_options(
+                CrosstermBackend::new(stderr()),
+                TerminalOptions {
+                    viewport: Viewport::Fixed(area),
+                },
+            )?);
+            // blank cells are only written where the last frame had others
+            self.terminal.as_mut().unwrap().clear()?;
+        }
+        let terminal = self.terminal.as_mut().unwrap();
+        let removed = removed.min(self.total);
+        let gauge = Gauge::default()
+            .block(
+                Block::default()
+                    .title(format!(" Removing {} ", self.name))
+                    .borders(Borders::ALL),
+            )
+            .gauge_style(Style::default().fg(Color::Red))
+            .ratio(removed as f64 / self.total as f64)
+            .label(format!("{removed} of {} entries", self.total));
+        terminal.draw(|frame| frame.render_widget(gauge, frame.size()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+//! Glob patterns for entries that can never be removed, read from the
+//! `[protect]` table of the config file (see `config`):
+//!
+//! ```toml
+//! [protect]
+//! patterns = [".git", "/home/*/.ssh"]
+//! ```
+//!
+//! A pattern without a `/` is matched against names, anywhere in the tree.
+//! One starting with `/` is matched against the whole absolute path, and
+//! any other against its last components, so `build/cache` protects every
+//! `cache` directly inside a `build`. `*` never crosses a `/`.
+
+use std::{
+    fs,
+    path::{self, Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobMatcher};
+
+#[derive(Debug, Default, Clone)]
+pub struct Protected {
+    /// Each pattern as written, with what it compiled to and whether it's
+    /// matched against names only
+    patterns: Vec<(String, GlobMatcher, bool)>,
+}
+
+impl Protected {
+    pub fn from_config(config: &toml::Table) -> Result<Self, String> {
+        let mut protected = Self::default();
+        let Some(table) = config.get("protect") else {
+            return Ok(protected);
+        };
+        let table = table.as_table().ok_or("`protect` must be a table")?;
+
+        for (name, value) in table {
+            if name != "patterns" {
+                return Err(format!("unknown setting '{name}' in [protect]"));
+            }
+            let patterns = value
+                .as_array()
+                .ok_or("protect patterns must be a list of strings")?;
+            for pattern in patterns {
+                let pattern = pattern
+                    .as_str()
+                    .ok_or("protect patterns must be a list of strings")?;
+                protected.add(pattern)?;
+            }
+        }
+        Ok(protected)
+    }
+
+    fn add(&mut self, pattern: &str) -> Result<(), String> {
+        let by_name = !pattern.contains('/');
+        let full = if by_name || pattern.starts_with('/') {
+            pattern.to_string()
+        } else {
+            format!("**/{pattern}")
+        };
+        let glob = GlobBuilder::new(full.trim_end_matches('/'))
+            .literal_separator(true)
+            .build()
+            .map_err(|err| format!("invalid protect pattern: {err}"))?;
+        self.patterns
+            .push((pattern.to_string(), glob.compile_matcher(), by_name));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// The pattern that protects the entry at `path` itself, if any
+    pub fn matching(&self, path: &Path) -> Option<&str> {
+        if self.is_empty() {
+            return None;
+        }
+        let path = path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let name = Path::new(path.file_name().unwrap_or_default());
+        self.patterns
+            .iter()
+            .find(|(_, glob, by_name)| glob.is_match(if *by_name { name } else { &path }))
+            .map(|(pattern, ..)| pattern.as_str())
+    }
+
+    /// A protected entry somewhere inside the directory at `path` on disk,
+    /// with the pattern protecting it. Symlinks aren't followed, since
+    /// removing one leaves what it points to alone.
+    pub fn inside(&self, path: &Path) -> Option<(PathBuf, &str)> {
+        if self.is_empty() {
+            return None;
+        }
+        for entry in fs::read_dir(path).ok()?.flatten() {
+            let entry = entry.path();
+            if let Some(pattern) = self.matching(&entry) {
+                return Some((entry, pattern));
+            }
+            let is_dir = fs::symlink_metadata(&entry).is_ok_and(|meta| meta.is_dir());
+            if is_dir {
+                if let Some(found) = self.inside(&entry) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn protected(patterns: &str) -> Protected {
+        let config = format!("[protect]\npatterns = {patterns}\n");
+        Protected::from_config(&config.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn patterns_match_names_whole_paths_or_their_ends() {
+        let protected = protected(r#"[".git", "/home/*/.ssh", "build/cache"]"#);
+        assert_eq!(protected.matching(Path::new("/src/irm/.git")), Some(".git"));
+        assert_eq!(
+            protected.matching(Path::new("/home/me/.ssh")),
+            Some("/home/*/.ssh")
+        );
+        assert_eq!(
+            protected.matching(Path::new("/src/build/cache")),
+            Some("build/cache")
+        );
+
+        // `*` stays within one component
+        assert_eq!(protected.matching(Path::new("/home/a/b/.ssh")), None);
+        assert_eq!(protected.matching(Path::new("/src/cache")), None);
+        assert_eq!(protected.matching(Path::new("/src/.github")), None);
+    }
+
+    #[test]
+    fn entries_inside_a_directory_are_found() {
+        let dir = TestDir::new();
+        let git = dir.dir("repo/.git");
+        dir.file("repo/src/main.rs", "");
+        let protected = protected(r#"[".git"]"#);
+
+        assert_eq!(protected.inside(&dir.join("repo")), Some((git, ".git")));
+        assert_eq!(protected.inside(&dir.join("repo/src")), None);
+        assert_eq!(Protected::default().inside(&dir.join("repo")), None);
+    }
+
+    #[test]
+    fn malformed_settings_are_errors() {
+        let parse = |config: &str| Protected::from_config(&config.parse().unwrap());
+        assert!(parse("protect = 1").is_err());
+        assert!(parse("[protect]\npatterns = \".git\"").is_err());
+        assert!(parse("[protect]\npatterns = [1]").is_err());
+        assert!(parse("[protect]\nglobs = []").is_err());
+        assert!(parse("[protect]\npatterns = [\"[\"]").is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+}
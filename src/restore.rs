@@ -0,0 +1,140 @@
+//! Puts entries that were moved to the trash back where they came from, or
+//! deletes them from it for good. Looking inside the trash is only possible
+//! on Windows and on systems that follow the freedesktop.org trash spec, so
+//! elsewhere this always fails.
+
+use std::{io, path::Path};
+
+/// Restore the most recent item trashed from `path`
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+pub fn restore(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "something else is there now",
+        ));
+    }
+    trash::os_limited::restore_all([latest(path)?]).map_err(io::Error::other)
+}
+
+/// Delete the most recent item trashed from `path` for good
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+pub fn purge(path: &Path) -> io::Result<()> {
+    trash::os_limited::purge_all([latest(path)?]).map_err(io::Error::other)
+}
+
+/// Delete everything in the trash for good, returning how many items that
+/// was
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+pub fn purge_everything() -> io::Result<usize> {
+    let items = trash::os_limited::list().map_err(io::Error::other)?;
+    let count = items.len();
+    trash::os_limited::purge_all(items).map_err(io::Error::other)?;
+    Ok(count)
+}
+
+/// The most recent item in the trash that was at `path`
+#[cfg(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+))]
+fn latest(path: &Path) -> io::Result<trash::TrashItem> {
+    use std::fs;
+
+    let parent = path.parent().map(fs::canonicalize).transpose()?;
+    trash::os_limited::list()
+        .map_err(io::Error::other)?
+        .into_iter()
+        .filter(|item| Some(item.name.as_os_str()) == path.file_name())
+        // the trash may have recorded the parent through a different route
+        .filter(|item| fs::canonicalize(&item.original_parent).ok() == parent)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "it's no longer in the trash"))
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+pub fn restore(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "restoring from the trash isn't supported on this platform",
+    ))
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+pub fn purge(_path: &Path) -> io::Result<()> {
+    Err(unsupported_purge())
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+pub fn purge_everything() -> io::Result<usize> {
+    Err(unsupported_purge())
+}
+
+#[cfg(not(any(
+    windows,
+    all(
+        unix,
+        not(target_os = "macos"),
+        not(target_os = "ios"),
+        not(target_os = "android")
+    )
+)))]
+fn unsupported_purge() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "emptying the trash isn't supported on this platform",
+    )
+}
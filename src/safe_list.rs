@@ -0,0 +1,135 @@
+//! Paths loaded from `--safe-list <FILE>` that can never be selected or
+//! removed, along with everything below them. The file holds one absolute
+//! path per line; blank lines and lines starting with `#` are ignored.
+
+use std::{
+    fs, io,
+    path::{self, Path, PathBuf},
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct SafeList {
+    paths: Vec<PathBuf>,
+}
+
+impl SafeList {
+    pub fn load(file: &str) -> io::Result<Self> {
+        let contents = fs::read_to_string(file).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("could not read safe list {file}: {err}"),
+            )
+        })?;
+
+        let mut paths = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let path = Path::new(line);
+            if !path.is_absolute() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{file}:{}: '{line}' is not an absolute path", number + 1),
+                ));
+            }
+            paths.push(resolve(path));
+        }
+        Ok(Self { paths })
+    }
+
+    /// The safe-listed path that `path` is, or lies under
+    pub fn protecting(&self, path: &Path) -> Option<&Path> {
+        let path = resolve(path);
+        self.paths
+            .iter()
+            .find(|safe| path.starts_with(safe))
+            .map(PathBuf::as_path)
+    }
+
+    /// A safe-listed path strictly inside the directory at `path`
+    pub fn inside(&self, path: &Path) -> Option<&Path> {
+        let path = resolve(path);
+        self.paths
+            .iter()
+            .find(|safe| safe.starts_with(&path) && **safe != path)
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Absolute form of `path` with every symlink along the way resolved,
+/// except for the last component so that a link is judged by where it
+/// sits rather than where it points
+fn resolve(path: &Path) -> PathBuf {
+    let parent = path
+        .parent()
+        .and_then(|parent| fs::canonicalize(parent).ok());
+    match (parent, path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(name),
+        _ => fs::canonicalize(path)
+            .or_else(|_| path::absolute(path))
+            .unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn listed_paths_protect_themselves_and_what_is_below() {
+        let dir = TestDir::new();
+        let keep = dir.dir("home/keep");
+        let list = dir.file(
+            "safe",
+            &format!(
+                "# kept\n\n  {}  \n{}\n",
+                keep.display(),
+                dir.join("gone").display()
+            ),
+        );
+        let safe = SafeList::load(list.to_str().unwrap()).unwrap();
+
+        assert_eq!(safe.protecting(&keep), Some(keep.as_path()));
+        assert_eq!(safe.protecting(&keep.join("file")), Some(keep.as_path()));
+        assert_eq!(safe.protecting(&dir.join("home")), None);
+        assert_eq!(safe.inside(&dir.join("home")), Some(keep.as_path()));
+        assert_eq!(safe.inside(&keep), None);
+        // a path needn't exist to be listed
+        assert!(safe.protecting(&dir.join("gone")).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_link_is_judged_by_where_it_sits() {
+        let dir = TestDir::new();
+        let keep = dir.dir("keep");
+        let link = dir.symlink(&keep, "link");
+        let via = dir.symlink(dir.path(), "via");
+        let list = dir.file("safe", &keep.display().to_string());
+        let safe = SafeList::load(list.to_str().unwrap()).unwrap();
+
+        assert_eq!(safe.protecting(&link), None);
+        assert_eq!(
+            safe.protecting(&via.join("keep/file")),
+            Some(keep.as_path())
+        );
+    }
+
+    #[test]
+    fn relative_paths_and_missing_lists_are_errors() {
+        let dir = TestDir::new();
+        let list = dir.file("safe", "/fine\nnot/absolute\n");
+        let err = SafeList::load(list.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .ends_with(":2: 'not/absolute' is not an absolute path"));
+
+        let missing = dir.join("missing");
+        let err = SafeList::load(missing.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
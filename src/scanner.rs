@@ -0,0 +1,122 @@
+//! Reads directories on worker threads so that a slow disk or network mount
+//! never holds up the interface. A worker only hands back plain paths and
+//! types; grafting them into the tree is left to the main thread.
+
+use std::{
+    cell::Cell,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use ignore::gitignore::Gitignore;
+
+use crate::{gitignore, DirType};
+
+/// Entries read from a directory by a worker
+#[derive(Debug)]
+pub struct Chunk {
+    /// The directory, as its node's full path
+    pub dir: String,
+    pub entries: Vec<(PathBuf, DirType)>,
+    /// The directory's own `.gitignore`, sent along with the first chunk
+    pub gitignore: Option<Gitignore>,
+    /// Reading stopped here, leaving the rest of the directory unread
+    pub error: Option<io::Error>,
+    /// Nothing is left to read, so the worker has stopped
+    pub done: bool,
+}
+
+/// A directory being read by a worker, one chunk per request. Dropping it
+/// stops the worker.
+#[derive(Debug)]
+pub struct Scan {
+    more: Sender<()>,
+    /// A chunk has been asked for and hasn't been received yet
+    in_flight: Cell<bool>,
+}
+
+impl Scan {
+    /// Ask for the next chunk, unless one is on its way already. Returns
+    /// whether a request went out.
+    pub fn request_more(&self) -> bool {
+        if self.in_flight.get() || self.more.send(()).is_err() {
+            return false;
+        }
+        self.in_flight.set(true);
+        true
+    }
+
+    /// Note that the chunk asked for has been received
+    pub fn received(&self) {
+        self.in_flight.set(false);
+    }
+}
+
+/// Start reading the directory at `path`, whose node's full path is `dir`,
+/// on a new thread, `chunk_size` entries at a time. The first chunk is read
+/// straight away; each one after that waits for `Scan::request_more`.
+pub fn start(dir: String, path: PathBuf, chunk_size: usize, results: Sender<Chunk>) -> Scan {
+    let (more, requests) = mpsc::channel();
+    thread::spawn(move || read(dir, &path, chunk_size, &results, &requests));
+    Scan {
+        more,
+        in_flight: Cell::new(true),
+    }
+}
+
+fn read(
+    dir: String,
+    path: &Path,
+    chunk_size: usize,
+    results: &Sender<Chunk>,
+    requests: &Receiver<()>,
+) {
+    let mut chunk = Chunk {
+        gitignore: gitignore::load(path),
+        dir,
+        entries: Vec::new(),
+        error: None,
+        done: true,
+    };
+    let mut entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            chunk.error = Some(err);
+            let _ = results.send(chunk);
+            return;
+        }
+    };
+
+    loop {
+        for entry in entries.by_ref().take(chunk_size) {
+            match entry {
+                Ok(entry) => {
+                    let path = entry.path();
+                    let type_ = DirType::of(&path);
+                    chunk.entries.push((path, type_));
+                }
+                Err(err) => {
+                    chunk.error = Some(err);
+                    break;
+                }
+            }
+        }
+        chunk.done = chunk.error.is_some() || chunk.entries.len() < chunk_size;
+
+        let next = Chunk {
+            dir: chunk.dir.clone(),
+            entries: Vec::new(),
+            gitignore: None,
+            error: None,
+            done: true,
+        };
+        let done = chunk.done;
+        // the tree no longer wants this directory, or the app has quit
+        if results.send(chunk).is_err() || done || requests.recv().is_err() {
+            return;
+        }
+        chunk = next;
+    }
+}
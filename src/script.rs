@@ -0,0 +1,270 @@
+//! Headless command interface, driven by `--script <file>` or `--script -`
+//! for stdin. Each line holds one command; blank lines and lines starting
+//! with `#` are ignored. Paths are relative to the root (or spelled out
+//! exactly as the tree shows them), and directories along the way are
+//! scanned as needed.
+//!
+//! ```text
+//! open <path>          expand a directory
+//! close <path>         collapse a directory
+//! select <path>        add an entry to the selection
+//! deselect <path>      drop an entry from the selection
+//! select-below <N>     select everything deeper than level N
+//! clear                deselect everything
+//! list                 print the visible tree, `*` marks selected rows
+//! selected             print the selected paths
+//! remove <path>        remove one entry from disk
+//! remove-selected      remove every selected entry from disk
+//! summary              print what has been removed so far
+//! ```
+//!
+//! With `--readonly`, both removals fail without touching anything.
+
+use std::{
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{App, DirType, Node, NodeRef, Row};
+
+/// Run every command from `input`, reporting each one's effect on `out`.
+/// Returns how many commands failed.
+pub fn run(app: &mut App, input: impl BufRead, out: &mut impl Write) -> io::Result<usize> {
+    let mut failures = 0;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        if let Err(err) = execute(app, command, arg, out) {
+            writeln!(out, "error: {line}: {err}")?;
+            failures += 1;
+        }
+    }
+
+    Ok(failures)
+}
+
+fn execute(app: &mut App, command: &str, arg: &str, out: &mut impl Write) -> Result<(), String> {
+    let write_err = |err: io::Error| err.to_string();
+
+    match command {
+        "open" | "close" => {
+            let node = resolve(app, arg)?;
+            if node.borrow().type_ != DirType::Dir {
+                return Err("not a directory".to_string());
+            }
+            let path = node.borrow().full_path();
+            if command == "open" {
+                if !app.dir_tree.can_open(&node.borrow()) {
+                    return Err("deeper than --max-depth allows".to_string());
+                }
+                Node::open(node).map_err(write_err)?;
+                writeln!(out, "opened {path}").map_err(write_err)
+            } else {
+                node.borrow_mut().is_open = false;
+                writeln!(out, "closed {path}").map_err(write_err)
+            }
+        }
+        "select" => {
+            let node = resolve(app, arg)?;
+            let path = node.borrow().full_path();
+            if !app.select_node(node) {
+                return Err(app.status.take().map(|(msg, _)| msg).unwrap_or_default());
+            }
+            writeln!(out, "selected {path}").map_err(write_err)
+        }
+        "deselect" => {
+            let path = resolve(app, arg)?.borrow().full_path();
+            app.deselect_path(&path);
+            writeln!(out, "deselected {path}").map_err(write_err)
+        }
+        "select-below" => {
+            let depth = arg
+                .parse()
+                .map_err(|_| format!("expected a depth, got '{arg}'"))?;
+            let before = app.selected.clone();
+            app.handle_select_below_depth(depth);
+            let added = app
+                .selected
+                .iter()
+                .filter(|path| !before.contains(path))
+                .count();
+            writeln!(out, "selected {added} entries below depth {depth}").map_err(write_err)
+        }
+        "clear" => {
+            app.selected.clear();
+            writeln!(out, "cleared selection").map_err(write_err)
+        }
+        "list" => {
+            let paths = app.dir_tree.to_array(app.view());
+            let rows = app.dir_tree.to_enriched_array(&app.selected, app.view());
+            for (
+                path,
+                Row {
+                    is_selected,
+                    is_placeholder,
+                    ..
+                },
+            ) in paths.iter().zip(rows)
+            {
+                if is_placeholder {
+                    continue;
+                }
+                let marker = if is_selected { '*' } else { ' ' };
+                writeln!(out, "{marker} {path}").map_err(write_err)?;
+            }
+            Ok(())
+        }
+        "selected" => {
+            for path in &app.selected {
+                writeln!(out, "{path}").map_err(write_err)?;
+            }
+            Ok(())
+        }
+        "remove" | "remove-selected" if app.readonly => {
+            Err("read-only mode, nothing can be removed".to_string())
+        }
+        "remove" => {
+            let path = resolve(app, arg)?.borrow().full_path();
+            let failures = app.summary.failures.len();
+            app.remove_path(&path, true);
+            match app.summary.failures.get(failures) {
+                Some(failure) => Err(failure.clone()),
+                None if app.dir_tree.dry_run => {
+                    writeln!(out, "would remove {path}").map_err(write_err)
+                }
+                None => writeln!(out, "removed {path}").map_err(write_err),
+            }
+        }
+        "remove-selected" => {
+            let failures = app.summary.failures.len();
+            app.remove_selected();
+            for failure in &app.summary.failures[failures..] {
+                writeln!(out, "failed to remove {failure}").map_err(write_err)?;
+            }
+            writeln!(out, "{} entries left selected", app.selected.len()).map_err(write_err)
+        }
+        "summary" => writeln!(out, "{}", app.summary).map_err(write_err),
+        _ => Err(format!("unknown command '{command}'")),
+    }
+}
+
+/// Look up `arg` in the tree, accepting both paths relative to the root and
+/// full paths as the tree displays them
+fn resolve(app: &App, arg: &str) -> Result<NodeRef, String> {
+    if arg.is_empty() {
+        return Err("missing path".to_string());
+    }
+
+    let root = app.dir_tree.base_node.borrow().path();
+    let path = if Path::new(arg).starts_with(&root) {
+        PathBuf::from(arg)
+    } else {
+        root.join(arg)
+    };
+    let path = path.to_string_lossy();
+
+    app.dir_tree
+        .load_path(&path)
+        .ok_or_else(|| format!("no such entry '{arg}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        audit::AuditLog, cli::Args, config::Config, path_filter::PathFilter, safe_list::SafeList,
+        test_dir::TestDir,
+    };
+
+    fn app(root: &Path, config: Config) -> App {
+        let app = App::new(
+            root.to_path_buf(),
+            None,
+            SafeList::default(),
+            AuditLog::default(),
+            config,
+            PathFilter::default(),
+            &Args::default(),
+        );
+        Node::open(app.dir_tree.base_node.clone()).unwrap();
+        app
+    }
+
+    /// What running `script` prints, and how many of its commands failed
+    fn run_script(app: &mut App, script: &str) -> (String, usize) {
+        let mut out = Vec::new();
+        let failures = run(app, script.as_bytes(), &mut out).unwrap();
+        (String::from_utf8(out).unwrap(), failures)
+    }
+
+    #[test]
+    fn commands_open_select_list_and_remove() {
+        let dir = TestDir::new();
+        let sub = dir.file("sub/old.log", "").parent().unwrap().to_path_buf();
+        dir.file("sub/new.log", "");
+        let mut app = app(dir.path(), Config::default());
+        let root = dir.path().display();
+
+        let (out, failures) = run_script(
+            &mut app,
+            &format!(
+                "# a comment\n\nopen sub\nselect sub/old.log\nlist\n\nselected\n\
+                 remove-selected\nselected\nremove {root}/sub/new.log\n"
+            ),
+        );
+        assert_eq!(failures, 0, "{out}");
+        let sub = sub.display();
+        assert_eq!(
+            out,
+            format!(
+                "opened {sub}\nselected {sub}/old.log\n  {root}\n  {sub}\n  {sub}/new.log\n\
+                 * {sub}/old.log\n{sub}/old.log\n0 entries left selected\nremoved {sub}/new.log\n"
+            )
+        );
+        assert_eq!(fs::read_dir(dir.join("sub")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn a_failed_command_is_reported_and_the_rest_still_run() {
+        let dir = TestDir::new();
+        let file = dir.file("file", "");
+        let config = Config {
+            readonly: true,
+            ..Config::default()
+        };
+        let mut app = app(dir.path(), config);
+
+        let (out, failures) = run_script(
+            &mut app,
+            "frobnicate\nopen file\nselect missing\nselect\nselect-below deep\n\
+             remove file\nselect file\n",
+        );
+        assert_eq!(failures, 6);
+        assert_eq!(
+            out,
+            format!(
+                "error: frobnicate: unknown command 'frobnicate'\n\
+                 error: open file: not a directory\n\
+                 error: select missing: no such entry 'missing'\n\
+                 error: select: missing path\n\
+                 error: select-below deep: expected a depth, got 'deep'\n\
+                 error: remove file: read-only mode, nothing can be removed\n\
+                 selected {}\n",
+                file.display()
+            )
+        );
+        assert!(file.exists());
+    }
+}
@@ -0,0 +1,81 @@
+//! Measures directories on worker threads, so that a big one showing up in
+//! the tree doesn't hold up the interface while everything below it is
+//! added up. Like `scanner`, a worker only hands back what it found; the
+//! main thread decides whether it's still wanted.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    thread,
+};
+
+/// Tells measurements apart, so that one finishing after its directory was
+/// measured again, or the tree replaced, can be recognized
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// What a worker found
+#[derive(Debug)]
+pub struct Measured {
+    /// The directory, as its node's full path
+    pub dir: String,
+    /// The `Job` it was for
+    pub id: u64,
+    /// Size on disk of everything at and below the directory, not following
+    /// symlinks
+    pub bytes: u64,
+}
+
+/// A directory being measured by a worker. Dropping it stops the worker,
+/// which still reports back, with a number nobody will take.
+#[derive(Debug)]
+pub struct Job {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Job {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Start measuring the directory at `path`, whose node's full path is `dir`,
+/// on a new thread
+pub fn start(dir: String, path: PathBuf, results: Sender<Measured>) -> Job {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let stop = cancelled.clone();
+    thread::spawn(move || {
+        let bytes = measure(&path, &stop);
+        let _ = results.send(Measured { dir, id, bytes });
+    });
+    Job { id, cancelled }
+}
+
+fn measure(path: &Path, stop: &AtomicBool) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    let mut bytes = 0;
+    for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        bytes += measure(&entry.path(), stop);
+    }
+    bytes
+}
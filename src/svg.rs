@@ -0,0 +1,187 @@
+//! Renders a drawn frame to a standalone SVG, so the view can be shared
+//! exactly as it looked on screen
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+
+const CELL_WIDTH: u32 = 9;
+const CELL_HEIGHT: u32 = 18;
+const FONT_SIZE: u32 = 15;
+/// What `Color::Reset` stands for, since the real terminal colors are unknown
+const DEFAULT_FG: &str = "#d4d4d4";
+const DEFAULT_BG: &str = "#1e1e1e";
+
+/// Cells on one row that share a style and are drawn as a single `<text>`
+struct Run {
+    column: u16,
+    width: u16,
+    text: String,
+    fg: Color,
+    bg: Color,
+    modifier: Modifier,
+}
+
+pub fn render(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"monospace\" font-size=\"{FONT_SIZE}\" xml:space=\"preserve\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{DEFAULT_BG}\"/>\n",
+        u32::from(area.width) * CELL_WIDTH,
+        u32::from(area.height) * CELL_HEIGHT,
+    );
+
+    for y in area.top()..area.bottom() {
+        let cells = (area.left()..area.right()).map(|x| (x - area.left(), buffer.get(x, y)));
+        for run in runs(cells) {
+            push_run(&mut svg, &run, y - area.top());
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Group a row's cells into runs. Anything that isn't ASCII or box drawing
+/// (emoji, mostly) gets a run of its own and is placed by column, so glyphs
+/// that the SVG font draws wider or narrower than the terminal don't shift
+/// the rest of the row.
+fn runs<'a>(cells: impl Iterator<Item = (u16, &'a Cell)>) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+
+    for (column, cell) in cells {
+        if cell.skip {
+            continue;
+        }
+        let (fg, bg) = if cell.modifier.contains(Modifier::REVERSED) {
+            (cell.bg, cell.fg)
+        } else {
+            (cell.fg, cell.bg)
+        };
+        let symbol = cell.symbol();
+
+        if let Some(last) = runs.last_mut() {
+            let same_style = last.fg == fg && last.bg == bg && last.modifier == cell.modifier;
+            if same_style && is_narrow(symbol) && is_narrow(&last.text) {
+                last.text.push_str(symbol);
+                last.width += 1;
+                continue;
+            }
+        }
+        runs.push(Run {
+            column,
+            width: 1,
+            text: symbol.to_string(),
+            fg,
+            bg,
+            modifier: cell.modifier,
+        });
+    }
+    runs
+}
+
+fn is_narrow(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii() || ('\u{2500}'..='\u{259f}').contains(&c))
+}
+
+fn push_run(svg: &mut String, run: &Run, row: u16) {
+    let x = u32::from(run.column) * CELL_WIDTH;
+    let y = u32::from(row) * CELL_HEIGHT;
+
+    if run.bg != Color::Reset {
+        svg.push_str(&format!(
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{}\" height=\"{CELL_HEIGHT}\" fill=\"{}\"/>\n",
+            u32::from(run.width) * CELL_WIDTH,
+            hex(run.bg, DEFAULT_BG),
+        ));
+    }
+    if run.text.trim().is_empty() {
+        return;
+    }
+
+    let mut attributes = format!(
+        "x=\"{x}\" y=\"{}\" fill=\"{}\"",
+        y + FONT_SIZE,
+        hex(run.fg, DEFAULT_FG)
+    );
+    if run.modifier.contains(Modifier::BOLD) {
+        attributes.push_str(" font-weight=\"bold\"");
+    }
+    if run.modifier.contains(Modifier::DIM) {
+        attributes.push_str(" opacity=\"0.6\"");
+    }
+    if run.modifier.contains(Modifier::ITALIC) {
+        attributes.push_str(" font-style=\"italic\"");
+    }
+    svg.push_str(&format!(
+        "<text {attributes}>{}</text>\n",
+        escape(&run.text)
+    ));
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// A typical dark terminal palette for the named and indexed colors
+fn hex(color: Color, default: &str) -> String {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0x00, 0x00, 0x00),
+        (0xcd, 0x31, 0x31),
+        (0x0d, 0xbc, 0x79),
+        (0xe5, 0xe5, 0x10),
+        (0x24, 0x72, 0xc8),
+        (0xbc, 0x3f, 0xbc),
+        (0x11, 0xa8, 0xcd),
+        (0xe5, 0xe5, 0xe5),
+        (0x66, 0x66, 0x66),
+        (0xf1, 0x4c, 0x4c),
+        (0x23, 0xd1, 0x8b),
+        (0xf5, 0xf5, 0x43),
+        (0x3b, 0x8e, 0xea),
+        (0xd6, 0x70, 0xd6),
+        (0x29, 0xb8, 0xdb),
+        (0xff, 0xff, 0xff),
+    ];
+
+    let index = match color {
+        Color::Reset => return default.to_string(),
+        Color::Rgb(r, g, b) => return format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(index) => index,
+    };
+
+    let (r, g, b) = match index {
+        0..=15 => ANSI[usize::from(index)],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = index - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    };
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
@@ -0,0 +1,143 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How many links to follow before giving up on a chain
+const MAX_CHAIN_LEN: usize = 40;
+
+/// How a chain of symlinks ends
+#[derive(Debug)]
+enum ChainEnd {
+    /// The last hop exists, with its canonical path if that differs
+    Resolved(Option<PathBuf>),
+    /// The last hop points at something that doesn't exist
+    Dangling,
+    /// A link points back at an earlier hop
+    Loop,
+    /// Gave up after `MAX_CHAIN_LEN` hops
+    TooLong,
+}
+
+/// Follow `path` one `read_link` at a time, returning every hop (starting
+/// with `path` itself) and how the chain ends
+fn chain(path: &Path) -> (Vec<PathBuf>, ChainEnd) {
+    let mut hops = vec![path.to_path_buf()];
+
+    while hops.len() <= MAX_CHAIN_LEN {
+        let current = hops.last().unwrap();
+        let Ok(target) = fs::read_link(current) else {
+            let end = match fs::canonicalize(current) {
+                Ok(real) if real != *current => ChainEnd::Resolved(Some(real)),
+                Ok(_) => ChainEnd::Resolved(None),
+                Err(_) => ChainEnd::Dangling,
+            };
+            return (hops, end);
+        };
+
+        let next = match current.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        };
+        if hops.contains(&next) {
+            hops.push(next);
+            return (hops, ChainEnd::Loop);
+        }
+        hops.push(next);
+    }
+
+    (hops, ChainEnd::TooLong)
+}
+
+/// Remove the link at `path` itself, never what it points to. Works for
+/// dangling links too.
+pub fn remove(path: &Path) -> io::Result<()> {
+    // Windows keeps links to directories as directory entries
+    #[cfg(windows)]
+    if fs::metadata(path).is_ok_and(|meta| meta.is_dir()) {
+        return fs::remove_dir(path);
+    }
+    fs::remove_file(path)
+}
+
+/// `a -> b -> /real/b`, with a note when the chain is broken
+pub fn describe_chain(path: &Path) -> String {
+    let (hops, end) = chain(path);
+    let mut description = hops
+        .iter()
+        .map(|hop| hop.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ");
+
+    match end {
+        ChainEnd::Resolved(Some(real)) => description.push_str(&format!(" -> {}", real.display())),
+        ChainEnd::Resolved(None) => {}
+        ChainEnd::Dangling => description.push_str(" (missing)"),
+        ChainEnd::Loop => description.push_str(" (loop)"),
+        ChainEnd::TooLong => description.push_str(" (too many links)"),
+    }
+    description
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn a_chain_is_followed_hop_by_hop() {
+        let dir = TestDir::new();
+        let file = dir.file("file", "");
+        let b = dir.symlink("file", "b");
+        let a = dir.symlink(&b, "a");
+        assert_eq!(
+            describe_chain(&a),
+            format!("{} -> {} -> {}", a.display(), b.display(), file.display())
+        );
+
+        // ending up somewhere spelled differently shows where that really is
+        let via = dir.symlink(dir.path(), "via");
+        let c = dir.symlink(via.join("file"), "c");
+        assert_eq!(
+            describe_chain(&c),
+            format!(
+                "{} -> {} -> {}",
+                c.display(),
+                via.join("file").display(),
+                file.display()
+            )
+        );
+    }
+
+    #[test]
+    fn broken_chains_say_how() {
+        let dir = TestDir::new();
+        let dangling = dir.symlink("missing", "dangling");
+        assert_eq!(
+            describe_chain(&dangling),
+            format!(
+                "{} -> {} (missing)",
+                dangling.display(),
+                dir.join("missing").display()
+            )
+        );
+
+        let a = dir.symlink("b", "a");
+        let b = dir.symlink("a", "b");
+        assert_eq!(
+            describe_chain(&a),
+            format!(
+                "{} -> {} -> {} (loop)",
+                a.display(),
+                b.display(),
+                a.display()
+            )
+        );
+
+        let mut next = dir.file("end", "");
+        for n in 0..=MAX_CHAIN_LEN {
+            next = dir.symlink(&next, format!("link{n}"));
+        }
+        assert!(describe_chain(&next).ends_with(" (too many links)"));
+    }
+}
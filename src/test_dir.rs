@@ -0,0 +1,99 @@
+//! Scratch directories for the tests to build trees in, removed again when
+//! dropped
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Tells apart the directories of tests running at the same time
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub struct TestDir {
+    /// Canonical, so that it compares equal to resolved paths
+    path: PathBuf,
+}
+
+impl TestDir {
+    pub fn new() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("irm-test-{}-{id}", process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self {
+            path: fs::canonicalize(&path).unwrap(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.path.join(relative)
+    }
+
+    /// Write a file at `relative`, creating the directories leading to it
+    pub fn file(&self, relative: impl AsRef<Path>, contents: &str) -> PathBuf {
+        let path = self.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Create a directory at `relative`, and any leading to it
+    pub fn dir(&self, relative: impl AsRef<Path>) -> PathBuf {
+        let path = self.join(relative);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    /// A symlink at `relative` pointing at `target`, as given
+    #[cfg(unix)]
+    pub fn symlink(&self, target: impl AsRef<Path>, relative: impl AsRef<Path>) -> PathBuf {
+        let path = self.join(relative);
+        std::os::unix::fs::symlink(target, &path).unwrap();
+        path
+    }
+
+    /// Make the directory at `relative` read-only, so nothing inside can be
+    /// added or removed. Returns whether that holds, which it doesn't for
+    /// root; `unlock` has to undo it before the directory can go.
+    #[cfg(unix)]
+    pub fn lock(&self, relative: impl AsRef<Path>) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = self.join(relative);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o555)).unwrap();
+        let probe = path.join(".probe");
+        let locked = fs::write(&probe, "").is_err();
+        let _ = fs::remove_file(probe);
+        locked
+    }
+
+    /// Make the directory at `relative` unreadable, so it can't be listed.
+    /// Returns whether that holds, like `lock`, and `unlock` undoes it too.
+    #[cfg(unix)]
+    pub fn seal(&self, relative: impl AsRef<Path>) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = self.join(relative);
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+        fs::read_dir(&path).is_err()
+    }
+
+    #[cfg(unix)]
+    pub fn unlock(&self, relative: impl AsRef<Path>) {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(self.join(relative), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
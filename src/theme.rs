@@ -0,0 +1,106 @@
+//! Colors for the rows of the tree, read from the `[theme]` table of the
+//! config file (see `config`):
+//!
+//! ```toml
+//! [theme]
+//! selected = "magenta"
+//! hovered = "#005f87"
+//! directory = "blue"
+//! ```
+//!
+//! Colors are names like `red` or `lightblue`, `#rrggbb`, or a 256-color
+//! index. Entries without a color use the terminal's own foreground.
+
+use ratatui::style::{Color, Style};
+
+use crate::DirType;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    selected: Option<Color>,
+    /// Directories with some, but not all, of what's in them selected
+    partial: Option<Color>,
+    /// Also applies on top of a selected row's color
+    hovered: Option<Color>,
+    directory: Option<Color>,
+    file: Option<Color>,
+    symlink: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            selected: Some(Color::Red),
+            partial: Some(Color::Yellow),
+            hovered: None,
+            directory: None,
+            file: None,
+            symlink: None,
+        }
+    }
+}
+
+impl Theme {
+    /// The theme from the config file's `[theme]` table, with defaults for
+    /// whatever it leaves out
+    pub fn from_config(config: &toml::Table) -> Result<Self, String> {
+        let mut theme = Self::default();
+        let Some(colors) = config.get("theme") else {
+            return Ok(theme);
+        };
+        let colors = colors.as_table().ok_or("`theme` must be a table")?;
+
+        for (name, value) in colors {
+            let slot = match name.as_str() {
+                "selected" => &mut theme.selected,
+                "partial" => &mut theme.partial,
+                "hovered" => &mut theme.hovered,
+                "directory" => &mut theme.directory,
+                "file" => &mut theme.file,
+                "symlink" => &mut theme.symlink,
+                _ => {
+                    return Err(format!(
+                        "unknown color '{name}' in [theme], expected one of: \
+                         selected, partial, hovered, directory, file, symlink"
+                    ))
+                }
+            };
+            let color = value
+                .as_str()
+                .ok_or(format!("the {name} color must be a string"))?;
+            *slot = Some(
+                color
+                    .parse()
+                    .map_err(|_| format!("'{color}' is not a color (in {name})"))?,
+            );
+        }
+        Ok(theme)
+    }
+
+    pub fn entry_style(&self, type_: &DirType) -> Style {
+        style(match type_ {
+            DirType::Dir => self.directory,
+            DirType::File => self.file,
+            DirType::Symlink => self.symlink,
+        })
+    }
+
+    pub fn selected_style(&self) -> Style {
+        style(self.selected)
+    }
+
+    pub fn partial_style(&self) -> Style {
+        style(self.partial)
+    }
+
+    pub fn hovered_style(&self) -> Style {
+        style(self.hovered)
+    }
+}
+
+fn style(color: Option<Color>) -> Style {
+    match color {
+        Some(color) => Style::default().fg(color),
+        None => Style::default(),
+    }
+}
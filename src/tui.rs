@@ -1,27 +1,76 @@
-use std::io::{self, stdout, Stdout};
+use std::{
+    io::{self, stderr, stdin, IsTerminal, Stderr},
+    panic,
+};
 
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
+        event::{DisableMouseCapture, EnableMouseCapture},
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
     Terminal,
 };
 
-/// A type alias for the terminal type used in this application
-pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+/// A type alias for the terminal type used in this application. It draws on
+/// stderr, leaving stdout for the removed paths printed on exit.
+pub type Tui = Terminal<CrosstermBackend<Stderr>>;
 
-/// Initialize the terminal
+/// Initialize the terminal. Fails without touching it when stdin or stderr
+/// isn't a terminal, since escape codes would only garble a pipe or file.
 pub fn init() -> io::Result<Tui> {
-    execute!(stdout(), EnterAlternateScreen)?;
+    if !stdin().is_terminal() || !stderr().is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "not attached to a terminal",
+        ));
+    }
+    install_panic_hook();
+    execute!(stderr(), EnterAlternateScreen, EnableMouseCapture)?;
     enable_raw_mode()?;
-    Terminal::new(CrosstermBackend::new(stdout()))
+    Terminal::new(CrosstermBackend::new(stderr()))
+}
+
+/// Put the terminal back before a panic's message is printed, so that it
+/// can be read and the shell is usable afterwards
+fn install_panic_hook() {
+    let print = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        print(info);
+    }));
 }
 
 /// Restore the terminal to its original state
 pub fn restore() -> io::Result<()> {
-    execute!(stdout(), LeaveAlternateScreen)?;
+    execute!(stderr(), DisableMouseCapture, LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
+
+/// Hand the terminal back to the user while `f` runs, then restore the TUI
+pub fn suspend<T>(terminal: &mut Tui, f: impl FnOnce() -> T) -> io::Result<T> {
+    restore()?;
+    let result = f();
+    execute!(stderr(), EnterAlternateScreen, EnableMouseCapture)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+    Ok(result)
+}
+
+/// Stop the process the way `Ctrl + Z` does outside raw mode, with the
+/// terminal handed back until the shell resumes it
+#[cfg(unix)]
+pub fn stop(terminal: &mut Tui) -> io::Result<()> {
+    // SAFETY: raise only signals this process; SIGTSTP's default action
+    // stops it until SIGCONT, after which raise returns
+    suspend(terminal, || unsafe { libc::raise(libc::SIGTSTP) })?;
+    Ok(())
+}
+
+/// Job control is a Unix thing, so elsewhere `Ctrl + Z` does nothing
+#[cfg(not(unix))]
+pub fn stop(_terminal: &mut Tui) -> io::Result<()> {
+    Ok(())
+}
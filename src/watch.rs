@@ -0,0 +1,87 @@
+//! Headless watch mode, driven by `--watch`: re-measures the root every
+//! `--interval` seconds and prints one summary line per pass, until
+//! interrupted.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::format;
+
+/// What a single pass over the tree found
+#[derive(Debug, Default)]
+struct Snapshot {
+    files: usize,
+    dirs: usize,
+    bytes: u64,
+    /// The biggest regular file, with its size
+    largest: Option<(PathBuf, u64)>,
+}
+
+impl Snapshot {
+    fn take(path: &Path) -> Self {
+        let mut snapshot = Self::default();
+        snapshot.visit(path);
+        snapshot
+    }
+
+    fn visit(&mut self, path: &Path) {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return;
+        };
+
+        if meta.is_dir() {
+            self.dirs += 1;
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    self.visit(&entry.path());
+                }
+            }
+            return;
+        }
+
+        self.files += 1;
+        self.bytes += meta.len();
+        let is_larger = self
+            .largest
+            .as_ref()
+            .is_none_or(|(_, size)| meta.len() > *size);
+        if meta.is_file() && is_larger {
+            self.largest = Some((path.to_path_buf(), meta.len()));
+        }
+    }
+}
+
+/// Print a summary of `root` every `interval` until killed, or until the
+/// reading end of a pipe goes away, which ends the watch quietly
+pub fn run(root: &Path, interval: Duration, out: &mut impl Write) -> io::Result<()> {
+    match watch(root, interval, out) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn watch(root: &Path, interval: Duration, out: &mut impl Write) -> io::Result<()> {
+    loop {
+        let snapshot = Snapshot::take(root);
+        let largest = match &snapshot.largest {
+            Some((path, size)) => format!("{} ({})", path.display(), format::size(*size)),
+            None => "none".to_string(),
+        };
+        writeln!(
+            out,
+            "{} {}: {} files, {} directories, {}, largest {largest}",
+            format::timestamp(SystemTime::now()),
+            root.display(),
+            format::count(snapshot.files as u64),
+            format::count(snapshot.dirs as u64),
+            format::size(snapshot.bytes),
+        )?;
+        out.flush()?;
+        thread::sleep(interval);
+    }
+}